@@ -0,0 +1,52 @@
+// Drag and drop for the kanban board, using native html drag events.
+// Cards are moved right away while dragging, dropping sends the final
+// column and position to the server, which answers with the whole board.
+(function () {
+  let dragged = null;
+
+  // The card the dragged one should be inserted before, based on the pointer height.
+  function cardBelow(cards, y) {
+    return [...cards.querySelectorAll("[data-task-id]")]
+      .filter((card) => card !== dragged)
+      .find((card) => {
+        const box = card.getBoundingClientRect();
+        return y < box.top + box.height / 2;
+      });
+  }
+
+  document.addEventListener("dragstart", (event) => {
+    const card = event.target.closest && event.target.closest("[data-task-id]");
+    if (!card) return;
+    dragged = card;
+    card.classList.add("dragging");
+    event.dataTransfer.effectAllowed = "move";
+    event.dataTransfer.setData("text/plain", card.dataset.taskId);
+  });
+
+  document.addEventListener("dragend", () => {
+    if (dragged) dragged.classList.remove("dragging");
+    dragged = null;
+  });
+
+  document.addEventListener("dragover", (event) => {
+    const column = dragged && event.target.closest("[data-column-id]");
+    if (!column) return;
+    event.preventDefault();
+    const cards = column.querySelector(".board-cards");
+    const below = cardBelow(cards, event.clientY);
+    if (below) cards.insertBefore(dragged, below);
+    else cards.appendChild(dragged);
+  });
+
+  document.addEventListener("drop", (event) => {
+    const column = dragged && event.target.closest("[data-column-id]");
+    if (!column) return;
+    event.preventDefault();
+    const position = [...column.querySelectorAll("[data-task-id]")].indexOf(dragged);
+    htmx.ajax("PUT", "/board/tasks/" + dragged.dataset.taskId, {
+      target: "#board",
+      swap: "outerHTML",
+      values: { column_id: column.dataset.columnId, position: position },
+    });
+  });
+})();
@@ -0,0 +1,142 @@
+// Minimal, dependency free explorer for the kanbasta OpenAPI document.
+// Lists every operation by tag and lets you send requests right from the page.
+(function () {
+  const root = document.getElementById("api-explorer");
+  const methods = ["get", "post", "put", "patch", "delete"];
+
+  function el(tag, attrs, children) {
+    const node = document.createElement(tag);
+    Object.entries(attrs || {}).forEach(([key, value]) => {
+      if (key === "text") node.textContent = value;
+      else node.setAttribute(key, value);
+    });
+    (children || []).forEach((child) => node.appendChild(child));
+    return node;
+  }
+
+  function resolve(spec, schema) {
+    if (schema && schema.$ref) {
+      const name = schema.$ref.split("/").pop();
+      return spec.components.schemas[name];
+    }
+    return schema;
+  }
+
+  // Builds an example value from a schema, so bodies can be edited instead of typed from scratch.
+  function example(spec, schema, depth) {
+    schema = resolve(spec, schema);
+    if (!schema || depth > 4) return null;
+    if (schema.oneOf) return example(spec, schema.oneOf[0], depth + 1);
+    const type = Array.isArray(schema.type) ? schema.type[0] : schema.type;
+    switch (type) {
+      case "object": {
+        const value = {};
+        Object.entries(schema.properties || {}).forEach(([key, property]) => {
+          value[key] = example(spec, property, depth + 1);
+        });
+        return value;
+      }
+      case "array":
+        return [example(spec, schema.items, depth + 1)];
+      case "integer":
+      case "number":
+        return 0;
+      case "boolean":
+        return false;
+      case "string":
+        return schema.format === "date-time" ? new Date().toISOString() : "";
+      default:
+        return null;
+    }
+  }
+
+  function operationView(spec, path, method, operation) {
+    const inputs = {};
+    const params = (operation.parameters || []).map((param) => {
+      const input = el("input", { type: "text", name: param.name, placeholder: param.name });
+      inputs[param.name] = param;
+      param.input = input;
+      return el("label", { text: `${param.name} (${param.in})` }, [input]);
+    });
+
+    let body = null;
+    const content = operation.requestBody && operation.requestBody.content["application/json"];
+    if (content) {
+      body = el("textarea", { rows: 5 });
+      body.value = JSON.stringify(example(spec, content.schema, 0), null, 2);
+    }
+
+    const output = el("pre", { class: "api-response" });
+    const send = el("button", { type: "button", text: "Send" });
+    send.addEventListener("click", async () => {
+      let url = path;
+      const query = new URLSearchParams();
+      Object.values(inputs).forEach((param) => {
+        const value = param.input.value.trim();
+        if (param.in === "path") url = url.replace(`{${param.name}}`, encodeURIComponent(value));
+        else if (value !== "") value.split(",").forEach((part) => query.append(param.name, part.trim()));
+      });
+      if ([...query].length > 0) url += `?${query}`;
+
+      const init = { method: method.toUpperCase(), headers: {} };
+      if (body) {
+        init.headers["content-type"] = "application/json";
+        init.body = body.value;
+      }
+      output.textContent = "...";
+      const response = await fetch(url, init);
+      const text = await response.text();
+      let pretty = text;
+      try {
+        pretty = JSON.stringify(JSON.parse(text), null, 2);
+      } catch (_) {}
+      output.textContent = `${response.status} ${response.statusText}\n${pretty}`;
+    });
+
+    const responses = el(
+      "ul",
+      {},
+      Object.entries(operation.responses || {}).map(([status, response]) =>
+        el("li", { text: `${status}: ${response.description}` })
+      )
+    );
+
+    const summary = el("summary", {}, [
+      el("span", { class: `api-method api-method-${method}`, text: method.toUpperCase() }),
+      el("code", { text: path }),
+      el("small", { text: ` ${operation.operationId || ""}` }),
+    ]);
+
+    const children = [summary];
+    if (operation.description) children.push(el("p", { text: operation.description }));
+    children.push(...params);
+    if (body) children.push(el("label", { text: "Body" }, [body]));
+    children.push(responses, send, output);
+    return el("details", { class: "api-operation" }, children);
+  }
+
+  function render(spec) {
+    const byTag = {};
+    Object.entries(spec.paths).forEach(([path, item]) => {
+      methods
+        .filter((method) => item[method])
+        .forEach((method) => {
+          const tag = (item[method].tags || ["default"])[0];
+          (byTag[tag] = byTag[tag] || []).push(operationView(spec, path, method, item[method]));
+        });
+    });
+
+    root.replaceChildren(
+      ...Object.entries(byTag).map(([tag, operations]) =>
+        el("section", {}, [el("h2", { text: tag }), ...operations])
+      )
+    );
+  }
+
+  fetch(root.dataset.openapi)
+    .then((response) => response.json())
+    .then(render)
+    .catch((error) => {
+      root.textContent = `Failed to load the api description: ${error}`;
+    });
+})();
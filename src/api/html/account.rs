@@ -0,0 +1,175 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/account";
+
+/// The account page itself, needs a logged in user.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::index))
+        .route("/password", routing::post(self::post::change_password))
+}
+
+/// Login, registration and logout, reachable without a session.
+pub fn login_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            crate::auth::LOGIN_PATH,
+            routing::get(self::get::login).post(self::post::login),
+        )
+        .route(
+            "/register",
+            routing::get(self::get::register).post(self::post::register),
+        )
+        .route("/logout", routing::post(self::post::logout))
+}
+
+async fn account_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    message: Option<String>,
+    error: Option<String>,
+) -> Result<crate::viewmodels::account::AccountIndex, crate::error::ApiError> {
+    Ok(crate::viewmodels::account::AccountIndex {
+        user: crate::models::user::get_by_id(pool, user_id).await?,
+        sessions: crate::models::session::count_for(pool, user_id).await?,
+        message,
+        error,
+    })
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Query, State};
+
+    use crate::{
+        api::types::user::LoginQuery,
+        auth::CurrentUser,
+        error::ApiError,
+        viewmodels::account::{LoginIndex, RegisterIndex},
+    };
+
+    pub(super) async fn index(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::account_view(&pool, user.id, None, None).await
+    }
+
+    pub(super) async fn login(Query(query): Query<LoginQuery>) -> impl IntoResponse {
+        LoginIndex {
+            next: query.next.unwrap_or_default(),
+            name: String::new(),
+            error: None,
+        }
+    }
+
+    pub(super) async fn register() -> impl IntoResponse {
+        RegisterIndex {
+            name: String::new(),
+            email: String::new(),
+            error: None,
+        }
+    }
+}
+
+mod post {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::State,
+        http::StatusCode,
+        response::{Redirect, Response},
+        Form,
+    };
+    use axum_extra::extract::CookieJar;
+
+    use crate::{
+        api::types::user::{ChangePassword, LoginUser, RegisterUser},
+        auth::{self, CurrentUser, Sessions},
+        error::ApiError,
+        models,
+        viewmodels::account::{LoginIndex, RegisterIndex},
+    };
+
+    pub(super) async fn login(
+        State(pool): State<sqlx::SqlitePool>,
+        State(sessions): State<Sessions>,
+        jar: CookieJar,
+        Form(payload): Form<LoginUser>,
+    ) -> Result<Response, ApiError> {
+        let Some(user) =
+            models::user::authenticate(&pool, &payload.name, &payload.password).await?
+        else {
+            return Ok((
+                StatusCode::UNAUTHORIZED,
+                LoginIndex {
+                    next: payload.next.unwrap_or_default(),
+                    name: payload.name,
+                    error: Some("Unknown name or wrong password".to_string()),
+                },
+            )
+                .into_response());
+        };
+
+        let jar = sessions.start(&pool, jar, user.id).await?;
+        let next = auth::local_path(payload.next.as_deref()).to_string();
+        Ok((jar, Redirect::to(&next)).into_response())
+    }
+
+    pub(super) async fn register(
+        State(pool): State<sqlx::SqlitePool>,
+        State(sessions): State<Sessions>,
+        jar: CookieJar,
+        Form(payload): Form<RegisterUser>,
+    ) -> Result<Response, ApiError> {
+        let id = match models::user::register(&pool, &payload).await {
+            Ok(id) => id,
+            Err(ApiError::Validation(error)) => {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    RegisterIndex {
+                        name: payload.name,
+                        email: payload.email,
+                        error: Some(error),
+                    },
+                )
+                    .into_response())
+            }
+            Err(e) => return Err(e),
+        };
+
+        let jar = sessions.start(&pool, jar, id).await?;
+        Ok((jar, Redirect::to(auth::local_path(None))).into_response())
+    }
+
+    pub(super) async fn logout(
+        State(pool): State<sqlx::SqlitePool>,
+        State(sessions): State<Sessions>,
+        jar: CookieJar,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let jar = sessions.end(&pool, jar).await?;
+        Ok((jar, Redirect::to(auth::LOGIN_PATH)))
+    }
+
+    pub(super) async fn change_password(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        jar: CookieJar,
+        Form(payload): Form<ChangePassword>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        match models::user::change_password(&pool, user.id, &payload).await {
+            Ok(()) => {
+                if let Some(token) = auth::token(&jar) {
+                    models::session::delete_others(&pool, user.id, token).await?;
+                }
+                super::account_view(&pool, user.id, Some("Password changed".to_string()), None)
+                    .await
+            }
+            Err(ApiError::Validation(error)) => {
+                super::account_view(&pool, user.id, None, Some(error)).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/activity";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::index))
+        .route("/entries", routing::get(self::get::entries))
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::extract::State;
+    use axum_extra::extract::Query;
+
+    use crate::{
+        api::types::audit::ActivityQuery, auth::CurrentUser, error::ApiError, models, viewmodels,
+    };
+
+    pub(super) async fn index(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let authors = models::audit::get_authors(&pool, user.id).await?;
+        Ok(viewmodels::activity::ActivityIndex { authors })
+    }
+
+    /// A page of entries, older pages are appended below it.
+    pub(super) async fn entries(
+        CurrentUser(user): CurrentUser,
+        Query(query): Query<ActivityQuery>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let entries = models::audit::get_activity(&pool, user.id, &query).await?;
+        Ok(viewmodels::activity::ActivityEntries {
+            more: models::audit::is_full_page(&entries),
+            entries,
+        })
+    }
+}
@@ -0,0 +1,84 @@
+use axum::{routing, Router};
+
+use crate::{
+    api::types::user::User, backup::BackupService, error::ApiError, state::AppState, viewmodels,
+};
+
+pub(crate) const NEST_PREFIX: &str = "/backups";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            routing::get(self::get::index).post(self::post::backup_now),
+        )
+        .route("/status", routing::get(self::get::status))
+        .route("/:file_name/restore", routing::post(self::post::restore))
+}
+
+async fn backups_view(
+    service: &BackupService,
+    user: &User,
+) -> Result<viewmodels::backups::Backups, ApiError> {
+    Ok(viewmodels::backups::Backups {
+        dir: service.dir().to_string_lossy().to_string(),
+        generations: service.generations(),
+        interval_hours: service.interval().map(|i| i.as_secs() / 3600),
+        status: service.status().await,
+        files: service.list().await?,
+        can_restore: service.may_restore(user),
+    })
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::extract::State;
+
+    use crate::{auth::CurrentUser, backup::BackupService, error::ApiError, viewmodels};
+
+    pub(super) async fn index() -> impl IntoResponse {
+        viewmodels::backups::BackupIndex
+    }
+
+    pub(super) async fn status(
+        CurrentUser(user): CurrentUser,
+        State(service): State<BackupService>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::backups_view(&service, &user).await
+    }
+}
+
+mod post {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{
+        auth::CurrentUser, backup::BackupService, database::Database, error::ApiError, restore,
+        viewmodels,
+    };
+
+    pub(super) async fn backup_now(
+        CurrentUser(user): CurrentUser,
+        State(service): State<BackupService>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        // A failed run is recorded in the status and shown in the view
+        let _ = service.backup_now(&pool).await;
+        super::backups_view(&service, &user).await
+    }
+
+    pub(super) async fn restore(
+        CurrentUser(user): CurrentUser,
+        Path(file_name): Path<String>,
+        State(db): State<Database>,
+        State(service): State<BackupService>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        if !service.may_restore(&user) {
+            return Err(ApiError::Forbidden(
+                "Only admins can restore backups".to_string(),
+            ));
+        }
+        let report = restore::restore(&db, &service, &file_name).await?;
+        Ok(viewmodels::backups::Restored { report })
+    }
+}
@@ -0,0 +1,222 @@
+use axum::{routing, Router};
+
+use crate::{error::ApiError, models, state::AppState, viewmodels::board::Board};
+
+pub(crate) const NEST_PREFIX: &str = "/board";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::index))
+        .route("/flow", routing::get(self::get::flow))
+        .route(
+            "/columns",
+            routing::get(self::get::columns).post(self::post::create_column),
+        )
+        .route(
+            "/columns/:id",
+            routing::put(self::put::update_column)
+                .patch(self::patch::move_column)
+                .delete(self::delete::delete_column),
+        )
+        .route("/tasks/:id", routing::put(self::put::move_task))
+}
+
+/// Renders the whole board of a project after a change. Validation errors, like deleting
+/// a column that still holds tasks, are shown above the board instead of failing the request.
+async fn board_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    project_id: i64,
+    result: Result<(), ApiError>,
+) -> Result<Board, ApiError> {
+    let error = match result {
+        Ok(()) => None,
+        Err(ApiError::Validation(message)) => Some(message),
+        Err(e) => return Err(e),
+    };
+    let project = models::project::get_by_id(pool, user_id, project_id).await?;
+    let columns = models::board::get_board(pool, user_id, project_id).await?;
+    Ok(Board {
+        project,
+        columns,
+        error,
+    })
+}
+
+/// Board path of a project, served by the project router.
+pub(crate) fn board_path(project_id: i64) -> String {
+    format!(
+        "{}/{project_id}/board",
+        crate::api::html::project::NEST_PREFIX
+    )
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::{extract::State, response::Redirect};
+    use axum_extra::extract::Query;
+
+    use crate::{
+        api::types::board::{BoardQuery, FlowQuery},
+        auth::CurrentUser,
+        error::ApiError,
+        models,
+        viewmodels::board::FlowReport,
+    };
+
+    /// Opens the board of the default project.
+    pub(super) async fn index(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let project_id = models::project::default_for(&pool, user.id).await?;
+        Ok(Redirect::to(&super::board_path(project_id)))
+    }
+
+    pub(super) async fn columns(
+        CurrentUser(user): CurrentUser,
+        Query(query): Query<BoardQuery>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let project = models::project::resolve(&pool, user.id, query.project).await?;
+        super::board_view(&pool, user.id, project.id, Ok(())).await
+    }
+
+    /// Lead time, cycle time and cumulative flow of a project's board.
+    pub(super) async fn flow(
+        CurrentUser(user): CurrentUser,
+        Query(query): Query<FlowQuery>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let metrics = models::task::get_flow(&pool, user.id, &query).await?;
+        let project = models::project::get_by_id(&pool, user.id, metrics.project_id).await?;
+        Ok(FlowReport { project, metrics })
+    }
+}
+
+mod post {
+    use askama_axum::IntoResponse;
+    use axum::{extract::State, Form};
+
+    use crate::{api::types::board::CreateColumn, auth::CurrentUser, error::ApiError, models};
+
+    pub(super) async fn create_column(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<CreateColumn>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let project = models::project::resolve(&pool, user.id, payload.project_id).await?;
+        let result = models::board::create_column(&pool, user.id, &payload)
+            .await
+            .map(|_| ());
+        super::board_view(&pool, user.id, project.id, result).await
+    }
+}
+
+mod put {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Path, State},
+        Form,
+    };
+
+    use crate::{
+        api::types::board::{MoveTask, UpdateColumn, UpdateColumnRaw},
+        auth::CurrentUser,
+        error::ApiError,
+        models,
+        undo::{UndoHistory, Undoable},
+    };
+
+    /// Undoing puts back the column together with the done state of its tasks.
+    pub(super) async fn update_column(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<UpdateColumnRaw>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let column = models::board::get_column(&pool, user.id, id).await?;
+        let mark = undo.mark(&pool).await?;
+        let result = match UpdateColumn::try_from(payload) {
+            Ok(payload) => models::board::update_column(&pool, user.id, id, &payload).await,
+            Err(message) => Err(ApiError::Validation(message)),
+        };
+        let project_id = column.project_id;
+        let token = undo.push_column(&pool, user.id, mark, column).await?;
+        Ok(Undoable(
+            super::board_view(&pool, user.id, project_id, result).await?,
+            token,
+        ))
+    }
+
+    /// Target of drag and drop on the board, see `assets/board.js`.
+    pub(super) async fn move_task(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<MoveTask>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let task = models::task::get_header(&pool, user.id, id).await?;
+        let project_id = task.project_id.ok_or(sqlx::Error::RowNotFound)?;
+        let mark = undo.mark(&pool).await?;
+        let result = models::board::move_task(&pool, user.id, id, &payload).await;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            super::board_view(&pool, user.id, project_id, result).await?,
+            token,
+        ))
+    }
+}
+
+mod patch {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Path, State},
+        Form,
+    };
+
+    use crate::{
+        api::types::board::UpdateColumn,
+        auth::CurrentUser,
+        error::ApiError,
+        models,
+        undo::{UndoHistory, Undoable},
+    };
+
+    pub(super) async fn move_column(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<UpdateColumn>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let column = models::board::get_column(&pool, user.id, id).await?;
+        let mark = undo.mark(&pool).await?;
+        let result = models::board::update_column(&pool, user.id, id, &payload).await;
+        let project_id = column.project_id;
+        let token = undo.push_column(&pool, user.id, mark, column).await?;
+        Ok(Undoable(
+            super::board_view(&pool, user.id, project_id, result).await?,
+            token,
+        ))
+    }
+}
+
+mod delete {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{auth::CurrentUser, error::ApiError, models};
+
+    pub(super) async fn delete_column(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let column = models::board::get_column(&pool, user.id, id).await?;
+        let result = models::board::delete_column(&pool, user.id, id).await;
+        super::board_view(&pool, user.id, column.project_id, result).await
+    }
+}
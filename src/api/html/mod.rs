@@ -1,2 +1,11 @@
+pub mod account;
+pub mod activity;
+pub mod backup;
+pub mod board;
+pub mod project;
+pub mod tag;
 pub mod task;
+pub mod timesheet;
 pub mod todo;
+pub mod trash;
+pub mod undo;
@@ -0,0 +1,159 @@
+use askama_axum::IntoResponse;
+use axum::{extract::State, routing, Router};
+
+use crate::{auth::CurrentUser, models, state::AppState, viewmodels::projects::ProjectSwitcher};
+
+pub(crate) const NEST_PREFIX: &str = "/projects";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::index).post(self::post::create))
+        .route("/all", routing::get(self::get::all))
+        .route("/switcher", routing::get(switcher))
+        .route(
+            "/:id",
+            routing::get(self::get::by_index)
+                .patch(self::patch::update)
+                .delete(self::delete::delete),
+        )
+        .route("/:id/edit", routing::get(self::get::edit))
+        .route("/:id/tasks", routing::get(self::get::tasks))
+        .route("/:id/board", routing::get(self::get::board))
+}
+
+/// Project links for the navigation of pages behind the login.
+async fn switcher(
+    CurrentUser(user): CurrentUser,
+    State(pool): State<sqlx::SqlitePool>,
+) -> Result<impl IntoResponse, crate::error::ApiError> {
+    let projects = models::project::get_all(&pool, user.id)
+        .await?
+        .into_iter()
+        .filter(|project| !project.archived)
+        .collect();
+    Ok(ProjectSwitcher { projects })
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{auth::CurrentUser, error::ApiError, models, viewmodels};
+
+    pub(super) async fn index() -> impl IntoResponse {
+        viewmodels::projects::ProjectIndex
+    }
+
+    pub(super) async fn all(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        // Makes sure there is at least the default project to show
+        models::project::default_for(&pool, user.id).await?;
+        let projects = models::project::get_all(&pool, user.id).await?;
+        Ok(viewmodels::projects::Projects { projects })
+    }
+
+    pub(super) async fn by_index(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::projects::ProjectModel { project })
+    }
+
+    pub(super) async fn edit(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::projects::ProjectEdit { project })
+    }
+
+    pub(super) async fn tasks(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        let tags = models::tag::get_all(&pool, user.id).await?;
+        Ok(viewmodels::tasks::TaskIndex {
+            tags,
+            project: Some(project),
+        })
+    }
+
+    pub(super) async fn board(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::board::BoardIndex { project })
+    }
+}
+
+mod post {
+    use askama_axum::IntoResponse;
+    use axum::{extract::State, Form};
+
+    use crate::{
+        api::types::project::CreateProject, auth::CurrentUser, error::ApiError, models, viewmodels,
+    };
+
+    pub(super) async fn create(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<CreateProject>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let id = models::project::create(&pool, user.id, &payload).await?;
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::projects::ProjectModel { project })
+    }
+}
+
+mod patch {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Path, State},
+        Form,
+    };
+
+    use crate::{
+        api::types::project::UpdateProject, auth::CurrentUser, error::ApiError, models, viewmodels,
+    };
+
+    /// Renames, archives and unarchives a project.
+    pub(super) async fn update(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<UpdateProject>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::project::update(&pool, user.id, id, &payload).await?;
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::projects::ProjectModel { project })
+    }
+}
+
+mod delete {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+
+    use crate::{auth::CurrentUser, error::ApiError, models};
+
+    pub(super) async fn delete(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::project::delete(&pool, user.id, id)
+            .await
+            .map(|_| StatusCode::OK)
+    }
+}
@@ -0,0 +1,158 @@
+use axum::{routing, Router};
+
+use crate::{
+    error::ApiError,
+    models::{self, tag::TagOwner},
+    state::AppState,
+    viewmodels::tags::{PickerTag, TagPicker},
+};
+
+pub(crate) const NEST_PREFIX: &str = "/tags";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::index).post(self::post::create))
+        .route("/all", routing::get(self::get::all))
+        .route(
+            "/:id",
+            routing::get(self::get::by_index)
+                .delete(self::delete::delete)
+                .patch(self::patch::update),
+        )
+        .route("/:id/edit", routing::get(self::get::edit))
+}
+
+/// Tag toggles for a single todo or task, shared by their routers.
+pub(crate) async fn picker(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    owner: TagOwner,
+    owner_id: i64,
+) -> Result<TagPicker, ApiError> {
+    owner.require_read(pool, user_id, owner_id).await?;
+    let assigned = models::tag::get_for(pool, owner, owner_id).await?;
+    let mut tags: Vec<PickerTag> = models::tag::get_all(pool, user_id)
+        .await?
+        .into_iter()
+        .map(|tag| PickerTag {
+            assigned: assigned.iter().any(|a| a.id == tag.id),
+            tag,
+        })
+        .collect();
+    // Tags of other users on a shared item can still be taken off
+    for tag in assigned {
+        if !tags.iter().any(|picked| picked.tag.id == tag.id) {
+            tags.push(PickerTag {
+                assigned: true,
+                tag,
+            });
+        }
+    }
+    tags.sort_by(|a, b| a.tag.name.cmp(&b.tag.name));
+
+    Ok(TagPicker {
+        prefix: owner.prefix(),
+        owner_id,
+        tags,
+    })
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{auth::CurrentUser, error::ApiError, models, viewmodels};
+
+    pub(super) async fn index() -> impl IntoResponse {
+        viewmodels::tags::TagIndex
+    }
+
+    pub(super) async fn all(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let tags = models::tag::get_all(&pool, user.id).await?;
+        Ok(viewmodels::tags::Tags { tags })
+    }
+
+    pub(super) async fn by_index(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let tag = models::tag::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::tags::TagModel { tag })
+    }
+
+    pub(super) async fn edit(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let tag = models::tag::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::tags::TagEdit { tag })
+    }
+}
+
+mod post {
+    use askama_axum::IntoResponse;
+    use axum::{extract::State, Form};
+
+    use crate::{
+        api::types::tag::CreateTag, auth::CurrentUser, error::ApiError, models, viewmodels,
+    };
+
+    pub(super) async fn create(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<CreateTag>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let id = models::tag::create(&pool, user.id, &payload).await?;
+        let tag = models::tag::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::tags::TagModel { tag })
+    }
+}
+
+mod patch {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Path, State},
+        Form,
+    };
+
+    use crate::{
+        api::types::tag::UpdateTag, auth::CurrentUser, error::ApiError, models, viewmodels,
+    };
+
+    pub(super) async fn update(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<UpdateTag>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::tag::update(&pool, user.id, id, &payload).await?;
+        let tag = models::tag::get_by_id(&pool, user.id, id).await?;
+        Ok(viewmodels::tags::TagModel { tag })
+    }
+}
+
+mod delete {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+
+    use crate::{auth::CurrentUser, error::ApiError, models};
+
+    pub(super) async fn delete(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::tag::delete(&pool, user.id, id)
+            .await
+            .map(|_| StatusCode::OK)
+            .map_err(|e| e.into())
+    }
+}
@@ -1,70 +1,811 @@
 use askama_axum::IntoResponse;
-use axum::{routing, Router};
+use axum::{extract::DefaultBodyLimit, routing, Router};
+
+use crate::{attachments::AttachmentStore, state::AppState};
 
 pub(crate) const NEST_PREFIX: &str = "/tasks";
 
-pub fn router(state: sqlx::SqlitePool) -> Router<sqlx::SqlitePool> {
+/// Sent along with every change to the todos linked to a task,
+/// so open todo lists of that task can refresh themselves.
+pub(crate) const TODOS_CHANGED_EVENT: &str = "task-todos-changed";
+
+/// Sent when a task is gone, so task lists and boards reload themselves.
+/// Closing a task dialog triggers it as well.
+pub(crate) const TASKS_CHANGED_EVENT: &str = "tasks-changed";
+
+async fn todos_changed(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<impl IntoResponse, crate::error::ApiError> {
+    let todos = crate::models::task::get_todos(pool, user_id, id).await?;
+    Ok((
+        axum_htmx::HxResponseTrigger::normal([TODOS_CHANGED_EVENT]),
+        crate::viewmodels::tasks::TaskTodos { task_id: id, todos },
+    ))
+}
+
+async fn shares_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    error: Option<String>,
+) -> Result<crate::viewmodels::tasks::TaskShares, crate::error::ApiError> {
+    let shares = crate::models::task::get_shares(pool, user_id, id).await?;
+    Ok(crate::viewmodels::tasks::TaskShares {
+        task_id: id,
+        shares,
+        error,
+    })
+}
+
+async fn subtasks_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    error: Option<String>,
+) -> Result<crate::viewmodels::tasks::TaskSubtasks, crate::error::ApiError> {
+    let access = crate::models::task::access(pool, user_id, id).await?;
+    let subtasks = crate::models::task::get_subtasks(pool, user_id, id).await?;
+    Ok(crate::viewmodels::tasks::TaskSubtasks {
+        task_id: id,
+        subtasks,
+        can_add: access.is_owner(),
+        error,
+    })
+}
+
+async fn dependencies_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    error: Option<String>,
+) -> Result<crate::viewmodels::tasks::TaskDependencies, crate::error::ApiError> {
+    let access = crate::models::task::access(pool, user_id, id).await?;
+    let candidates = match access.can_write() {
+        true => crate::models::task::get_blocker_candidates(pool, user_id, id).await?,
+        false => Vec::new(),
+    };
+    Ok(crate::viewmodels::tasks::TaskDependencies {
+        task_id: id,
+        blockers: crate::models::task::get_blockers(pool, user_id, id).await?,
+        dependents: crate::models::task::get_dependents(pool, user_id, id).await?,
+        candidates,
+        can_write: access.can_write(),
+        error,
+    })
+}
+
+async fn time_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    error: Option<String>,
+) -> Result<crate::viewmodels::tasks::TaskTime, crate::error::ApiError> {
+    let access = crate::models::task::access(pool, user_id, id).await?;
+    Ok(crate::viewmodels::tasks::TaskTime {
+        task_id: id,
+        user_id,
+        entries: crate::models::tracking::get_for_task(pool, user_id, id).await?,
+        running: crate::models::tracking::get_running(pool, user_id).await?,
+        can_track: access.can_write(),
+        today: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+        error,
+    })
+}
+
+async fn comments_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    editing: Option<i64>,
+    error: Option<String>,
+) -> Result<crate::viewmodels::tasks::TaskComments, crate::error::ApiError> {
+    let access = crate::models::task::access(pool, user_id, id).await?;
+    Ok(crate::viewmodels::tasks::TaskComments {
+        task_id: id,
+        user_id,
+        comments: crate::models::comment::get_for_task(pool, user_id, id).await?,
+        is_owner: access.is_owner(),
+        editing,
+        error,
+    })
+}
+
+async fn attachments_view(
+    pool: &sqlx::SqlitePool,
+    store: &AttachmentStore,
+    user_id: i64,
+    id: i64,
+    error: Option<String>,
+) -> Result<crate::viewmodels::tasks::TaskAttachments, crate::error::ApiError> {
+    let access = crate::models::task::access(pool, user_id, id).await?;
+    Ok(crate::viewmodels::tasks::TaskAttachments {
+        task_id: id,
+        attachments: crate::models::attachment::get_for_task(pool, user_id, id).await?,
+        can_write: access.can_write(),
+        max_mb: store.max_bytes() / (1024 * 1024),
+        error,
+    })
+}
+
+pub fn router() -> Router<AppState> {
     Router::new()
-        .with_state(state)
-        .route("/", routing::get(self::get::index))
+        .route("/", routing::get(self::get::index).post(self::post::create))
         .route("/all", routing::get(self::get::headers))
+        .route(
+            "/:id",
+            routing::get(self::get::by_index)
+                .put(self::put::update)
+                .delete(self::delete::delete),
+        )
         .route("/:id/edit", routing::get(self::get::edit))
         .route("/:id/details", routing::get(self::get::details))
+        .route(
+            "/:id/todos",
+            routing::get(self::get::todos).post(self::post::create_todo),
+        )
+        .route("/:id/todos/list", routing::get(self::get::todo_list))
+        .route("/:id/todos/search", routing::get(self::get::todo_search))
+        .route(
+            "/:id/todos/:todo_id",
+            routing::post(self::post::add_todo).delete(self::delete::remove_todo),
+        )
+        .route(
+            "/:id/shares",
+            routing::get(self::get::shares).post(self::post::share),
+        )
+        .route(
+            "/:id/shares/:user_id",
+            routing::delete(self::delete::unshare),
+        )
+        .route(
+            "/:id/subtasks",
+            routing::get(self::get::subtasks).post(self::post::create_subtask),
+        )
+        .route("/:id/dependencies", routing::get(self::get::dependencies))
+        .route("/:id/blockers", routing::post(self::post::add_blocker))
+        .route(
+            "/:id/blockers/:blocker_id",
+            routing::delete(self::delete::remove_blocker),
+        )
+        .route(
+            "/:id/time",
+            routing::get(self::get::time).post(self::post::track),
+        )
+        .route(
+            "/:id/time/:entry_id",
+            routing::delete(self::delete::untrack),
+        )
+        .route(
+            "/:id/timer",
+            routing::post(self::post::start_timer).delete(self::delete::stop_timer),
+        )
+        // Uploads are limited per file while they are written, see `AttachmentStore`
+        .route(
+            "/:id/attachments",
+            routing::get(self::get::attachments)
+                .post(self::post::upload)
+                .layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/:id/attachments/:attachment_id",
+            routing::get(self::get::download).delete(self::delete::delete_attachment),
+        )
+        .route(
+            "/:id/comments",
+            routing::get(self::get::comments).post(self::post::comment),
+        )
+        .route(
+            "/:id/comments/:comment_id",
+            routing::put(self::put::update_comment).delete(self::delete::delete_comment),
+        )
+        .route("/:id/history", routing::get(self::get::history))
+        .route("/:id/tags", routing::get(self::get::tags))
+        .route(
+            "/:id/tags/:tag_id",
+            routing::post(self::post::add_tag).delete(self::delete::remove_tag),
+        )
 }
 
 mod get {
     use askama_axum::IntoResponse;
-    use axum::extract::{Path, Query, State};
+    use axum::extract::{Path, State};
+    use axum_extra::extract::Query;
 
-    use crate::{error::ApiError, models, viewmodels};
+    use crate::{
+        api::types::{comment::CommentQuery, task::TaskQuery, todo::TodoSearch},
+        attachments::{self, AttachmentStore},
+        auth::CurrentUser,
+        error::ApiError,
+        models::{self, tag::TagOwner},
+        viewmodels,
+    };
 
-    pub(super) async fn index() -> impl IntoResponse {
-        viewmodels::tasks::TaskIndex
+    pub(super) async fn index(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let tags = models::tag::get_all(&pool, user.id).await?;
+        Ok(viewmodels::tasks::TaskIndex {
+            tags,
+            project: None,
+        })
     }
 
     pub(super) async fn headers(
+        CurrentUser(user): CurrentUser,
+        Query(query): Query<TaskQuery>,
         State(pool): State<sqlx::SqlitePool>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let headers = models::task::get_all_headers(&pool).await?;
+        let headers = models::task::get_headers_matching(&pool, user.id, &query).await?;
         Ok(viewmodels::tasks::Tasks { tasks: headers })
     }
 
+    pub(super) async fn by_index(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let task_header = models::task::get_header(&pool, user.id, id).await?;
+        Ok(viewmodels::tasks::TaskHeaderModel {
+            task_header,
+            depth: 0,
+        })
+    }
+
     pub(super) async fn edit(
+        CurrentUser(user): CurrentUser,
         Path(id): Path<i64>,
         State(pool): State<sqlx::SqlitePool>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let task = models::task::get_by_id(&pool, id).await?;
+        let task = models::task::get_by_id(&pool, user.id, id).await?;
+        let (projects, parents) = match task.access.is_owner() {
+            true => (
+                models::project::get_all(&pool, user.id).await?,
+                models::task::get_parent_candidates(&pool, user.id, id).await?,
+            ),
+            false => (Vec::new(), Vec::new()),
+        };
 
-        Ok(viewmodels::tasks::TaskEdit { task })
+        Ok(viewmodels::tasks::TaskEdit {
+            task,
+            projects,
+            parents,
+        })
     }
 
     pub(super) async fn details(
+        CurrentUser(user): CurrentUser,
         Path(id): Path<i64>,
         State(pool): State<sqlx::SqlitePool>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let task = models::task::get_by_id(&pool, id).await?;
+        let task = models::task::get_by_id(&pool, user.id, id).await?;
 
         Ok(viewmodels::tasks::TaskDetails { task })
     }
+
+    pub(super) async fn todos(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let todos = models::task::get_todos(&pool, user.id, id).await?;
+        Ok(viewmodels::tasks::TaskTodos { task_id: id, todos })
+    }
+
+    pub(super) async fn todo_list(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let todos = models::task::get_todos(&pool, user.id, id).await?;
+        Ok(viewmodels::tasks::TaskTodoList { task_id: id, todos })
+    }
+
+    pub(super) async fn todo_search(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        Query(search): Query<TodoSearch>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let todos = match search.q.trim() {
+            "" => Vec::new(),
+            text => models::todo::search_unlinked(&pool, user.id, id, text).await?,
+        };
+        Ok(viewmodels::tasks::TaskTodoSearch { task_id: id, todos })
+    }
+
+    pub(super) async fn tags(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        crate::api::html::tag::picker(&pool, user.id, TagOwner::Task, id).await
+    }
+
+    pub(super) async fn shares(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::shares_view(&pool, user.id, id, None).await
+    }
+
+    pub(super) async fn subtasks(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::subtasks_view(&pool, user.id, id, None).await
+    }
+
+    pub(super) async fn dependencies(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::dependencies_view(&pool, user.id, id, None).await
+    }
+
+    pub(super) async fn time(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::time_view(&pool, user.id, id, None).await
+    }
+
+    pub(super) async fn attachments(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::attachments_view(&pool, &store, user.id, id, None).await
+    }
+
+    pub(super) async fn download(
+        CurrentUser(user): CurrentUser,
+        Path((id, attachment_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let (attachment, content) =
+            models::attachment::read(&pool, &store, user.id, id, attachment_id).await?;
+        Ok(attachments::download(&attachment, content))
+    }
+
+    /// Loading the thread marks its comments as read.
+    pub(super) async fn comments(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        Query(query): Query<CommentQuery>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::comments_view(&pool, user.id, id, query.edit, None).await
+    }
+
+    pub(super) async fn history(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let entries = models::audit::get_for_task(&pool, user.id, id).await?;
+        Ok(viewmodels::tasks::TaskHistory {
+            task_id: id,
+            entries,
+        })
+    }
 }
 
 mod post {
     use askama_axum::IntoResponse;
     use axum::{
-        extract::{Path, Query, State},
+        extract::{Multipart, Path, State},
         Form,
     };
 
-    use crate::{api::types::task::CreateTask, error::ApiError, models, viewmodels};
+    use crate::{
+        api::types::{
+            comment::CreateComment,
+            task::{AddBlocker, CreateSubtask, CreateTask, ShareTask},
+            todo::CreateTodo,
+            tracking::{CreateTimeEntry, CreateTimeEntryRaw},
+        },
+        attachments::{AttachmentError, AttachmentStore},
+        auth::CurrentUser,
+        error::ApiError,
+        models::{self, tag::TagOwner},
+        undo::{UndoHistory, Undoable},
+        viewmodels,
+    };
 
     pub(super) async fn create(
+        CurrentUser(user): CurrentUser,
         State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
         Form(payload): Form<CreateTask>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let id = models::task::create_task(&pool, &payload).await?;
-        let task = models::task::get_by_id(&pool, id).await?;
+        let mark = undo.mark(&pool).await?;
+        let id = models::task::create_task(&pool, user.id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let task_header = models::task::get_header(&pool, user.id, id).await?;
+        Ok(Undoable(
+            viewmodels::tasks::TaskHeaderModel {
+                task_header,
+                depth: 0,
+            },
+            token,
+        ))
+    }
+
+    pub(super) async fn create_todo(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<CreateTodo>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::create_todo(&pool, user.id, id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            super::todos_changed(&pool, user.id, id).await?,
+            token,
+        ))
+    }
+
+    pub(super) async fn add_todo(
+        CurrentUser(user): CurrentUser,
+        Path((id, todo_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::add_todo(&pool, user.id, id, todo_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            super::todos_changed(&pool, user.id, id).await?,
+            token,
+        ))
+    }
 
-        // TODO: There is no proper viewmodel to return here yet...
-        Ok(viewmodels::tasks::TaskIndex)
+    pub(super) async fn add_tag(
+        CurrentUser(user): CurrentUser,
+        Path((id, tag_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::tag::add(&pool, user.id, TagOwner::Task, id, tag_id).await?;
+        crate::api::html::tag::picker(&pool, user.id, TagOwner::Task, id).await
+    }
+
+    /// Validation errors are shown inside the subtask form, open task lists reload.
+    pub(super) async fn create_subtask(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<CreateSubtask>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let payload = CreateTask {
+            name: payload.name,
+            project_id: None,
+            priority: Default::default(),
+            due: None,
+            recurrence: None,
+            parent_id: Some(id),
+        };
+        let mark = undo.mark(&pool).await?;
+        let error = match models::task::create_task(&pool, user.id, &payload).await {
+            Ok(_) => None,
+            Err(ApiError::Validation(message)) => Some(message),
+            Err(e) => return Err(e),
+        };
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            (
+                axum_htmx::HxResponseTrigger::normal([super::TASKS_CHANGED_EVENT]),
+                super::subtasks_view(&pool, user.id, id, error).await?,
+            ),
+            token,
+        ))
+    }
+
+    /// Validation errors, like a cycle, are shown inside the blocker form.
+    pub(super) async fn add_blocker(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<AddBlocker>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        let error = match models::task::add_blocker(&pool, user.id, id, payload.blocker_id).await {
+            Ok(()) => None,
+            Err(ApiError::Validation(message)) => Some(message),
+            Err(e) => return Err(e),
+        };
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            (
+                axum_htmx::HxResponseTrigger::normal([super::TASKS_CHANGED_EVENT]),
+                super::dependencies_view(&pool, user.id, id, error).await?,
+            ),
+            token,
+        ))
+    }
+
+    /// Validation errors, like a missing duration, are shown inside the time entry form.
+    pub(super) async fn track(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<CreateTimeEntryRaw>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let created = match CreateTimeEntry::try_from(payload) {
+            Ok(payload) => models::tracking::create(&pool, user.id, id, &payload).await,
+            Err(message) => Err(ApiError::Validation(message)),
+        };
+        let error = match created {
+            Ok(_) => None,
+            Err(ApiError::Validation(message)) => Some(message),
+            Err(e) => return Err(e),
+        };
+        super::time_view(&pool, user.id, id, error).await
+    }
+
+    /// Rejected files, like ones over the size limit, are reported inside the upload form.
+    pub(super) async fn upload(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+        mut multipart: Multipart,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let error =
+            match models::attachment::upload(&pool, &store, user.id, id, &mut multipart).await {
+                Ok(_) => None,
+                Err(ApiError::Validation(message)) => Some(message),
+                Err(
+                    e @ ApiError::Attachment(
+                        AttachmentError::TooLarge { .. } | AttachmentError::Upload(_),
+                    ),
+                ) => Some(e.message()),
+                Err(e) => return Err(e),
+            };
+        super::attachments_view(&pool, &store, user.id, id, error).await
+    }
+
+    /// Validation errors, like an empty comment, are shown inside the comment form.
+    pub(super) async fn comment(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<CreateComment>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let error = match models::comment::create(&pool, user.id, id, &payload).await {
+            Ok(_) => None,
+            Err(ApiError::Validation(message)) => Some(message),
+            Err(e) => return Err(e),
+        };
+        super::comments_view(&pool, user.id, id, None, error).await
+    }
+
+    /// Stops the timer running on any other task.
+    pub(super) async fn start_timer(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::tracking::start(&pool, user.id, id).await?;
+        super::time_view(&pool, user.id, id, None).await
+    }
+
+    /// Validation errors, like an unknown user name, are shown inside the sharing form.
+    pub(super) async fn share(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<ShareTask>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        let error = match models::task::share(&pool, user.id, id, &payload).await {
+            Ok(()) => None,
+            Err(ApiError::Validation(message)) => Some(message),
+            Err(e) => return Err(e),
+        };
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            super::shares_view(&pool, user.id, id, error).await?,
+            token,
+        ))
+    }
+}
+
+mod put {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Path, State},
+        Form,
+    };
+
+    use crate::{
+        api::types::{
+            comment::UpdateComment,
+            task::{UpdateTask, UpdateTaskRaw},
+        },
+        auth::CurrentUser,
+        error::ApiError,
+        models,
+        undo::{UndoHistory, Undoable},
+        viewmodels,
+    };
+
+    pub(super) async fn update(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        Form(payload): Form<UpdateTaskRaw>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::update(
+            &pool,
+            user.id,
+            id,
+            &UpdateTask::try_from(payload).map_err(ApiError::Validation)?,
+        )
+        .await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let task = models::task::get_by_id(&pool, user.id, id).await?;
+        Ok(Undoable(viewmodels::tasks::TaskDetails { task }, token))
+    }
+
+    /// Validation errors keep the comment in edit mode.
+    pub(super) async fn update_comment(
+        CurrentUser(user): CurrentUser,
+        Path((id, comment_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        Form(payload): Form<UpdateComment>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let (editing, error) =
+            match models::comment::update(&pool, user.id, id, comment_id, &payload).await {
+                Ok(()) => (None, None),
+                Err(ApiError::Validation(message)) => (Some(comment_id), Some(message)),
+                Err(e) => return Err(e),
+            };
+        super::comments_view(&pool, user.id, id, editing, error).await
+    }
+}
+
+mod delete {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{
+        attachments::AttachmentStore,
+        auth::CurrentUser,
+        error::ApiError,
+        models::{self, tag::TagOwner},
+        undo::{UndoHistory, Undoable},
+    };
+
+    /// The dialog removes itself, open task lists and boards reload on the event.
+    pub(super) async fn delete(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::delete(&pool, user.id, id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            (
+                axum_htmx::HxResponseTrigger::normal([super::TASKS_CHANGED_EVENT]),
+                "",
+            ),
+            token,
+        ))
+    }
+
+    pub(super) async fn remove_blocker(
+        CurrentUser(user): CurrentUser,
+        Path((id, blocker_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::remove_blocker(&pool, user.id, id, blocker_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            (
+                axum_htmx::HxResponseTrigger::normal([super::TASKS_CHANGED_EVENT]),
+                super::dependencies_view(&pool, user.id, id, None).await?,
+            ),
+            token,
+        ))
+    }
+
+    pub(super) async fn stop_timer(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let error = match models::tracking::stop(&pool, user.id, id).await {
+            Ok(()) => None,
+            Err(ApiError::Validation(message)) => Some(message),
+            Err(e) => return Err(e),
+        };
+        super::time_view(&pool, user.id, id, error).await
+    }
+
+    pub(super) async fn untrack(
+        CurrentUser(user): CurrentUser,
+        Path((id, entry_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::tracking::delete(&pool, user.id, id, entry_id).await?;
+        super::time_view(&pool, user.id, id, None).await
+    }
+
+    pub(super) async fn delete_attachment(
+        CurrentUser(user): CurrentUser,
+        Path((id, attachment_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::attachment::delete(&pool, &store, user.id, id, attachment_id).await?;
+        super::attachments_view(&pool, &store, user.id, id, None).await
+    }
+
+    pub(super) async fn delete_comment(
+        CurrentUser(user): CurrentUser,
+        Path((id, comment_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::comment::delete(&pool, user.id, id, comment_id).await?;
+        super::comments_view(&pool, user.id, id, None, None).await
+    }
+
+    pub(super) async fn remove_todo(
+        CurrentUser(user): CurrentUser,
+        Path((id, todo_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::remove_todo(&pool, user.id, id, todo_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            super::todos_changed(&pool, user.id, id).await?,
+            token,
+        ))
+    }
+
+    pub(super) async fn remove_tag(
+        CurrentUser(user): CurrentUser,
+        Path((id, tag_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::tag::remove(&pool, user.id, TagOwner::Task, id, tag_id).await?;
+        crate::api::html::tag::picker(&pool, user.id, TagOwner::Task, id).await
+    }
+
+    pub(super) async fn unshare(
+        CurrentUser(user): CurrentUser,
+        Path((id, share_user_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::unshare(&pool, user.id, id, share_user_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(
+            super::shares_view(&pool, user.id, id, None).await?,
+            token,
+        ))
     }
 }
@@ -0,0 +1,55 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/timesheet";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::index))
+        .route("/csv", routing::get(self::get::csv))
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::{
+        extract::{Query, State},
+        http::header,
+    };
+
+    use crate::{
+        api::types::tracking::TimesheetQuery, auth::CurrentUser, error::ApiError, models,
+        viewmodels,
+    };
+
+    pub(super) async fn index(
+        CurrentUser(user): CurrentUser,
+        Query(query): Query<TimesheetQuery>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let timesheet = models::tracking::get_timesheet(&pool, user.id, &query).await?;
+        Ok(viewmodels::timesheet::TimesheetIndex { timesheet })
+    }
+
+    /// Downloads the week as `timesheet-<monday>.csv`.
+    pub(super) async fn csv(
+        CurrentUser(user): CurrentUser,
+        Query(query): Query<TimesheetQuery>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let timesheet = models::tracking::get_timesheet(&pool, user.id, &query).await?;
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"timesheet-{}.csv\"",
+                        timesheet.week.format("%Y-%m-%d")
+                    ),
+                ),
+            ],
+            models::tracking::to_csv(&timesheet),
+        ))
+    }
+}
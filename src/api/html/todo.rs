@@ -1,43 +1,62 @@
 use axum::routing;
 use axum::Router;
 
+use crate::state::AppState;
+
 pub const NEST_PREFIX: &str = "/todos";
 
-pub fn router(state: &sqlx::SqlitePool) -> Router<sqlx::SqlitePool> {
+/// Sent when todos changed outside of the todo list, e.g. by an undo, so it reloads itself.
+pub(crate) const TODOS_CHANGED_EVENT: &str = "todos-changed";
+
+pub fn router() -> Router<AppState> {
     Router::new()
-        .with_state(state.clone())
         .route("/", routing::get(self::get::index).post(self::post::create))
         .route("/find", routing::get(self::get::by_query))
         .route("/all", routing::get(self::get::all))
         .route(
             "/:id",
-            routing::get(self::get::all)
+            routing::get(self::get::by_index)
                 .delete(self::delete::delete)
                 .patch(self::patch::update),
         )
         .route("/:id/edit", routing::get(self::get::edit))
         .route("/:id/toggle", routing::put(self::put::toggle_state))
+        .route("/:id/tags", routing::get(self::get::tags))
+        .route(
+            "/:id/tags/:tag_id",
+            routing::post(self::post::add_tag).delete(self::delete::remove_tag),
+        )
 }
 
 mod get {
     use askama_axum::IntoResponse;
-    use axum::extract::{Path, Query, State};
+    use axum::extract::{Path, State};
+    use axum_extra::extract::Query;
 
     use crate::{
         api::types::todo::TodoQuery,
+        auth::CurrentUser,
         error::ApiError,
-        models,
+        models::{self, tag::TagOwner},
         viewmodels::{self, todos::TodoIndex},
     };
 
-    pub async fn index() -> impl IntoResponse {
-        TodoIndex {
+    pub async fn index(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let tags = models::tag::get_all(&pool, user.id).await?;
+        Ok(TodoIndex {
             filter: "all".to_string(),
-        }
+            tags,
+        })
     }
 
-    pub async fn all(State(pool): State<sqlx::SqlitePool>) -> Result<impl IntoResponse, ApiError> {
-        let todos = models::todo::get_all(&pool).await?;
+    pub async fn all(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let todos = models::todo::get_all(&pool, user.id).await?;
         Ok(viewmodels::todos::TodoListModel {
             filter: "all".to_string(),
             todos,
@@ -45,10 +64,11 @@ mod get {
     }
 
     pub async fn by_query(
+        CurrentUser(user): CurrentUser,
         Query(query): Query<TodoQuery>,
         State(pool): State<sqlx::SqlitePool>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let todos = models::todo::get_all_matching(&pool, &query).await?;
+        let todos = models::todo::get_all_matching(&pool, user.id, &query).await?;
         let filter = match query.done {
             Some(true) => "completed",
             Some(false) => "active",
@@ -62,36 +82,69 @@ mod get {
     }
 
     pub async fn by_index(
+        CurrentUser(user): CurrentUser,
         Path(id): Path<i64>,
         State(pool): State<sqlx::SqlitePool>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let todo = models::todo::get_by_id(&pool, id).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
         Ok(viewmodels::todos::TodoModel { todo })
     }
 
     pub async fn edit(
+        CurrentUser(user): CurrentUser,
         Path(id): Path<i64>,
         State(pool): State<sqlx::SqlitePool>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let todo = models::todo::get_by_id(&pool, id).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
 
         Ok(viewmodels::todos::EditTodoModel { todo })
     }
+
+    pub async fn tags(
+        CurrentUser(user): CurrentUser,
+        Path(id): Path<i64>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        crate::api::html::tag::picker(&pool, user.id, TagOwner::Todo, id).await
+    }
 }
 
 mod post {
     use askama_axum::IntoResponse;
-    use axum::{extract::State, http::StatusCode, Form};
+    use axum::{
+        extract::{Path, State},
+        Form,
+    };
 
-    use crate::{api::types::todo::CreateTodo, error::ApiError, models, viewmodels};
+    use crate::{
+        api::types::todo::CreateTodo,
+        auth::CurrentUser,
+        error::ApiError,
+        models::{self, tag::TagOwner},
+        undo::{UndoHistory, Undoable},
+        viewmodels,
+    };
 
     pub async fn create(
+        CurrentUser(user): CurrentUser,
         State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
         Form(payload): Form<CreateTodo>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let id = models::todo::create(&pool, &payload).await?;
-        let todo = models::todo::get_by_id(&pool, id).await?;
-        Ok(viewmodels::todos::TodoModel { todo })
+        let mark = undo.mark(&pool).await?;
+        let id = models::todo::create(&pool, user.id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
+        Ok(Undoable(viewmodels::todos::TodoModel { todo }, token))
+    }
+
+    pub async fn add_tag(
+        CurrentUser(user): CurrentUser,
+        Path((id, tag_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::tag::add(&pool, user.id, TagOwner::Todo, id, tag_id).await?;
+        crate::api::html::tag::picker(&pool, user.id, TagOwner::Todo, id).await
     }
 }
 
@@ -99,15 +152,25 @@ mod put {
     use askama_axum::IntoResponse;
     use axum::extract::{Path, State};
 
-    use crate::{error::ApiError, models, viewmodels};
+    use crate::{
+        auth::CurrentUser,
+        error::ApiError,
+        models,
+        undo::{UndoHistory, Undoable},
+        viewmodels,
+    };
 
     pub async fn toggle_state(
+        CurrentUser(user): CurrentUser,
         Path(id): Path<i64>,
         State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let _ = models::todo::toggle_state(&pool, id).await?;
-        let todo = models::todo::get_by_id(&pool, id).await?;
-        Ok(viewmodels::todos::TodoModel { todo })
+        let mark = undo.mark(&pool).await?;
+        models::todo::toggle_state(&pool, user.id, id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
+        Ok(Undoable(viewmodels::todos::TodoModel { todo }, token))
     }
 }
 
@@ -118,16 +181,33 @@ mod delete {
         http::StatusCode,
     };
 
-    use crate::{error::ApiError, models};
+    use crate::{
+        auth::CurrentUser,
+        error::ApiError,
+        models::{self, tag::TagOwner},
+        undo::{UndoHistory, Undoable},
+    };
 
+    /// The row removes itself, the response offers to undo the deletion.
     pub async fn delete(
+        CurrentUser(user): CurrentUser,
         Path(id): Path<i64>,
         State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let mark = undo.mark(&pool).await?;
+        models::todo::delete(&pool, user.id, id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Undoable(StatusCode::OK, token))
+    }
+
+    pub async fn remove_tag(
+        CurrentUser(user): CurrentUser,
+        Path((id, tag_id)): Path<(i64, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
     ) -> Result<impl IntoResponse, ApiError> {
-        models::todo::delete(&pool, id)
-            .await
-            .map(|_| StatusCode::OK)
-            .map_err(|e| e.into())
+        models::tag::remove(&pool, user.id, TagOwner::Todo, id, tag_id).await?;
+        crate::api::html::tag::picker(&pool, user.id, TagOwner::Todo, id).await
     }
 }
 
@@ -139,18 +219,25 @@ mod patch {
     };
 
     use crate::{
-        api::types::todo::{UpdateTodo, UpdateTodoRaw},
+        api::types::todo::UpdateTodoRaw,
+        auth::CurrentUser,
         error::ApiError,
-        models, viewmodels,
+        models,
+        undo::{UndoHistory, Undoable},
+        viewmodels,
     };
 
     pub async fn update(
+        CurrentUser(user): CurrentUser,
         Path(id): Path<i64>,
         State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
         Form(payload): Form<UpdateTodoRaw>,
     ) -> Result<impl IntoResponse, ApiError> {
-        let _ = models::todo::update(&pool, id, &payload.into()).await?;
-        let todo = models::todo::get_by_id(&pool, id).await?;
-        Ok(viewmodels::todos::TodoModel { todo })
+        let mark = undo.mark(&pool).await?;
+        models::todo::update(&pool, user.id, id, &payload.into()).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
+        Ok(Undoable(viewmodels::todos::TodoModel { todo }, token))
     }
 }
@@ -0,0 +1,99 @@
+use axum::{routing, Router};
+
+use crate::{error::ApiError, models, state::AppState, trash::TrashService, viewmodels};
+
+pub(crate) const NEST_PREFIX: &str = "/trash";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::index))
+        .route(
+            "/items",
+            routing::get(self::get::items).delete(self::delete::empty),
+        )
+        .route("/:kind/:id", routing::delete(self::delete::purge))
+        .route("/:kind/:id/restore", routing::post(self::post::restore))
+}
+
+async fn trash_view(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    service: &TrashService,
+) -> Result<viewmodels::trash::TrashItems, ApiError> {
+    Ok(viewmodels::trash::TrashItems {
+        items: models::trash::get_all(pool, user_id).await?,
+        retention_days: service.retention_days(),
+    })
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::extract::State;
+
+    use crate::{auth::CurrentUser, error::ApiError, trash::TrashService, viewmodels};
+
+    pub(super) async fn index() -> impl IntoResponse {
+        viewmodels::trash::TrashIndex
+    }
+
+    pub(super) async fn items(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        State(service): State<TrashService>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        super::trash_view(&pool, user.id, &service).await
+    }
+}
+
+mod post {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{
+        api::types::trash::TrashKind, auth::CurrentUser, error::ApiError, models,
+        trash::TrashService,
+    };
+
+    pub(super) async fn restore(
+        CurrentUser(user): CurrentUser,
+        Path((kind, id)): Path<(TrashKind, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(service): State<TrashService>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::trash::restore(&pool, user.id, kind, id).await?;
+        super::trash_view(&pool, user.id, &service).await
+    }
+}
+
+mod delete {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{
+        api::types::trash::TrashKind, attachments::AttachmentStore, auth::CurrentUser,
+        error::ApiError, models, trash::TrashService,
+    };
+
+    pub(super) async fn purge(
+        CurrentUser(user): CurrentUser,
+        Path((kind, id)): Path<(TrashKind, i64)>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(service): State<TrashService>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::trash::purge(&pool, user.id, kind, id).await?;
+        store.clean_up(&pool).await;
+        super::trash_view(&pool, user.id, &service).await
+    }
+
+    pub(super) async fn empty(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        State(service): State<TrashService>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        models::trash::empty(&pool, user.id).await?;
+        store.clean_up(&pool).await;
+        super::trash_view(&pool, user.id, &service).await
+    }
+}
@@ -0,0 +1,65 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/undo";
+
+pub fn router() -> Router<AppState> {
+    Router::new().route(
+        "/:token",
+        routing::get(self::get::toast).post(self::post::undo),
+    )
+}
+
+mod get {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{auth::CurrentUser, undo::UndoHistory, viewmodels};
+
+    /// Offers to undo the step `token`, fetched when a response announced it.
+    pub(super) async fn toast(
+        CurrentUser(user): CurrentUser,
+        Path(token): Path<String>,
+        State(undo): State<UndoHistory>,
+    ) -> impl IntoResponse {
+        let label = undo.label(user.id, &token);
+        viewmodels::undo::UndoToast {
+            step: label.map(|label| (token, label)),
+            message: None,
+        }
+    }
+}
+
+mod post {
+    use askama_axum::IntoResponse;
+    use axum::extract::{Path, State};
+
+    use crate::{auth::CurrentUser, error::ApiError, undo::UndoHistory, viewmodels};
+
+    /// Everything showing todos or tasks reloads, errors like a conflicting change
+    /// are shown in the toast.
+    pub(super) async fn undo(
+        CurrentUser(user): CurrentUser,
+        Path(token): Path<String>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let message = match undo.undo(&pool, user.id, &token).await {
+            Ok(label) => format!("Undone: {label}"),
+            Err(ApiError::Validation(message) | ApiError::Forbidden(message)) => message,
+            Err(e) => return Err(e),
+        };
+        Ok((
+            axum_htmx::HxResponseTrigger::normal([
+                super::super::todo::TODOS_CHANGED_EVENT,
+                super::super::task::TODOS_CHANGED_EVENT,
+                super::super::task::TASKS_CHANGED_EVENT,
+            ]),
+            viewmodels::undo::UndoToast {
+                step: None,
+                message: Some(message),
+            },
+        ))
+    }
+}
@@ -0,0 +1,48 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/activity";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(get::page))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", routing::get(self::get::page))
+}
+
+mod get {
+    use axum::{extract::State, Json};
+    use axum_extra::extract::{Query, WithRejection};
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::audit::{ActivityQuery, AuditEntry},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "get_activity",
+        tag = "activity",
+        params(ActivityQuery),
+        responses(
+            (status = 200, description = "Up to 50 changes to todos and tasks you can see, newest first", body = [AuditEntry]),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn page(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Query(query), _): WithRejection<Query<ActivityQuery>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<AuditEntry>>, JsonError> {
+        let entries = models::audit::get_activity(&pool, user.id, &query).await?;
+        Ok(Json(entries))
+    }
+}
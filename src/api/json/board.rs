@@ -0,0 +1,280 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/board";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(
+    get::board,
+    get::columns,
+    get::flow,
+    post::create_column,
+    put::move_task,
+    patch::update_column,
+    delete::delete_column
+))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::board))
+        .route("/flow", routing::get(self::get::flow))
+        .route(
+            "/columns",
+            routing::get(self::get::columns).post(self::post::create_column),
+        )
+        .route(
+            "/columns/:id",
+            routing::patch(self::patch::update_column).delete(self::delete::delete_column),
+        )
+        .route("/tasks/:id", routing::put(self::put::move_task))
+}
+
+mod get {
+    use axum::{extract::State, Json};
+    use axum_extra::extract::{Query, WithRejection};
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::board::{BoardColumn, BoardQuery, Column, FlowMetrics, FlowQuery},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "get_board",
+        tag = "board",
+        params(BoardQuery),
+        responses(
+            (status = 200, description = "Columns of the board with the tasks in them, in board order", body = [BoardColumn]),
+            (status = 404, description = "Project not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn board(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Query(query), _): WithRejection<Query<BoardQuery>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<BoardColumn>>, JsonError> {
+        let project = models::project::resolve(&pool, user.id, query.project).await?;
+        let board = models::board::get_board(&pool, user.id, project.id).await?;
+        Ok(Json(board))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/columns",
+        operation_id = "list_columns",
+        tag = "board",
+        params(BoardQuery),
+        responses(
+            (status = 200, description = "Columns of the board, in board order", body = [Column]),
+            (status = 404, description = "Project not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn columns(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Query(query), _): WithRejection<Query<BoardQuery>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<Column>>, JsonError> {
+        let project = models::project::resolve(&pool, user.id, query.project).await?;
+        let columns = models::board::get_columns(&pool, user.id, project.id).await?;
+        Ok(Json(columns))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/flow",
+        operation_id = "get_flow",
+        tag = "board",
+        params(FlowQuery),
+        responses(
+            (status = 200, description = "Lead time, cycle time and cumulative flow of the board", body = FlowMetrics),
+            (status = 400, description = "Invalid date range", body = ErrorBody),
+            (status = 404, description = "Project not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn flow(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Query(query), _): WithRejection<Query<FlowQuery>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<FlowMetrics>, JsonError> {
+        let metrics = models::task::get_flow(&pool, user.id, &query).await?;
+        Ok(Json(metrics))
+    }
+}
+
+mod post {
+    use axum::{extract::State, http::StatusCode, Json};
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::board::{Column, CreateColumn},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        post,
+        path = "/columns",
+        operation_id = "create_column",
+        tag = "board",
+        request_body = CreateColumn,
+        responses(
+            (status = 201, description = "The created column, placed after the existing ones", body = Column),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn create_column(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateColumn>, JsonError>,
+    ) -> Result<(StatusCode, Json<Column>), JsonError> {
+        let id = models::board::create_column(&pool, user.id, &payload).await?;
+        let column = models::board::get_column(&pool, user.id, id).await?;
+        Ok((StatusCode::CREATED, Json(column)))
+    }
+}
+
+mod put {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{board::MoveTask, task::TaskModel, undo::WithUndo},
+        },
+        auth::CurrentUser,
+        models,
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        put,
+        path = "/tasks/{id}",
+        operation_id = "move_task",
+        tag = "board",
+        params(("id" = i64, Path, description = "Task id")),
+        request_body = MoveTask,
+        responses(
+            (status = 200, description = "The moved task, it takes over the done state of the column", body = UndoableTask),
+            (status = 400, description = "Column is not on the board of the task's project or blocks on its WIP limit", body = ErrorBody),
+            (status = 403, description = "No write access to the task", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn move_task(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<MoveTask>, JsonError>,
+    ) -> Result<Json<WithUndo<TaskModel>>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::board::move_task(&pool, user.id, id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let task = models::task::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(WithUndo {
+            item: task,
+            undo: token,
+        }))
+    }
+}
+
+mod patch {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{
+                board::{Column, UpdateColumn},
+                undo::WithUndo,
+            },
+        },
+        auth::CurrentUser,
+        models,
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        patch,
+        path = "/columns/{id}",
+        operation_id = "update_column",
+        tag = "board",
+        params(("id" = i64, Path, description = "Column id")),
+        request_body = UpdateColumn,
+        responses(
+            (status = 200, description = "The updated column, undoing it also reverts the done state of its tasks", body = UndoableColumn),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 404, description = "Column not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn update_column(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<UpdateColumn>, JsonError>,
+    ) -> Result<Json<WithUndo<Column>>, JsonError> {
+        let before = models::board::get_column(&pool, user.id, id).await?;
+        let mark = undo.mark(&pool).await?;
+        models::board::update_column(&pool, user.id, id, &payload).await?;
+        let token = undo.push_column(&pool, user.id, mark, before).await?;
+        let column = models::board::get_column(&pool, user.id, id).await?;
+        Ok(Json(WithUndo {
+            item: column,
+            undo: token,
+        }))
+    }
+}
+
+mod delete {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{api::json::error::JsonError, auth::CurrentUser, models};
+
+    #[utoipa::path(
+        delete,
+        path = "/columns/{id}",
+        operation_id = "delete_column",
+        tag = "board",
+        params(("id" = i64, Path, description = "Column id")),
+        responses(
+            (status = 204, description = "Column deleted"),
+            (status = 400, description = "Column still holds tasks or is the last one", body = ErrorBody),
+            (status = 404, description = "Column not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn delete_column(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::board::delete_column(&pool, user.id, id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
@@ -0,0 +1,65 @@
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::error::ApiError;
+
+/// Error body of every json endpoint.
+#[derive(Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ErrorBody {
+    pub status: u16,
+    pub message: String,
+}
+
+/// Turns failing handlers and rejected extractors into a json [`ErrorBody`],
+/// instead of the plain text errors of the html api.
+pub(crate) enum JsonError {
+    Api(ApiError),
+    Rejection(StatusCode, String),
+}
+
+impl<E: Into<ApiError>> From<E> for JsonError {
+    fn from(e: E) -> Self {
+        Self::Api(e.into())
+    }
+}
+
+impl From<JsonRejection> for JsonError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::Rejection(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for JsonError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::Rejection(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<axum_extra::extract::QueryRejection> for JsonError {
+    fn from(rejection: axum_extra::extract::QueryRejection) -> Self {
+        Self::Rejection(StatusCode::BAD_REQUEST, rejection.to_string())
+    }
+}
+
+impl IntoResponse for JsonError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::Api(e) => (e.status(), e.message()),
+            Self::Rejection(status, message) => (status, message),
+        };
+
+        if status.is_server_error() {
+            tracing::error!("{}: {}", status, message);
+        }
+
+        let body = ErrorBody {
+            status: status.as_u16(),
+            message,
+        };
+        (status, Json(body)).into_response()
+    }
+}
@@ -1 +1,188 @@
+//! Versioned JSON api, built on the same `models` as the html routes.
+//! Every error is answered with a [`error::JsonError`] body.
 
+use axum::{extract::State, http::StatusCode, Json, Router};
+use axum_extra::extract::CookieJar;
+use utoipa::OpenApi;
+
+use crate::{api::types, state::AppState};
+
+pub mod activity;
+pub mod board;
+pub mod error;
+pub mod project;
+pub mod tag;
+pub mod task;
+pub mod timesheet;
+pub mod todo;
+pub mod trash;
+pub mod undo;
+
+pub(crate) const NEST_PREFIX: &str = "/api/v1";
+/// Served outside of [`NEST_PREFIX`], the document describes every api version.
+pub(crate) const OPENAPI_PATH: &str = "/api/openapi.json";
+pub(crate) const EXPLORER_PATH: &str = "/api/explorer";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "kanbasta", description = "Todos, tasks and tags of a kanbasta instance"),
+    components(schemas(
+        types::todo::TodoItem,
+        types::todo::CreateTodo,
+        types::todo::UpdateTodo,
+        types::task::TaskHeader,
+        types::task::TaskModel,
+        types::task::CreateTask,
+        types::task::UpdateTask,
+        types::task::Access,
+        types::task::Priority,
+        types::task::SortBy,
+        types::task::DueFilter,
+        types::task::TodoProgress,
+        types::task::TaskShare,
+        types::task::TaskShares,
+        types::recurrence::Recurrence,
+        types::recurrence::Weekday,
+        types::task::ShareTask,
+        types::comment::Comment,
+        types::attachment::Attachment,
+        types::attachment::UploadAttachments,
+        types::comment::CreateComment,
+        types::comment::UpdateComment,
+        types::tracking::TimeEntry,
+        types::tracking::CreateTimeEntry,
+        types::tracking::Timesheet,
+        types::tracking::TimesheetRow,
+        types::board::Column,
+        types::board::BoardColumn,
+        types::board::CreateColumn,
+        types::board::UpdateColumn,
+        types::board::MoveTask,
+        types::board::FlowMetrics,
+        types::board::FlowDay,
+        types::board::TaskFlow,
+        types::project::Project,
+        types::project::CreateProject,
+        types::project::UpdateProject,
+        types::tag::Tag,
+        types::tag::CreateTag,
+        types::tag::UpdateTag,
+        types::trash::TrashItem,
+        types::trash::TrashKind,
+        types::audit::AuditEntry,
+        types::audit::AuditEntity,
+        types::audit::AuditAction,
+        types::undo::UndoableTodo,
+        types::undo::UndoableTask,
+        types::undo::UndoableColumn,
+        types::undo::UndoToken,
+        types::undo::UndoneStep,
+        error::ErrorBody,
+    )),
+    tags(
+        (name = "todos"),
+        (name = "tasks", description = "Tasks and the todos linked to them"),
+        (name = "tags"),
+        (name = "board", description = "Status columns and moving tasks between them"),
+        (name = "projects", description = "Projects owning tasks and their board"),
+        (name = "timesheet", description = "Time tracked on tasks, per week"),
+        (name = "trash", description = "Deleted todos and tasks, until they are purged"),
+        (name = "activity", description = "Changes made to todos and tasks"),
+        (name = "undo", description = "Reverting recent changes by the token they returned"),
+    )
+)]
+struct ApiDoc;
+
+/// Moves the paths of a nested router's document below `prefix`,
+/// so they line up with where the router is mounted.
+fn nest(openapi: &mut utoipa::openapi::OpenApi, prefix: &str, nested: utoipa::openapi::OpenApi) {
+    for (path, item) in nested.paths.paths {
+        let path = match path.as_str() {
+            "/" => prefix.to_string(),
+            path => format!("{prefix}{path}"),
+        };
+        openapi.paths.paths.insert(path, item);
+    }
+}
+
+/// The OpenApi 3 document of the json api, generated from `api::types` and the route annotations.
+pub(crate) fn openapi() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", todo::NEST_PREFIX),
+        todo::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", task::NEST_PREFIX),
+        task::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", tag::NEST_PREFIX),
+        tag::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", board::NEST_PREFIX),
+        board::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", project::NEST_PREFIX),
+        project::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", timesheet::NEST_PREFIX),
+        timesheet::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", trash::NEST_PREFIX),
+        trash::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", activity::NEST_PREFIX),
+        activity::ApiDoc::openapi(),
+    );
+    nest(
+        &mut openapi,
+        &format!("{NEST_PREFIX}{}", undo::NEST_PREFIX),
+        undo::ApiDoc::openapi(),
+    );
+    openapi
+}
+
+pub(crate) async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
+
+/// Offline api explorer, rendering [`OPENAPI_PATH`] with a bundled script.
+pub(crate) async fn explorer(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<crate::viewmodels::api::ApiExplorer, crate::error::ApiError> {
+    Ok(crate::viewmodels::api::ApiExplorer {
+        openapi_path: OPENAPI_PATH,
+        signed_in: crate::auth::lookup(&state, &jar).await?.is_some(),
+    })
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .nest(self::todo::NEST_PREFIX, self::todo::router())
+        .nest(self::task::NEST_PREFIX, self::task::router())
+        .nest(self::tag::NEST_PREFIX, self::tag::router())
+        .nest(self::board::NEST_PREFIX, self::board::router())
+        .nest(self::project::NEST_PREFIX, self::project::router())
+        .nest(self::timesheet::NEST_PREFIX, self::timesheet::router())
+        .nest(self::trash::NEST_PREFIX, self::trash::router())
+        .nest(self::activity::NEST_PREFIX, self::activity::router())
+        .nest(self::undo::NEST_PREFIX, self::undo::router())
+        .fallback(|| async {
+            error::JsonError::Rejection(StatusCode::NOT_FOUND, "No such endpoint".to_string())
+        })
+}
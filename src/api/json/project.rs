@@ -0,0 +1,182 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/projects";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(get::all, get::by_id, post::create, patch::update, delete::delete))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::all).post(self::post::create))
+        .route(
+            "/:id",
+            routing::get(self::get::by_id)
+                .patch(self::patch::update)
+                .delete(self::delete::delete),
+        )
+}
+
+mod get {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{json::error::JsonError, types::project::Project},
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "list_projects",
+        tag = "projects",
+        responses(
+            (status = 200, description = "All projects of the current user, archived ones last", body = [Project]),
+        )
+    )]
+    pub(super) async fn all(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<Project>>, JsonError> {
+        models::project::default_for(&pool, user.id).await?;
+        let projects = models::project::get_all(&pool, user.id).await?;
+        Ok(Json(projects))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}",
+        operation_id = "get_project",
+        tag = "projects",
+        params(("id" = i64, Path, description = "Project id")),
+        responses(
+            (status = 200, description = "The project", body = Project),
+            (status = 404, description = "Project not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn by_id(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Project>, JsonError> {
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(project))
+    }
+}
+
+mod post {
+    use axum::{extract::State, http::StatusCode, Json};
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::project::{CreateProject, Project},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        post,
+        path = "/",
+        operation_id = "create_project",
+        tag = "projects",
+        request_body = CreateProject,
+        responses(
+            (status = 201, description = "The created project, with the default board columns", body = Project),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn create(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateProject>, JsonError>,
+    ) -> Result<(StatusCode, Json<Project>), JsonError> {
+        let id = models::project::create(&pool, user.id, &payload).await?;
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok((StatusCode::CREATED, Json(project)))
+    }
+}
+
+mod patch {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::project::{Project, UpdateProject},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        patch,
+        path = "/{id}",
+        operation_id = "update_project",
+        tag = "projects",
+        params(("id" = i64, Path, description = "Project id")),
+        request_body = UpdateProject,
+        responses(
+            (status = 200, description = "The updated project", body = Project),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 404, description = "Project not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn update(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<UpdateProject>, JsonError>,
+    ) -> Result<Json<Project>, JsonError> {
+        models::project::update(&pool, user.id, id, &payload).await?;
+        let project = models::project::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(project))
+    }
+}
+
+mod delete {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{api::json::error::JsonError, auth::CurrentUser, models};
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}",
+        operation_id = "delete_project",
+        tag = "projects",
+        params(("id" = i64, Path, description = "Project id")),
+        responses(
+            (status = 204, description = "Project deleted together with its board columns"),
+            (status = 400, description = "Project still holds tasks", body = ErrorBody),
+            (status = 404, description = "Project not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn delete(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::project::delete(&pool, user.id, id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
@@ -0,0 +1,180 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/tags";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(get::all, get::by_id, post::create, patch::update, delete::delete))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::all).post(self::post::create))
+        .route(
+            "/:id",
+            routing::get(self::get::by_id)
+                .patch(self::patch::update)
+                .delete(self::delete::delete),
+        )
+}
+
+mod get {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{json::error::JsonError, types::tag::Tag},
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "list_tags",
+        tag = "tags",
+        responses(
+            (status = 200, description = "All tags of the current user", body = [Tag]),
+        )
+    )]
+    pub(super) async fn all(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<Tag>>, JsonError> {
+        let tags = models::tag::get_all(&pool, user.id).await?;
+        Ok(Json(tags))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}",
+        operation_id = "get_tag",
+        tag = "tags",
+        params(("id" = i64, Path, description = "Tag id")),
+        responses(
+            (status = 200, description = "The tag", body = Tag),
+            (status = 404, description = "Tag not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn by_id(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Tag>, JsonError> {
+        let tag = models::tag::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(tag))
+    }
+}
+
+mod post {
+    use axum::{extract::State, http::StatusCode, Json};
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::tag::{CreateTag, Tag},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        post,
+        path = "/",
+        operation_id = "create_tag",
+        tag = "tags",
+        request_body = CreateTag,
+        responses(
+            (status = 201, description = "The created tag", body = Tag),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn create(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateTag>, JsonError>,
+    ) -> Result<(StatusCode, Json<Tag>), JsonError> {
+        let id = models::tag::create(&pool, user.id, &payload).await?;
+        let tag = models::tag::get_by_id(&pool, user.id, id).await?;
+        Ok((StatusCode::CREATED, Json(tag)))
+    }
+}
+
+mod patch {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::tag::{Tag, UpdateTag},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        patch,
+        path = "/{id}",
+        operation_id = "update_tag",
+        tag = "tags",
+        params(("id" = i64, Path, description = "Tag id")),
+        request_body = UpdateTag,
+        responses(
+            (status = 200, description = "The updated tag", body = Tag),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 404, description = "Tag not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn update(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<UpdateTag>, JsonError>,
+    ) -> Result<Json<Tag>, JsonError> {
+        models::tag::update(&pool, user.id, id, &payload).await?;
+        let tag = models::tag::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(tag))
+    }
+}
+
+mod delete {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{api::json::error::JsonError, auth::CurrentUser, models};
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}",
+        operation_id = "delete_tag",
+        tag = "tags",
+        params(("id" = i64, Path, description = "Tag id")),
+        responses(
+            (status = 204, description = "Tag deleted and removed from all todos and tasks"),
+            (status = 404, description = "Tag not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn delete(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::tag::delete(&pool, user.id, id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
@@ -0,0 +1,921 @@
+use axum::{extract::DefaultBodyLimit, routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/tasks";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(
+    get::all,
+    get::by_id,
+    get::todos,
+    get::shares,
+    get::time,
+    get::comments,
+    get::history,
+    get::attachments,
+    get::download,
+    post::create,
+    post::create_todo,
+    post::share,
+    post::track,
+    post::comment,
+    post::upload,
+    put::add_todo,
+    put::add_tag,
+    put::add_blocker,
+    put::start_timer,
+    patch::update,
+    patch::update_comment,
+    delete::delete,
+    delete::remove_todo,
+    delete::remove_tag,
+    delete::remove_blocker,
+    delete::stop_timer,
+    delete::untrack,
+    delete::delete_comment,
+    delete::delete_attachment,
+    delete::unshare
+))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::all).post(self::post::create))
+        .route(
+            "/:id",
+            routing::get(self::get::by_id)
+                .patch(self::patch::update)
+                .delete(self::delete::delete),
+        )
+        .route(
+            "/:id/todos",
+            routing::get(self::get::todos).post(self::post::create_todo),
+        )
+        .route(
+            "/:id/todos/:todo_id",
+            routing::put(self::put::add_todo).delete(self::delete::remove_todo),
+        )
+        .route(
+            "/:id/tags/:tag_id",
+            routing::put(self::put::add_tag).delete(self::delete::remove_tag),
+        )
+        .route(
+            "/:id/blockers/:blocker_id",
+            routing::put(self::put::add_blocker).delete(self::delete::remove_blocker),
+        )
+        .route(
+            "/:id/time",
+            routing::get(self::get::time).post(self::post::track),
+        )
+        .route(
+            "/:id/time/:entry_id",
+            routing::delete(self::delete::untrack),
+        )
+        .route(
+            "/:id/timer",
+            routing::put(self::put::start_timer).delete(self::delete::stop_timer),
+        )
+        .route(
+            "/:id/comments",
+            routing::get(self::get::comments).post(self::post::comment),
+        )
+        .route("/:id/history", routing::get(self::get::history))
+        // Uploads are limited per file while they are written, see `AttachmentStore`
+        .route(
+            "/:id/attachments",
+            routing::get(self::get::attachments)
+                .post(self::post::upload)
+                .layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/:id/attachments/:attachment_id",
+            routing::get(self::get::download).delete(self::delete::delete_attachment),
+        )
+        .route(
+            "/:id/comments/:comment_id",
+            routing::patch(self::patch::update_comment).delete(self::delete::delete_comment),
+        )
+        .route(
+            "/:id/shares",
+            routing::get(self::get::shares).post(self::post::share),
+        )
+        .route(
+            "/:id/shares/:user_id",
+            routing::delete(self::delete::unshare),
+        )
+}
+
+mod get {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::{Query, WithRejection};
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{
+                attachment::Attachment,
+                audit::AuditEntry,
+                comment::Comment,
+                task::{TaskHeader, TaskModel, TaskQuery, TaskShare},
+                todo::TodoItem,
+                tracking::TimeEntry,
+            },
+        },
+        attachments::{self, AttachmentStore},
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "list_tasks",
+        tag = "tasks",
+        params(TaskQuery),
+        responses(
+            (status = 200, description = "Tasks matching the query", body = [TaskHeader]),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn all(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Query(query), _): WithRejection<Query<TaskQuery>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<TaskHeader>>, JsonError> {
+        let headers = models::task::get_headers_matching(&pool, user.id, &query).await?;
+        Ok(Json(headers))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}",
+        operation_id = "get_task",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "The task with its todos and tags", body = TaskModel),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn by_id(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<TaskModel>, JsonError> {
+        let task = models::task::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(task))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}/todos",
+        operation_id = "list_task_todos",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "Todos linked to the task", body = [TodoItem]),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn todos(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<TodoItem>>, JsonError> {
+        // Fails with 404 for unknown tasks instead of an empty list
+        models::task::get_header(&pool, user.id, id).await?;
+        let todos = models::task::get_todos(&pool, user.id, id).await?;
+        Ok(Json(todos))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}/shares",
+        operation_id = "list_task_shares",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "Users the task is shared with", body = [TaskShare]),
+            (status = 403, description = "Only the owner can see shares", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn shares(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<TaskShare>>, JsonError> {
+        let shares = models::task::get_shares(&pool, user.id, id).await?;
+        Ok(Json(shares))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}/time",
+        operation_id = "list_task_time",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "Time tracked on the task by everybody, newest first", body = [TimeEntry]),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn time(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<TimeEntry>>, JsonError> {
+        let entries = models::tracking::get_for_task(&pool, user.id, id).await?;
+        Ok(Json(entries))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}/comments",
+        operation_id = "list_task_comments",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "The discussion of the task, oldest first. Marks the comments as read", body = [Comment]),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn comments(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<Comment>>, JsonError> {
+        let comments = models::comment::get_for_task(&pool, user.id, id).await?;
+        Ok(Json(comments))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}/history",
+        operation_id = "list_task_history",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "Changes to the task and its linked todos, newest first", body = [AuditEntry]),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn history(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<AuditEntry>>, JsonError> {
+        let entries = models::audit::get_for_task(&pool, user.id, id).await?;
+        Ok(Json(entries))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}/attachments",
+        operation_id = "list_task_attachments",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "Files attached to the task", body = [Attachment]),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn attachments(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<Attachment>>, JsonError> {
+        let attachments = models::attachment::get_for_task(&pool, user.id, id).await?;
+        Ok(Json(attachments))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}/attachments/{attachment_id}",
+        operation_id = "download_task_attachment",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("attachment_id" = i64, Path, description = "Attachment id")),
+        responses(
+            (status = 200, description = "The file, with its stored mime type", content_type = "application/octet-stream"),
+            (status = 404, description = "Task or attachment not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn download(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, attachment_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<axum::response::Response, JsonError> {
+        let (attachment, content) =
+            models::attachment::read(&pool, &store, user.id, id, attachment_id).await?;
+        Ok(attachments::download(&attachment, content))
+    }
+}
+
+mod post {
+    use axum::{
+        extract::{Multipart, Path, State},
+        http::StatusCode,
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{
+                attachment::Attachment,
+                comment::{Comment, CreateComment},
+                task::{CreateTask, ShareTask, TaskModel, TaskShares},
+                todo::{CreateTodo, TodoItem},
+                tracking::{CreateTimeEntry, TimeEntry},
+                undo::WithUndo,
+            },
+        },
+        attachments::AttachmentStore,
+        auth::CurrentUser,
+        models,
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        post,
+        path = "/",
+        operation_id = "create_task",
+        tag = "tasks",
+        request_body = CreateTask,
+        responses(
+            (status = 201, description = "The created task", body = UndoableTask),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn create(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateTask>, JsonError>,
+    ) -> Result<(StatusCode, Json<WithUndo<TaskModel>>), JsonError> {
+        let mark = undo.mark(&pool).await?;
+        let id = models::task::create_task(&pool, user.id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let task = models::task::get_by_id(&pool, user.id, id).await?;
+        Ok((
+            StatusCode::CREATED,
+            Json(WithUndo {
+                item: task,
+                undo: token,
+            }),
+        ))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/{id}/todos",
+        operation_id = "create_task_todo",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        request_body = CreateTodo,
+        responses(
+            (status = 201, description = "The created todo, already linked to the task", body = UndoableTodo),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 409, description = "Task does not exist", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn create_todo(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateTodo>, JsonError>,
+    ) -> Result<(StatusCode, Json<WithUndo<TodoItem>>), JsonError> {
+        let mark = undo.mark(&pool).await?;
+        let todo_id = models::task::create_todo(&pool, user.id, id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, todo_id).await?;
+        Ok((
+            StatusCode::CREATED,
+            Json(WithUndo {
+                item: todo,
+                undo: token,
+            }),
+        ))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/{id}/shares",
+        operation_id = "share_task",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        request_body = ShareTask,
+        responses(
+            (status = 200, description = "All shares of the task, sharing again changes the access", body = TaskShares),
+            (status = 400, description = "Unknown user or invalid access", body = ErrorBody),
+            (status = 403, description = "Only the owner can share", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn share(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<ShareTask>, JsonError>,
+    ) -> Result<Json<TaskShares>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::share(&pool, user.id, id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let shares = models::task::get_shares(&pool, user.id, id).await?;
+        Ok(Json(TaskShares {
+            shares,
+            undo: token,
+        }))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/{id}/time",
+        operation_id = "track_task_time",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        request_body = CreateTimeEntry,
+        responses(
+            (status = 201, description = "The created time entry", body = TimeEntry),
+            (status = 400, description = "Duration out of range", body = ErrorBody),
+            (status = 403, description = "Read only access", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn track(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateTimeEntry>, JsonError>,
+    ) -> Result<(StatusCode, Json<TimeEntry>), JsonError> {
+        let entry_id = models::tracking::create(&pool, user.id, id, &payload).await?;
+        let entry = models::tracking::get_by_id(&pool, entry_id).await?;
+        Ok((StatusCode::CREATED, Json(entry)))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/{id}/comments",
+        operation_id = "create_task_comment",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        request_body = CreateComment,
+        responses(
+            (status = 201, description = "The created comment", body = Comment),
+            (status = 400, description = "Empty or too long comment", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn comment(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateComment>, JsonError>,
+    ) -> Result<(StatusCode, Json<Comment>), JsonError> {
+        let comment_id = models::comment::create(&pool, user.id, id, &payload).await?;
+        let comment = models::comment::get_by_id(&pool, user.id, id, comment_id).await?;
+        Ok((StatusCode::CREATED, Json(comment)))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/{id}/attachments",
+        operation_id = "upload_task_attachments",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        request_body(content = UploadAttachments, content_type = "multipart/form-data"),
+        responses(
+            (status = 201, description = "The stored attachments", body = [Attachment]),
+            (status = 400, description = "No file or a malformed upload", body = ErrorBody),
+            (status = 403, description = "Read only access", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+            (status = 413, description = "A file is over the size limit, files before it are kept", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn upload(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+        mut multipart: Multipart,
+    ) -> Result<(StatusCode, Json<Vec<Attachment>>), JsonError> {
+        let mut attachments = Vec::new();
+        for attachment_id in
+            models::attachment::upload(&pool, &store, user.id, id, &mut multipart).await?
+        {
+            attachments
+                .push(models::attachment::get_by_id(&pool, user.id, id, attachment_id).await?);
+        }
+        Ok((StatusCode::CREATED, Json(attachments)))
+    }
+}
+
+mod put {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{tracking::TimeEntry, undo::UndoToken},
+        },
+        auth::CurrentUser,
+        models::{self, tag::TagOwner},
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        put,
+        path = "/{id}/todos/{todo_id}",
+        operation_id = "link_task_todo",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("todo_id" = i64, Path, description = "Todo id")),
+        responses(
+            (status = 200, description = "Todo linked", body = UndoToken),
+            (status = 409, description = "Task or todo does not exist", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn add_todo(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, todo_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoToken>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::add_todo(&pool, user.id, id, todo_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Json(UndoToken { undo: token }))
+    }
+
+    #[utoipa::path(
+        put,
+        path = "/{id}/tags/{tag_id}",
+        operation_id = "add_task_tag",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("tag_id" = i64, Path, description = "Tag id")),
+        responses(
+            (status = 204, description = "Tag assigned"),
+            (status = 409, description = "Task or tag does not exist", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn add_tag(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, tag_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::tag::add(&pool, user.id, TagOwner::Task, id, tag_id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    #[utoipa::path(
+        put,
+        path = "/{id}/blockers/{blocker_id}",
+        operation_id = "add_task_blocker",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("blocker_id" = i64, Path, description = "Task that has to be done first")),
+        responses(
+            (status = 200, description = "Blocker added", body = UndoToken),
+            (status = 400, description = "The blocker is waiting for the task itself", body = ErrorBody),
+            (status = 404, description = "Task or blocker not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn add_blocker(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, blocker_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoToken>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::add_blocker(&pool, user.id, id, blocker_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Json(UndoToken { undo: token }))
+    }
+
+    #[utoipa::path(
+        put,
+        path = "/{id}/timer",
+        operation_id = "start_task_timer",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "The running timer, a timer on another task is stopped", body = TimeEntry),
+            (status = 403, description = "Read only access", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn start_timer(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<TimeEntry>, JsonError> {
+        let entry_id = models::tracking::start(&pool, user.id, id).await?;
+        let entry = models::tracking::get_by_id(&pool, entry_id).await?;
+        Ok(Json(entry))
+    }
+}
+
+mod patch {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{
+                comment::{Comment, UpdateComment},
+                task::{TaskModel, UpdateTask},
+                undo::WithUndo,
+            },
+        },
+        auth::CurrentUser,
+        models,
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        patch,
+        path = "/{id}",
+        operation_id = "update_task",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        request_body = UpdateTask,
+        responses(
+            (status = 200, description = "The updated task", body = UndoableTask),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 404, description = "Task not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn update(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<UpdateTask>, JsonError>,
+    ) -> Result<Json<WithUndo<TaskModel>>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::update(&pool, user.id, id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let task = models::task::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(WithUndo {
+            item: task,
+            undo: token,
+        }))
+    }
+
+    #[utoipa::path(
+        patch,
+        path = "/{id}/comments/{comment_id}",
+        operation_id = "update_task_comment",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("comment_id" = i64, Path, description = "Comment id")),
+        request_body = UpdateComment,
+        responses(
+            (status = 200, description = "The updated comment", body = Comment),
+            (status = 400, description = "Empty or too long comment", body = ErrorBody),
+            (status = 403, description = "Only the author can edit a comment", body = ErrorBody),
+            (status = 404, description = "Task or comment not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn update_comment(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, comment_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        WithRejection(Json(payload), _): WithRejection<Json<UpdateComment>, JsonError>,
+    ) -> Result<Json<Comment>, JsonError> {
+        models::comment::update(&pool, user.id, id, comment_id, &payload).await?;
+        let comment = models::comment::get_by_id(&pool, user.id, id, comment_id).await?;
+        Ok(Json(comment))
+    }
+}
+
+mod delete {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{json::error::JsonError, types::undo::UndoToken},
+        attachments::AttachmentStore,
+        auth::CurrentUser,
+        models::{self, tag::TagOwner},
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}",
+        operation_id = "delete_task",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 200, description = "Task moved to the trash", body = UndoToken),
+            (status = 400, description = "Task still has open subtasks", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn delete(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoToken>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::delete(&pool, user.id, id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Json(UndoToken { undo: token }))
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/todos/{todo_id}",
+        operation_id = "unlink_task_todo",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("todo_id" = i64, Path, description = "Todo id")),
+        responses(
+            (status = 200, description = "Todo unlinked", body = UndoToken),
+        )
+    )]
+    pub(super) async fn remove_todo(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, todo_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoToken>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::remove_todo(&pool, user.id, id, todo_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Json(UndoToken { undo: token }))
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/tags/{tag_id}",
+        operation_id = "remove_task_tag",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("tag_id" = i64, Path, description = "Tag id")),
+        responses(
+            (status = 204, description = "Tag removed"),
+        )
+    )]
+    pub(super) async fn remove_tag(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, tag_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::tag::remove(&pool, user.id, TagOwner::Task, id, tag_id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/blockers/{blocker_id}",
+        operation_id = "remove_task_blocker",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("blocker_id" = i64, Path, description = "Blocking task id")),
+        responses(
+            (status = 200, description = "Blocker removed", body = UndoToken),
+        )
+    )]
+    pub(super) async fn remove_blocker(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, blocker_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoToken>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::remove_blocker(&pool, user.id, id, blocker_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Json(UndoToken { undo: token }))
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/timer",
+        operation_id = "stop_task_timer",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id")),
+        responses(
+            (status = 204, description = "Timer stopped"),
+            (status = 400, description = "No timer is running on the task", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn stop_timer(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::tracking::stop(&pool, user.id, id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/time/{entry_id}",
+        operation_id = "delete_time_entry",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("entry_id" = i64, Path, description = "Time entry id")),
+        responses(
+            (status = 204, description = "Time entry deleted"),
+            (status = 404, description = "No time entry of yours on the task", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn untrack(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, entry_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::tracking::delete(&pool, user.id, id, entry_id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/attachments/{attachment_id}",
+        operation_id = "delete_task_attachment",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("attachment_id" = i64, Path, description = "Attachment id")),
+        responses(
+            (status = 204, description = "Attachment and its file deleted"),
+            (status = 403, description = "Read only access", body = ErrorBody),
+            (status = 404, description = "Task or attachment not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn delete_attachment(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, attachment_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<StatusCode, JsonError> {
+        models::attachment::delete(&pool, &store, user.id, id, attachment_id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/comments/{comment_id}",
+        operation_id = "delete_task_comment",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("comment_id" = i64, Path, description = "Comment id")),
+        responses(
+            (status = 204, description = "Comment deleted"),
+            (status = 403, description = "Only the author or the task owner can delete a comment", body = ErrorBody),
+            (status = 404, description = "Task or comment not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn delete_comment(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, comment_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::comment::delete(&pool, user.id, id, comment_id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/shares/{user_id}",
+        operation_id = "unshare_task",
+        tag = "tasks",
+        params(("id" = i64, Path, description = "Task id"), ("user_id" = i64, Path, description = "User the task is shared with")),
+        responses(
+            (status = 200, description = "Share removed", body = UndoToken),
+            (status = 403, description = "Only the owner can remove shares", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn unshare(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, share_user_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoToken>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::task::unshare(&pool, user.id, id, share_user_id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Json(UndoToken { undo: token }))
+    }
+}
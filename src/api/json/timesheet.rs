@@ -0,0 +1,48 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/timesheet";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(get::week))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", routing::get(self::get::week))
+}
+
+mod get {
+    use axum::{extract::State, Json};
+    use axum_extra::extract::{Query, WithRejection};
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::tracking::{Timesheet, TimesheetQuery},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "get_timesheet",
+        tag = "timesheet",
+        params(TimesheetQuery),
+        responses(
+            (status = 200, description = "Your tracked time in the week, in seconds", body = Timesheet),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn week(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Query(query), _): WithRejection<Query<TimesheetQuery>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Timesheet>, JsonError> {
+        let timesheet = models::tracking::get_timesheet(&pool, user.id, &query).await?;
+        Ok(Json(timesheet))
+    }
+}
@@ -0,0 +1,282 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/todos";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(
+    get::all,
+    get::by_id,
+    post::create,
+    put::add_tag,
+    patch::update,
+    delete::delete,
+    delete::remove_tag
+))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(self::get::all).post(self::post::create))
+        .route(
+            "/:id",
+            routing::get(self::get::by_id)
+                .patch(self::patch::update)
+                .delete(self::delete::delete),
+        )
+        .route(
+            "/:id/tags/:tag_id",
+            routing::put(self::put::add_tag).delete(self::delete::remove_tag),
+        )
+}
+
+mod get {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::{Query, WithRejection};
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::todo::{TodoItem, TodoQuery},
+        },
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "list_todos",
+        tag = "todos",
+        params(TodoQuery),
+        responses(
+            (status = 200, description = "Todos matching the query", body = [TodoItem]),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn all(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Query(query), _): WithRejection<Query<TodoQuery>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<TodoItem>>, JsonError> {
+        let todos = models::todo::get_all_matching(&pool, user.id, &query).await?;
+        Ok(Json(todos))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/{id}",
+        operation_id = "get_todo",
+        tag = "todos",
+        params(("id" = i64, Path, description = "Todo id")),
+        responses(
+            (status = 200, description = "The todo", body = TodoItem),
+            (status = 404, description = "Todo not found", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn by_id(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<TodoItem>, JsonError> {
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(todo))
+    }
+}
+
+mod post {
+    use axum::{extract::State, http::StatusCode, Json};
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{
+                todo::{CreateTodo, TodoItem},
+                undo::WithUndo,
+            },
+        },
+        auth::CurrentUser,
+        models,
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        post,
+        path = "/",
+        operation_id = "create_todo",
+        tag = "todos",
+        request_body = CreateTodo,
+        responses(
+            (status = 201, description = "The created todo", body = UndoableTodo),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn create(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<CreateTodo>, JsonError>,
+    ) -> Result<(StatusCode, Json<WithUndo<TodoItem>>), JsonError> {
+        let mark = undo.mark(&pool).await?;
+        let id = models::todo::create(&pool, user.id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
+        Ok((
+            StatusCode::CREATED,
+            Json(WithUndo {
+                item: todo,
+                undo: token,
+            }),
+        ))
+    }
+}
+
+mod put {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::json::error::JsonError,
+        auth::CurrentUser,
+        models::{self, tag::TagOwner},
+    };
+
+    #[utoipa::path(
+        put,
+        path = "/{id}/tags/{tag_id}",
+        operation_id = "add_todo_tag",
+        tag = "todos",
+        params(("id" = i64, Path, description = "Todo id"), ("tag_id" = i64, Path, description = "Tag id")),
+        responses(
+            (status = 204, description = "Tag assigned"),
+            (status = 409, description = "Todo or tag does not exist", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn add_tag(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, tag_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::tag::add(&pool, user.id, TagOwner::Todo, id, tag_id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+mod patch {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{
+            json::error::JsonError,
+            types::{
+                todo::{TodoItem, UpdateTodo},
+                undo::WithUndo,
+            },
+        },
+        auth::CurrentUser,
+        models,
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        patch,
+        path = "/{id}",
+        operation_id = "update_todo",
+        tag = "todos",
+        params(("id" = i64, Path, description = "Todo id")),
+        request_body = UpdateTodo,
+        responses(
+            (status = 200, description = "The updated todo", body = UndoableTodo),
+            (status = 400, description = "Invalid input", body = ErrorBody),
+            (status = 404, description = "Todo not found", body = ErrorBody),
+            (status = 422, description = "Malformed json body", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn update(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+        WithRejection(Json(payload), _): WithRejection<Json<UpdateTodo>, JsonError>,
+    ) -> Result<Json<WithUndo<TodoItem>>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::todo::update(&pool, user.id, id, &payload).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        let todo = models::todo::get_by_id(&pool, user.id, id).await?;
+        Ok(Json(WithUndo {
+            item: todo,
+            undo: token,
+        }))
+    }
+}
+
+mod delete {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{json::error::JsonError, types::undo::UndoToken},
+        auth::CurrentUser,
+        models::{self, tag::TagOwner},
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}",
+        operation_id = "delete_todo",
+        tag = "todos",
+        params(("id" = i64, Path, description = "Todo id")),
+        responses(
+            (status = 200, description = "Todo moved to the trash of its owner", body = UndoToken),
+        )
+    )]
+    pub(super) async fn delete(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(id), _): WithRejection<Path<i64>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoToken>, JsonError> {
+        let mark = undo.mark(&pool).await?;
+        models::todo::delete(&pool, user.id, id).await?;
+        let token = undo.push(&pool, user.id, mark).await?;
+        Ok(Json(UndoToken { undo: token }))
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/{id}/tags/{tag_id}",
+        operation_id = "remove_todo_tag",
+        tag = "todos",
+        params(("id" = i64, Path, description = "Todo id"), ("tag_id" = i64, Path, description = "Tag id")),
+        responses(
+            (status = 204, description = "Tag removed"),
+        )
+    )]
+    pub(super) async fn remove_tag(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((id, tag_id)), _): WithRejection<Path<(i64, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::tag::remove(&pool, user.id, TagOwner::Todo, id, tag_id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
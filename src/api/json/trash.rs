@@ -0,0 +1,143 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/trash";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(get::all, post::restore, delete::purge, delete::empty))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/",
+            routing::get(self::get::all).delete(self::delete::empty),
+        )
+        .route("/:kind/:id", routing::delete(self::delete::purge))
+        .route("/:kind/:id/restore", routing::post(self::post::restore))
+}
+
+mod get {
+    use axum::{extract::State, Json};
+
+    use crate::{
+        api::{json::error::JsonError, types::trash::TrashItem},
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        get,
+        path = "/",
+        operation_id = "get_trash",
+        tag = "trash",
+        responses(
+            (status = 200, description = "Your deleted todos and tasks, most recently deleted first", body = [TrashItem]),
+        )
+    )]
+    pub(super) async fn all(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<Json<Vec<TrashItem>>, JsonError> {
+        let items = models::trash::get_all(&pool, user.id).await?;
+        Ok(Json(items))
+    }
+}
+
+mod post {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{json::error::JsonError, types::trash::TrashKind},
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        post,
+        path = "/{kind}/{id}/restore",
+        operation_id = "restore_from_trash",
+        tag = "trash",
+        params(
+            ("kind" = TrashKind, Path, description = "Kind of the item"),
+            ("id" = i64, Path, description = "Task or todo id"),
+        ),
+        responses(
+            (status = 204, description = "Item restored together with its task links"),
+            (status = 404, description = "No such item in your trash", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn restore(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((kind, id)), _): WithRejection<Path<(TrashKind, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+    ) -> Result<StatusCode, JsonError> {
+        models::trash::restore(&pool, user.id, kind, id).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+mod delete {
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{json::error::JsonError, types::trash::TrashKind},
+        attachments::AttachmentStore,
+        auth::CurrentUser,
+        models,
+    };
+
+    #[utoipa::path(
+        delete,
+        path = "/{kind}/{id}",
+        operation_id = "purge_from_trash",
+        tag = "trash",
+        params(
+            ("kind" = TrashKind, Path, description = "Kind of the item"),
+            ("id" = i64, Path, description = "Task or todo id"),
+        ),
+        responses(
+            (status = 204, description = "Item deleted for good, tasks along with their attachments"),
+            (status = 404, description = "No such item in your trash", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn purge(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path((kind, id)), _): WithRejection<Path<(TrashKind, i64)>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<StatusCode, JsonError> {
+        models::trash::purge(&pool, user.id, kind, id).await?;
+        store.clean_up(&pool).await;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    #[utoipa::path(
+        delete,
+        path = "/",
+        operation_id = "empty_trash",
+        tag = "trash",
+        responses(
+            (status = 204, description = "Everything in your trash deleted for good"),
+        )
+    )]
+    pub(super) async fn empty(
+        CurrentUser(user): CurrentUser,
+        State(pool): State<sqlx::SqlitePool>,
+        State(store): State<AttachmentStore>,
+    ) -> Result<StatusCode, JsonError> {
+        models::trash::empty(&pool, user.id).await?;
+        store.clean_up(&pool).await;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
@@ -0,0 +1,50 @@
+use axum::{routing, Router};
+
+use crate::state::AppState;
+
+pub(crate) const NEST_PREFIX: &str = "/undo";
+
+/// Paths relative to [`NEST_PREFIX`], collected into the full document by [`super::openapi`].
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(post::undo))]
+pub(crate) struct ApiDoc;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/:token", routing::post(self::post::undo))
+}
+
+mod post {
+    use axum::{
+        extract::{Path, State},
+        Json,
+    };
+    use axum_extra::extract::WithRejection;
+
+    use crate::{
+        api::{json::error::JsonError, types::undo::UndoneStep},
+        auth::CurrentUser,
+        undo::UndoHistory,
+    };
+
+    #[utoipa::path(
+        post,
+        path = "/{token}",
+        operation_id = "undo",
+        tag = "undo",
+        params(("token" = String, Path, description = "Token returned along with the change")),
+        responses(
+            (status = 200, description = "The change was reverted", body = UndoneStep),
+            (status = 400, description = "Too old to be undone, already undone or changed since", body = ErrorBody),
+            (status = 403, description = "No write access anymore", body = ErrorBody),
+        )
+    )]
+    pub(super) async fn undo(
+        CurrentUser(user): CurrentUser,
+        WithRejection(Path(token), _): WithRejection<Path<String>, JsonError>,
+        State(pool): State<sqlx::SqlitePool>,
+        State(undo): State<UndoHistory>,
+    ) -> Result<Json<UndoneStep>, JsonError> {
+        let label = undo.undo(&pool, user.id, &token).await?;
+        Ok(Json(UndoneStep { label }))
+    }
+}
@@ -0,0 +1,40 @@
+/// A file attached to a task.
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct Attachment {
+    pub id: i64,
+    pub task_id: i64,
+    /// Uploaded by
+    pub user_id: i64,
+    /// Name of the uploader
+    pub user: String,
+    /// File name as uploaded
+    pub name: String,
+    /// In bytes
+    pub size: i64,
+    pub mime: String,
+    /// Hex encoded checksum of the content
+    pub sha256: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created: super::time::DateTime,
+}
+
+impl Attachment {
+    /// Size in the largest fitting unit, like `1.5 MB`.
+    pub fn human_size(&self) -> String {
+        let size = self.size as f64;
+        match self.size {
+            size_bytes if size_bytes < 1024 => format!("{size_bytes} B"),
+            size_bytes if size_bytes < 1024 * 1024 => format!("{:.1} KB", size / 1024.0),
+            _ => format!("{:.1} MB", size / (1024.0 * 1024.0)),
+        }
+    }
+}
+
+/// Multipart form of an upload, every `file` part becomes an attachment.
+/// Only describes the request in the api docs, uploads are read part by part.
+#[derive(utoipa::ToSchema)]
+#[allow(dead_code)]
+pub struct UploadAttachments {
+    #[schema(value_type = Vec<String>, format = Binary)]
+    pub file: Vec<Vec<u8>>,
+}
@@ -0,0 +1,230 @@
+use super::{
+    recurrence::Recurrence,
+    task::{Access, Priority},
+    time::DateTime,
+};
+
+/// What an audit entry is about.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum AuditEntity {
+    Task,
+    Todo,
+}
+
+impl AuditEntity {
+    pub const ALL: [AuditEntity; 2] = [Self::Task, Self::Todo];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Task => "task",
+            Self::Todo => "todo",
+        }
+    }
+}
+
+/// What happened to the todo or task.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum AuditAction {
+    Create,
+    Update,
+    /// Moved to the trash
+    Delete,
+    /// Taken out of the trash
+    Restore,
+}
+
+impl AuditAction {
+    /// As in "ann updated task 3".
+    pub fn past_tense(self) -> &'static str {
+        match self {
+            Self::Create => "created",
+            Self::Update => "updated",
+            Self::Delete => "deleted",
+            Self::Restore => "restored",
+        }
+    }
+}
+
+/// The fields of a task the audit log keeps track of.
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct TaskSnapshot {
+    pub name: String,
+    pub description: String,
+    pub due: Option<DateTime>,
+    pub done: bool,
+    pub priority: Priority,
+    pub project_id: Option<i64>,
+    pub parent_id: Option<i64>,
+    /// The board column, missing for tasks outside projects
+    #[serde(default)]
+    pub column_id: Option<i64>,
+    #[sqlx(skip)]
+    pub recurrence: Option<Recurrence>,
+    /// Ids of the linked todos
+    #[sqlx(skip)]
+    pub todos: Vec<i64>,
+    /// Ids of the blocking tasks
+    #[sqlx(skip)]
+    pub blockers: Vec<i64>,
+    /// Everybody but the owner with access to the task, missing in older entries
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub shares: Option<Vec<ShareSnapshot>>,
+}
+
+/// A user a task is shared with, as kept in a [`TaskSnapshot`].
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct ShareSnapshot {
+    pub user_id: i64,
+    pub access: Access,
+}
+
+/// The fields of a todo the audit log keeps track of.
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct TodoSnapshot {
+    pub name: String,
+    pub done: bool,
+    pub priority: Priority,
+}
+
+/// An audit entry as stored, with its states as json text.
+#[derive(sqlx::FromRow)]
+pub struct AuditRow {
+    pub id: i64,
+    pub entity: AuditEntity,
+    pub entity_id: i64,
+    pub user_id: Option<i64>,
+    pub user: Option<String>,
+    pub action: AuditAction,
+    pub at: DateTime,
+    pub before_state: Option<String>,
+    pub after_state: Option<String>,
+}
+
+/// A change made to a todo or task.
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub entity: AuditEntity,
+    pub entity_id: i64,
+    /// Who made the change, `None` once the account is gone
+    pub user_id: Option<i64>,
+    /// Name of that user
+    pub user: Option<String>,
+    pub action: AuditAction,
+    #[schema(value_type = String, format = DateTime)]
+    pub at: DateTime,
+    /// State before the change, `None` for creates and restores
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<serde_json::Value>,
+    /// State after the change, `None` for deletes
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<serde_json::Value>,
+}
+
+fn parse_state(state: Option<String>) -> Option<serde_json::Value> {
+    state.and_then(|state| serde_json::from_str(&state).ok())
+}
+
+impl From<AuditRow> for AuditEntry {
+    fn from(row: AuditRow) -> Self {
+        Self {
+            id: row.id,
+            entity: row.entity,
+            entity_id: row.entity_id,
+            user_id: row.user_id,
+            user: row.user,
+            action: row.action,
+            at: row.at,
+            before: parse_state(row.before_state),
+            after: parse_state(row.after_state),
+        }
+    }
+}
+
+/// A field that differs between the two states of an entry.
+pub struct AuditChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Longest value shown in a change, longer ones like descriptions are cut.
+const MAX_CHANGE_CHARS: usize = 80;
+
+fn show_value(value: Option<&serde_json::Value>) -> String {
+    let shown = match value {
+        None | Some(serde_json::Value::Null) => "-".to_string(),
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    };
+    match shown.chars().count() > MAX_CHANGE_CHARS {
+        true => shown.chars().take(MAX_CHANGE_CHARS).collect::<String>() + "…",
+        false => shown,
+    }
+}
+
+impl AuditEntry {
+    /// Name of the todo or task, from the latest state the entry knows.
+    pub fn name(&self) -> Option<&str> {
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .and_then(|state| state.get("name"))
+            .and_then(|name| name.as_str())
+    }
+
+    /// The fields an update changed, by field name.
+    /// Creates, deletes and restores change nothing field by field.
+    pub fn changes(&self) -> Vec<AuditChange> {
+        let (Some(serde_json::Value::Object(before)), Some(serde_json::Value::Object(after))) =
+            (&self.before, &self.after)
+        else {
+            return Vec::new();
+        };
+        after
+            .iter()
+            .filter(|(field, value)| before.get(*field) != Some(*value))
+            .map(|(field, value)| AuditChange {
+                field: field.replace('_', " "),
+                before: show_value(before.get(field)),
+                after: show_value(Some(value)),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ActivityQuery {
+    /// Only changes made by this user
+    pub user: Option<i64>,
+    /// Only changes to todos or to tasks
+    pub entity: Option<AuditEntity>,
+    /// Only entries older than this entry id, to page back in time
+    pub before: Option<i64>,
+}
@@ -0,0 +1,168 @@
+/// A status column of a project's board, tasks are moved between columns.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    sqlx::FromRow,
+    utoipa::ToSchema,
+)]
+pub struct Column {
+    pub id: i64,
+    pub project_id: i64,
+    pub name: String,
+    /// Columns are shown in ascending position, starting at `0`
+    pub position: i64,
+    /// Tasks in this column count as done
+    pub done: bool,
+    /// Number of tasks the column should hold at most, no limit if left out
+    pub wip_limit: Option<i64>,
+    /// Reject moves into a full column instead of only warning about it
+    pub wip_block: bool,
+}
+
+/// A column together with its tasks, in board order.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct BoardColumn {
+    pub column: Column,
+    pub tasks: Vec<super::task::TaskHeader>,
+}
+
+impl BoardColumn {
+    /// The column holds more tasks than its WIP limit allows.
+    pub fn over_limit(&self) -> bool {
+        self.column
+            .wip_limit
+            .is_some_and(|limit| self.tasks.len() as i64 > limit)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct CreateColumn {
+    pub name: String,
+    #[serde(default)]
+    pub done: bool,
+    pub wip_limit: Option<i64>,
+    #[serde(default)]
+    pub wip_block: bool,
+    /// Defaults to the oldest project that is not archived
+    pub project_id: Option<i64>,
+}
+
+/// Fields left out keep their current value.
+#[derive(serde::Serialize, serde::Deserialize, Default, utoipa::ToSchema)]
+pub struct UpdateColumn {
+    pub name: Option<String>,
+    pub done: Option<bool>,
+    /// New position, the other columns shift to make room
+    pub position: Option<i64>,
+    /// `0` removes the limit
+    pub wip_limit: Option<i64>,
+    pub wip_block: Option<bool>,
+}
+
+/// Column form as submitted by the browser, with `done` and `wip_block` as checkbox state
+/// and an empty WIP limit for no limit.
+#[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
+pub struct UpdateColumnRaw {
+    pub name: Option<String>,
+    pub done: Option<String>,
+    pub wip_limit: Option<String>,
+    pub wip_block: Option<String>,
+}
+
+impl TryFrom<UpdateColumnRaw> for UpdateColumn {
+    /// Human readable reason the form was rejected
+    type Error = String;
+
+    fn try_from(value: UpdateColumnRaw) -> Result<Self, Self::Error> {
+        // An unchecked checkbox is not submitted at all
+        let done = matches!(value.done.as_deref(), Some("on"));
+        let wip_block = matches!(value.wip_block.as_deref(), Some("on"));
+        let wip_limit = match value.wip_limit.as_deref().map(str::trim) {
+            None | Some("") => 0,
+            Some(limit) => limit
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid WIP limit {limit}"))?,
+        };
+
+        Ok(UpdateColumn {
+            name: value.name,
+            done: Some(done),
+            position: None,
+            wip_limit: Some(wip_limit),
+            wip_block: Some(wip_block),
+        })
+    }
+}
+
+/// Target of a drag and drop move on the board.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct MoveTask {
+    pub column_id: i64,
+    /// Index within the target column, positions past the end append the task
+    pub position: i64,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BoardQuery {
+    /// Board of this project, defaults to the oldest project that is not archived
+    pub project: Option<i64>,
+}
+
+/// Project and date range of the flow metrics, both days are included.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FlowQuery {
+    /// Defaults to the oldest project that is not archived
+    pub project: Option<i64>,
+    /// Defaults to 30 days before `to`
+    pub from: Option<chrono::NaiveDate>,
+    /// Defaults to today
+    pub to: Option<chrono::NaiveDate>,
+}
+
+/// How long a task finished within the range of the [`FlowMetrics`] took.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, utoipa::ToSchema)]
+pub struct TaskFlow {
+    pub id: i64,
+    pub name: String,
+    pub created: super::time::DateTime,
+    /// When the task first left the first column of the board
+    pub started: super::time::DateTime,
+    /// When the task entered the done column it is in
+    pub finished: super::time::DateTime,
+    /// Hours from creation to finish
+    pub lead_time: f64,
+    /// Hours from start to finish
+    pub cycle_time: f64,
+}
+
+/// Number of tasks per column at the end of a day.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, utoipa::ToSchema)]
+pub struct FlowDay {
+    pub date: chrono::NaiveDate,
+    /// One count per column of [`FlowMetrics::columns`], in the same order
+    pub counts: Vec<i64>,
+}
+
+/// Lead time, cycle time and cumulative flow of a project's board,
+/// computed from the times tasks entered each column.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, utoipa::ToSchema)]
+pub struct FlowMetrics {
+    pub project_id: i64,
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    pub columns: Vec<Column>,
+    /// One entry per day of the range, the cumulative flow diagram
+    pub days: Vec<FlowDay>,
+    /// Tasks finished within the range, oldest first
+    pub finished: Vec<TaskFlow>,
+    /// Average lead time of the finished tasks in hours
+    pub lead_time: Option<f64>,
+    /// Average cycle time of the finished tasks in hours
+    pub cycle_time: Option<f64>,
+}
@@ -0,0 +1,35 @@
+/// A comment in the discussion of a task.
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct Comment {
+    pub id: i64,
+    pub task_id: i64,
+    pub user_id: i64,
+    /// Name of the author
+    pub author: String,
+    /// Markdown
+    pub body: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created: super::time::DateTime,
+    /// When the body was last changed
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub edited: Option<super::time::DateTime>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct CreateComment {
+    /// Markdown
+    pub body: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct UpdateComment {
+    /// Markdown
+    pub body: String,
+}
+
+/// Comment thread query of the html routes.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct CommentQuery {
+    /// Comment shown as edit form
+    pub edit: Option<i64>,
+}
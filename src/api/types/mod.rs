@@ -1,5 +1,16 @@
 pub mod time {
     pub type DateTime = chrono::DateTime<chrono::Utc>;
 }
+pub mod attachment;
+pub mod audit;
+pub mod board;
+pub mod comment;
+pub mod project;
+pub mod recurrence;
+pub mod tag;
 pub mod task;
 pub mod todo;
+pub mod tracking;
+pub mod trash;
+pub mod undo;
+pub mod user;
@@ -0,0 +1,31 @@
+/// Groups tasks and the board columns they move through.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    sqlx::FromRow,
+    utoipa::ToSchema,
+)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    /// Archived projects are hidden from the project switcher and task lists
+    pub archived: bool,
+    /// Number of tasks in the project
+    pub tasks: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct CreateProject {
+    pub name: String,
+}
+
+/// Fields left out keep their current value.
+#[derive(serde::Serialize, serde::Deserialize, Default, utoipa::ToSchema)]
+pub struct UpdateProject {
+    pub name: Option<String>,
+    pub archived: Option<bool>,
+}
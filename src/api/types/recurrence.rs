@@ -0,0 +1,105 @@
+/// Day of the week, written as in RFC 5545 rules.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Weekday {
+    Mo,
+    Tu,
+    We,
+    Th,
+    Fr,
+    Sa,
+    Su,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Self::Mo,
+        Self::Tu,
+        Self::We,
+        Self::Th,
+        Self::Fr,
+        Self::Sa,
+        Self::Su,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Mo => "MO",
+            Self::Tu => "TU",
+            Self::We => "WE",
+            Self::Th => "TH",
+            Self::Fr => "FR",
+            Self::Sa => "SA",
+            Self::Su => "SU",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|day| day.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(value: chrono::Weekday) -> Self {
+        Self::ALL[value.num_days_from_monday() as usize]
+    }
+}
+
+/// When a task comes back after it is done.
+/// Completing a recurring task creates its next occurrence, which takes over the rule.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    /// On each of the given days of the week
+    Weekly {
+        weekdays: Vec<Weekday>,
+    },
+    /// On this day of every month, or on the last day of shorter months
+    Monthly {
+        day: u32,
+    },
+    /// Counted from the day the task was done, not from its due date
+    AfterCompletion {
+        days: u32,
+    },
+    /// An RFC 5545 `RRULE` using `FREQ` (daily to yearly), `INTERVAL`, `BYDAY`
+    /// without ordinals, `BYMONTHDAY`, `BYMONTH`, `COUNT` and `UNTIL`
+    Rule {
+        rrule: String,
+    },
+}
+
+impl Recurrence {
+    /// Human readable form of the rule.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Daily => "Every day".to_string(),
+            Self::Weekly { weekdays } => format!(
+                "Every week on {}",
+                weekdays
+                    .iter()
+                    .map(|day| day.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Monthly { day } => format!("Every month on day {day}"),
+            Self::AfterCompletion { days: 1 } => "1 day after completion".to_string(),
+            Self::AfterCompletion { days } => format!("{days} days after completion"),
+            Self::Rule { rrule } => format!("RRULE {rrule}"),
+        }
+    }
+}
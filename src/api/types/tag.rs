@@ -0,0 +1,55 @@
+#[derive(
+    serde::Deserialize,
+    serde::Serialize,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+    sqlx::FromRow,
+    utoipa::ToSchema,
+)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    /// Hex color like `#1abc9c`
+    pub color: String,
+}
+
+impl Tag {
+    /// Black or white, whichever reads better on top of `color`.
+    pub fn text_color(&self) -> &'static str {
+        let channel = |range: std::ops::Range<usize>| {
+            self.color
+                .get(range)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .unwrap_or(0) as u32
+        };
+        let luminance = 299 * channel(1..3) + 587 * channel(3..5) + 114 * channel(5..7);
+        if luminance > 128_000 {
+            "#000000"
+        } else {
+            "#ffffff"
+        }
+    }
+}
+
+pub(crate) const DEFAULT_COLOR: &str = "#7f8c8d";
+
+/// Colors are rendered into inline styles, so only plain `#rrggbb` values are accepted.
+pub(crate) fn is_valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct CreateTag {
+    pub name: String,
+    /// Hex color like `#1abc9c`, defaults to grey
+    pub color: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct UpdateTag {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
@@ -1,34 +1,569 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveTime};
+use serde::{Deserialize, Deserializer};
+
+use super::recurrence::{Recurrence, Weekday};
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct TaskHeaders {
     pub tasks: Vec<TaskHeader>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct TaskHeader {
     pub id: i64,
     pub name: String,
+    /// The board column the task is in
+    pub column_id: Option<i64>,
+    /// Name of that column
+    pub status: Option<String>,
+    pub project_id: Option<i64>,
+    /// Name of the project
+    pub project: Option<String>,
+    /// The task this one is a subtask of
+    pub parent_id: Option<i64>,
+    /// Number of direct subtasks
+    pub subtasks: i64,
+    /// How many of them are done
+    pub subtasks_done: i64,
+    /// Number of tasks blocking this one that are not done yet
+    pub open_blockers: i64,
+    /// Number of comments on the task
+    pub comments: i64,
+    /// Comments by others the requesting user has not seen yet
+    pub unread_comments: i64,
+    /// Number of todos linked to the task
+    pub todos: i64,
+    /// How many of them are done
+    pub todos_done: i64,
+    pub priority: Priority,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub due: Option<super::time::DateTime>,
+    pub done: bool,
+    /// What the requesting user may do with the task
+    pub access: Access,
+    #[sqlx(skip)]
+    pub tags: Vec<super::tag::Tag>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+impl TaskHeader {
+    pub fn has_subtasks(&self) -> bool {
+        self.subtasks > 0
+    }
+
+    pub fn has_todos(&self) -> bool {
+        self.todos > 0
+    }
+
+    /// How far the linked todos are.
+    pub fn progress(&self) -> TodoProgress {
+        TodoProgress::of(self.todos_done, self.todos)
+    }
+
+    pub fn has_unread_comments(&self) -> bool {
+        self.unread_comments > 0
+    }
+
+    /// Blocked tasks can't be done before their blockers are.
+    pub fn is_blocked(&self) -> bool {
+        self.open_blockers > 0
+    }
+
+    /// The due date view an open task shows up in today, used to highlight it.
+    pub fn due_state(&self) -> Option<DueFilter> {
+        match self.done {
+            true => None,
+            false => self
+                .due
+                .and_then(|due| DueFilter::of(due, chrono::Utc::now().date_naive())),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct TaskModel {
     pub id: i64,
     pub name: String,
+    /// Markdown
     pub description: String,
+    #[schema(value_type = String, format = DateTime)]
     pub created: super::time::DateTime,
-    pub due: super::time::DateTime,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub due: Option<super::time::DateTime>,
     pub done: bool,
+    pub column_id: Option<i64>,
+    pub status: Option<String>,
+    pub project_id: Option<i64>,
+    pub project: Option<String>,
+    pub parent_id: Option<i64>,
+    /// Name of the parent task, if the requesting user can see it
+    pub parent: Option<String>,
+    /// The direct subtasks the requesting user can see
+    pub subtasks: Vec<TaskHeader>,
+    /// Tasks that have to be done before this one, as far as the requesting user can see them
+    pub blockers: Vec<TaskHeader>,
+    /// Tasks waiting for this one
+    pub dependents: Vec<TaskHeader>,
+    pub priority: Priority,
+    pub recurrence: Option<Recurrence>,
+    /// When the next occurrence would be due if the task was done today
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub next_occurrence: Option<super::time::DateTime>,
+    /// Seconds tracked on the task by everybody, running timers up to now
+    pub tracked: i64,
+    pub access: Access,
     pub todos: Vec<super::todo::TodoItem>,
+    pub tags: Vec<super::tag::Tag>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct CreateTask {
     pub name: String,
+    /// Defaults to the oldest project that is not archived
+    pub project_id: Option<i64>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub due: Option<super::time::DateTime>,
+    pub recurrence: Option<Recurrence>,
+    /// Makes the task a subtask of one of your tasks, in the project of the parent
+    /// unless `project_id` is given
+    pub parent_id: Option<i64>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// Blocker form of the details dialog.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct AddBlocker {
+    pub blocker_id: i64,
+}
+
+/// Subtask form of the details dialog.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct CreateSubtask {
+    pub name: String,
+}
+
+/// Tells a field set to `null` apart from a field left out, which stays `None`.
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Fields left out keep their current value.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct UpdateTask {
     pub name: Option<String>,
     pub description: Option<String>,
-    pub due: Option<super::time::DateTime>,
+    /// `null` removes the due date
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(value_type = Option<String>, format = DateTime, nullable)]
+    pub due: Option<Option<super::time::DateTime>>,
     pub done: Option<bool>,
+    /// Moves the task to another project of its owner
+    pub project_id: Option<i64>,
+    pub priority: Option<Priority>,
+    /// `null` stops the task from recurring
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(nullable)]
+    pub recurrence: Option<Option<Recurrence>>,
+    /// Moves the task below another task of its owner, `null` makes it a top level task
+    #[serde(
+        default,
+        deserialize_with = "double_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schema(nullable)]
+    pub parent_id: Option<Option<i64>>,
+}
+
+/// Access of a user to a task, ordered from least to most.
+/// Shares grant `read` or `write`, only the owner may delete and share a task.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum Access {
+    Read,
+    Write,
+    Owner,
+}
+
+impl Access {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Owner => "owner",
+        }
+    }
+
+    pub fn can_write(self) -> bool {
+        self >= Self::Write
+    }
+
+    pub fn is_owner(self) -> bool {
+        self == Self::Owner
+    }
+}
+
+/// How important a task or todo is, ordered from least to most.
+/// Stored as its rank, so sorting by priority is sorting by the column.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[repr(i64)]
+pub enum Priority {
+    #[default]
+    None = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+    Urgent = 4,
+}
+
+impl Priority {
+    /// Every priority, from least to most important.
+    pub const ALL: [Priority; 5] = [
+        Self::None,
+        Self::Low,
+        Self::Medium,
+        Self::High,
+        Self::Urgent,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Urgent => "urgent",
+        }
+    }
+}
+
+/// Views of the open tasks by due date, in whole UTC days.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DueFilter {
+    /// Due before today
+    Overdue,
+    /// Due from today until the end of the week, on Sunday
+    Week,
+}
+
+fn start_of(date: NaiveDate) -> super::time::DateTime {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+impl DueFilter {
+    pub const ALL: [DueFilter; 2] = [Self::Overdue, Self::Week];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Overdue => "overdue",
+            Self::Week => "week",
+        }
+    }
+
+    /// The due dates covered on `today`, the end is exclusive.
+    pub fn range(self, today: NaiveDate) -> (Option<super::time::DateTime>, super::time::DateTime) {
+        match self {
+            Self::Overdue => (None, start_of(today)),
+            Self::Week => {
+                let days_left = 7 - u64::from(today.weekday().num_days_from_monday());
+                (
+                    Some(start_of(today)),
+                    start_of(today + Days::new(days_left)),
+                )
+            }
+        }
+    }
+
+    /// The view a task due at `due` falls into on `today`, if any.
+    pub fn of(due: super::time::DateTime, today: NaiveDate) -> Option<Self> {
+        Self::ALL.into_iter().find(|filter| {
+            let (from, to) = filter.range(today);
+            from.is_none_or(|from| due >= from) && due < to
+        })
+    }
+}
+
+/// Views of the tasks by how many of their linked todos are done.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoProgress {
+    /// None of the linked todos is done, or there are none
+    NotStarted,
+    /// Some of the linked todos are done
+    InProgress,
+    /// All of the linked todos are done
+    Complete,
+}
+
+impl TodoProgress {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NotStarted => "not_started",
+            Self::InProgress => "in_progress",
+            Self::Complete => "complete",
+        }
+    }
+
+    pub fn of(done: i64, total: i64) -> Self {
+        match done {
+            0 => Self::NotStarted,
+            done if done < total => Self::InProgress,
+            _ => Self::Complete,
+        }
+    }
+
+    /// The condition narrowing the tasks `t` down to this view, trashed todos don't count.
+    pub fn condition(self) -> &'static str {
+        match self {
+            Self::NotStarted => {
+                r#" AND NOT EXISTS (
+                    SELECT 1 FROM tasktodos tt JOIN todos o ON o.id = tt.todo_id
+                    WHERE tt.task_id = t.id AND o.deleted_at IS NULL AND o.done
+                )"#
+            }
+            Self::InProgress => {
+                r#" AND EXISTS (
+                    SELECT 1 FROM tasktodos tt JOIN todos o ON o.id = tt.todo_id
+                    WHERE tt.task_id = t.id AND o.deleted_at IS NULL AND o.done
+                ) AND EXISTS (
+                    SELECT 1 FROM tasktodos tt JOIN todos o ON o.id = tt.todo_id
+                    WHERE tt.task_id = t.id AND o.deleted_at IS NULL AND NOT o.done
+                )"#
+            }
+            Self::Complete => {
+                r#" AND EXISTS (
+                    SELECT 1 FROM tasktodos tt JOIN todos o ON o.id = tt.todo_id
+                    WHERE tt.task_id = t.id AND o.deleted_at IS NULL
+                ) AND NOT EXISTS (
+                    SELECT 1 FROM tasktodos tt JOIN todos o ON o.id = tt.todo_id
+                    WHERE tt.task_id = t.id AND o.deleted_at IS NULL AND NOT o.done
+                )"#
+            }
+        }
+    }
+}
+
+/// Orders of the task and todo lists, each one falls back to the next most useful order.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Most important first
+    Priority,
+    /// Soonest due first
+    Due,
+    /// Newest first
+    Created,
+    /// Alphabetical
+    Name,
+}
+
+impl SortBy {
+    pub const ALL: [SortBy; 4] = [Self::Priority, Self::Due, Self::Created, Self::Name];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Priority => "priority",
+            Self::Due => "due",
+            Self::Created => "created",
+            Self::Name => "name",
+        }
+    }
+}
+
+/// Another user a task is shared with.
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct TaskShare {
+    pub user_id: i64,
+    pub user_name: String,
+    pub access: Access,
+}
+
+/// All shares of a task after a change to them.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct TaskShares {
+    pub shares: Vec<TaskShare>,
+    /// Pass to `POST /undo/{token}` to revert the change, left out if nothing changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ShareTask {
+    /// Name of the user to share with
+    pub user: String,
+    /// `read` or `write`
+    pub access: Access,
+}
+
+/// Task edit form as submitted by the browser, with the due date
+/// as `<input type="date">` value and `done` as checkbox state.
+/// `repeat` selects the kind of recurrence, the `repeat_*` fields hold its settings.
+#[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
+pub struct UpdateTaskRaw {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub due: Option<String>,
+    pub done: Option<String>,
+    pub project_id: Option<String>,
+    /// Empty for a top level task
+    pub parent_id: Option<String>,
+    pub priority: Option<Priority>,
+    pub repeat: Option<String>,
+    /// One checkbox per weekday
+    pub repeat_mo: Option<String>,
+    pub repeat_tu: Option<String>,
+    pub repeat_we: Option<String>,
+    pub repeat_th: Option<String>,
+    pub repeat_fr: Option<String>,
+    pub repeat_sa: Option<String>,
+    pub repeat_su: Option<String>,
+    pub repeat_day: Option<String>,
+    pub repeat_days: Option<String>,
+    pub repeat_rule: Option<String>,
+}
+
+fn parse_count(field: Option<&str>, name: &str) -> Result<u32, String> {
+    let value = field.map(str::trim).unwrap_or_default();
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {name} {value}, expected a positive number"))
+}
+
+impl TryFrom<UpdateTaskRaw> for UpdateTask {
+    /// Human readable reason the form was rejected
+    type Error = String;
+
+    fn try_from(value: UpdateTaskRaw) -> Result<Self, Self::Error> {
+        // A cleared date input is submitted empty and removes the due date
+        let due = match value.due.as_deref().map(str::trim) {
+            None => None,
+            Some("") => Some(None),
+            Some(due) => Some(Some(start_of(
+                NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                    .map_err(|_| format!("Invalid due date {due}, expected YYYY-MM-DD"))?,
+            ))),
+        };
+        let done = matches!(value.done.as_deref(), Some("on"));
+        let project_id = match value.project_id.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(id) => Some(
+                id.parse::<i64>()
+                    .map_err(|_| format!("Invalid project {id}"))?,
+            ),
+        };
+        let parent_id = match value.parent_id.as_deref().map(str::trim) {
+            None => None,
+            Some("") => Some(None),
+            Some(id) => Some(Some(
+                id.parse::<i64>()
+                    .map_err(|_| format!("Invalid parent task {id}"))?,
+            )),
+        };
+        let recurrence = match value.repeat.as_deref().map(str::trim) {
+            None => None,
+            Some("") => Some(None),
+            Some("daily") => Some(Some(Recurrence::Daily)),
+            Some("weekly") => Some(Some(Recurrence::Weekly {
+                weekdays: Weekday::ALL
+                    .into_iter()
+                    .zip([
+                        &value.repeat_mo,
+                        &value.repeat_tu,
+                        &value.repeat_we,
+                        &value.repeat_th,
+                        &value.repeat_fr,
+                        &value.repeat_sa,
+                        &value.repeat_su,
+                    ])
+                    .filter(|(_, checked)| matches!(checked.as_deref(), Some("on")))
+                    .map(|(weekday, _)| weekday)
+                    .collect(),
+            })),
+            Some("monthly") => Some(Some(Recurrence::Monthly {
+                day: parse_count(value.repeat_day.as_deref(), "day of month")?,
+            })),
+            Some("after_completion") => Some(Some(Recurrence::AfterCompletion {
+                days: parse_count(value.repeat_days.as_deref(), "number of days")?,
+            })),
+            Some("rule") => Some(Some(Recurrence::Rule {
+                rrule: value.repeat_rule.unwrap_or_default(),
+            })),
+            Some(other) => return Err(format!("Unknown recurrence {other}")),
+        };
+
+        Ok(UpdateTask {
+            name: value.name,
+            description: value.description,
+            due,
+            done: Some(done),
+            project_id,
+            priority: value.priority,
+            recurrence,
+            parent_id,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskQuery {
+    /// Only tasks carrying all of these tag ids
+    #[serde(default)]
+    pub tags: Vec<i64>,
+    /// Only tasks of this project, without it tasks of archived projects are left out
+    pub project: Option<i64>,
+    /// Order of the tasks, in creation order if left out
+    pub sort: Option<SortBy>,
+    /// Only open tasks due in this view
+    pub due: Option<DueFilter>,
+    /// Only open tasks whose blockers are all done
+    #[serde(default)]
+    pub ready: bool,
+    /// Only tasks whose linked todos are this far
+    pub progress: Option<TodoProgress>,
 }
@@ -1,61 +1,77 @@
-use crate::error::ApiError;
-
 #[derive(serde::Deserialize)]
 pub struct TodoItems {
     pub items: Vec<TodoItem>,
 }
 
 #[derive(
-    serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Debug, Default, sqlx::FromRow,
+    serde::Deserialize,
+    serde::Serialize,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Default,
+    sqlx::FromRow,
+    utoipa::ToSchema,
 )]
 pub struct TodoItem {
     pub id: i64,
     pub name: String,
     pub done: bool,
+    pub priority: super::task::Priority,
+    #[sqlx(skip)]
+    pub tags: Vec<super::tag::Tag>,
 }
 
-impl From<(i64, String, bool)> for TodoItem {
-    fn from(value: (i64, String, bool)) -> Self {
-        Self {
-            id: value.0,
-            name: value.1,
-            done: value.2,
-        }
-    }
-}
-
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct CreateTodo {
     pub name: String,
+    #[serde(default)]
+    pub priority: super::task::Priority,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
 pub struct UpdateTodoRaw {
     pub name: Option<String>,
     pub done: Option<String>,
+    pub priority: Option<super::task::Priority>,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
+/// Fields left out keep their current value.
+#[derive(serde::Deserialize, serde::Serialize, Default, Debug, utoipa::ToSchema)]
 pub struct UpdateTodo {
     pub name: Option<String>,
-    pub done: bool,
+    pub done: Option<bool>,
+    pub priority: Option<super::task::Priority>,
 }
 
 impl From<UpdateTodoRaw> for UpdateTodo {
     fn from(value: UpdateTodoRaw) -> Self {
-        let done = match value.done.as_deref() {
-            Some("on") => true,
-            _ => false,
-        };
+        // An unchecked checkbox is not submitted at all
+        let done = matches!(value.done.as_deref(), Some("on"));
 
         UpdateTodo {
             name: value.name,
-            done,
+            done: Some(done),
+            priority: value.priority,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TodoQuery {
     pub done: Option<bool>,
+    /// Only todos carrying all of these tag ids
+    #[serde(default)]
+    pub tags: Vec<i64>,
+    /// Order of the todos, in creation order if left out.
+    /// The due date of a todo is the earliest one of the tasks it is linked to.
+    pub sort: Option<super::task::SortBy>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct TodoSearch {
+    #[serde(default)]
+    pub q: String,
 }
@@ -0,0 +1,124 @@
+use chrono::NaiveDate;
+
+/// Time tracked on a task, by a timer or entered by hand.
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub task_id: i64,
+    /// Name of the task
+    pub task: String,
+    pub user_id: i64,
+    /// Name of the user who tracked the time
+    pub user: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub started: super::time::DateTime,
+    /// Missing while the timer is running
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub stopped: Option<super::time::DateTime>,
+    /// Tracked so far, for running timers up to now
+    #[sqlx(skip)]
+    pub seconds: i64,
+    pub note: String,
+}
+
+impl TimeEntry {
+    pub fn is_running(&self) -> bool {
+        self.stopped.is_none()
+    }
+}
+
+/// Time tracked by hand, it ends `minutes` after `started`.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct CreateTimeEntry {
+    #[schema(value_type = String, format = DateTime)]
+    pub started: super::time::DateTime,
+    pub minutes: i64,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Manual time entry form as submitted by the browser, the time is tracked on `date`.
+#[derive(serde::Deserialize, serde::Serialize, Default, Debug)]
+pub struct CreateTimeEntryRaw {
+    pub date: String,
+    pub hours: Option<String>,
+    pub minutes: Option<String>,
+    #[serde(default)]
+    pub note: String,
+}
+
+fn parse_amount(value: Option<&str>, name: &str) -> Result<i64, String> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(0),
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Invalid number of {name} {value}")),
+    }
+}
+
+impl TryFrom<CreateTimeEntryRaw> for CreateTimeEntry {
+    /// Human readable reason the form was rejected
+    type Error = String;
+
+    fn try_from(value: CreateTimeEntryRaw) -> Result<Self, Self::Error> {
+        let date = value.date.trim();
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date {date}, expected YYYY-MM-DD"))?;
+        let hours = parse_amount(value.hours.as_deref(), "hours")?;
+        let minutes = parse_amount(value.minutes.as_deref(), "minutes")?;
+        let minutes = hours
+            .checked_mul(60)
+            .and_then(|hours| hours.checked_add(minutes))
+            .ok_or_else(|| format!("Too much time, {hours} hours and {minutes} minutes"))?;
+
+        Ok(CreateTimeEntry {
+            started: date.and_time(chrono::NaiveTime::MIN).and_utc(),
+            minutes,
+            note: value.note,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimesheetQuery {
+    /// Any day of the week, the current week if left out
+    pub week: Option<NaiveDate>,
+}
+
+/// Time tracked on a task or tag, per day of the week.
+#[derive(serde::Serialize, serde::Deserialize, Clone, utoipa::ToSchema)]
+pub struct TimesheetRow {
+    /// Task or tag id, missing for the time on untagged tasks
+    pub id: Option<i64>,
+    pub name: String,
+    /// Seconds per day, from Monday to Sunday
+    pub days: Vec<i64>,
+    /// Seconds in the whole week
+    pub total: i64,
+}
+
+/// The time a user tracked in one week, in UTC days.
+/// Entries count on the day they started.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Timesheet {
+    /// The Monday the week starts on
+    #[schema(value_type = String, format = Date)]
+    pub week: NaiveDate,
+    #[schema(value_type = Vec<String>)]
+    pub days: Vec<NaiveDate>,
+    pub tasks: Vec<TimesheetRow>,
+    /// Tasks with several tags count towards each of them
+    pub tags: Vec<TimesheetRow>,
+    /// Seconds per day of all tasks
+    pub totals: Vec<i64>,
+    pub total: i64,
+    /// The timer the user is running, in any week
+    pub running: Option<TimeEntry>,
+}
+
+/// Tracked time as hours and minutes, like `1:05`.
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
@@ -0,0 +1,55 @@
+/// What kind of item is in the trash.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum TrashKind {
+    Task,
+    Todo,
+}
+
+impl TrashKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Task => "task",
+            Self::Todo => "todo",
+        }
+    }
+}
+
+impl From<TrashKind> for super::audit::AuditEntity {
+    fn from(kind: TrashKind) -> Self {
+        match kind {
+            TrashKind::Task => Self::Task,
+            TrashKind::Todo => Self::Todo,
+        }
+    }
+}
+
+impl From<super::audit::AuditEntity> for TrashKind {
+    fn from(entity: super::audit::AuditEntity) -> Self {
+        match entity {
+            super::audit::AuditEntity::Task => Self::Task,
+            super::audit::AuditEntity::Todo => Self::Todo,
+        }
+    }
+}
+
+/// A deleted task or todo, it can be restored until it is purged.
+#[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub id: i64,
+    pub name: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub deleted_at: super::time::DateTime,
+}
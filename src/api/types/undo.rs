@@ -0,0 +1,31 @@
+use super::{board::Column, task::TaskModel, todo::TodoItem};
+
+/// The result of an undoable change, together with the token that undoes it.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[aliases(
+    UndoableTodo = WithUndo<TodoItem>,
+    UndoableTask = WithUndo<TaskModel>,
+    UndoableColumn = WithUndo<Column>
+)]
+pub struct WithUndo<T> {
+    #[serde(flatten)]
+    pub item: T,
+    /// Pass to `POST /undo/{token}` to revert the change, left out if nothing changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo: Option<String>,
+}
+
+/// Answer to an undoable change that has nothing else to report.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct UndoToken {
+    /// Pass to `POST /undo/{token}` to revert the change, left out if nothing changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo: Option<String>,
+}
+
+/// A change that was undone.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct UndoneStep {
+    /// What the change did, as in "Deleted todo Milk"
+    pub label: String,
+}
@@ -0,0 +1,36 @@
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, sqlx::FromRow)]
+pub struct User {
+    pub id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub created: Option<super::time::DateTime>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct RegisterUser {
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+    pub password: String,
+    pub password_confirm: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct LoginUser {
+    pub name: String,
+    pub password: String,
+    /// Where to go after a successful login
+    pub next: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ChangePassword {
+    pub current: String,
+    pub password: String,
+    pub password_confirm: String,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct LoginQuery {
+    pub next: Option<String>,
+}
@@ -0,0 +1,260 @@
+use std::{path::PathBuf, sync::Arc};
+
+use axum::{
+    extract::multipart::Field,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{RwLock, RwLockReadGuard},
+};
+
+use crate::{api::types::attachment::Attachment, config::Config};
+
+/// Suffix of files still being uploaded, the orphan sweep leaves them alone.
+const PARTIAL_SUFFIX: &str = ".part";
+/// Length of the random hex names files are stored under.
+const STORED_NAME_LEN: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum AttachmentError {
+    #[error("Attachment IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Attachment SQL error: {0}")]
+    SQLError(#[from] sqlx::Error),
+    #[error("Invalid upload: {0}")]
+    Upload(String),
+    #[error("{name} is larger than the limit of {max_mb} MB")]
+    TooLarge { name: String, max_mb: u64 },
+}
+
+/// A file written into the attachment directory, not yet visible under its stored name.
+pub(crate) struct StoredFile {
+    pub(crate) stored_name: String,
+    pub(crate) size: u64,
+    /// Hex encoded
+    pub(crate) sha256: String,
+}
+
+/// Task attachments on disk, the metadata lives in the `attachments` table.
+/// Files are stored under random names, so user supplied names never end up in a path.
+#[derive(Clone)]
+pub(crate) struct AttachmentStore {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    dir: PathBuf,
+    max_bytes: u64,
+    /// Shared by uploads adding files, taken exclusively by the orphan sweep.
+    sweep: RwLock<()>,
+}
+
+/// 128 random bits, hex encoded.
+fn new_stored_name() -> String {
+    let mut bytes = [0u8; STORED_NAME_LEN / 2];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Whether a file in the directory was written by the store.
+fn is_stored_name(file_name: &str) -> bool {
+    file_name.len() == STORED_NAME_LEN
+        && file_name
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+impl AttachmentStore {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                dir: PathBuf::from(&config.attachments_dir),
+                max_bytes: config.attachment_max_mb.max(1) * 1024 * 1024,
+                sweep: RwLock::new(()),
+            }),
+        }
+    }
+
+    /// Largest file that can be uploaded.
+    pub(crate) fn max_bytes(&self) -> u64 {
+        self.inner.max_bytes
+    }
+
+    pub(crate) fn path_of(&self, stored_name: &str) -> PathBuf {
+        self.inner.dir.join(stored_name)
+    }
+
+    fn partial_path_of(&self, stored_name: &str) -> PathBuf {
+        self.inner
+            .dir
+            .join(format!("{stored_name}{PARTIAL_SUFFIX}"))
+    }
+
+    /// Streams an uploaded file to disk while hashing it.
+    /// The file stays partial until [`Self::commit`] is called for it.
+    pub(crate) async fn write(
+        &self,
+        name: &str,
+        field: &mut Field<'_>,
+    ) -> Result<StoredFile, AttachmentError> {
+        tokio::fs::create_dir_all(&self.inner.dir).await?;
+        let stored_name = new_stored_name();
+        let path = self.partial_path_of(&stored_name);
+        let written = self.write_to(&path, name, field).await;
+        if written.is_err() {
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+        let (size, sha256) = written?;
+
+        Ok(StoredFile {
+            stored_name,
+            size,
+            sha256,
+        })
+    }
+
+    async fn write_to(
+        &self,
+        path: &std::path::Path,
+        name: &str,
+        field: &mut Field<'_>,
+    ) -> Result<(u64, String), AttachmentError> {
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| AttachmentError::Upload(e.body_text()))?
+        {
+            size += chunk.len() as u64;
+            if size > self.inner.max_bytes {
+                return Err(AttachmentError::TooLarge {
+                    name: name.to_string(),
+                    max_mb: self.inner.max_bytes / (1024 * 1024),
+                });
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.sync_all().await?;
+
+        let sha256 = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        Ok((size, sha256))
+    }
+
+    /// Keeps the orphan sweep from running while a file and its metadata are added.
+    pub(crate) async fn lock_sweep(&self) -> RwLockReadGuard<'_, ()> {
+        self.inner.sweep.read().await
+    }
+
+    /// Makes a written file available under its stored name,
+    /// once its metadata is in the database.
+    pub(crate) async fn commit(&self, file: &StoredFile) -> Result<(), AttachmentError> {
+        tokio::fs::rename(
+            self.partial_path_of(&file.stored_name),
+            self.path_of(&file.stored_name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a partial or stored file, missing files are fine.
+    pub(crate) async fn remove(&self, stored_name: &str) {
+        for path in [self.partial_path_of(stored_name), self.path_of(stored_name)] {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => tracing::warn!("Failed to remove attachment {}: {e}", path.display()),
+            }
+        }
+    }
+
+    /// Removes stored files without metadata, left behind by deleted tasks.
+    /// Returns how many files were removed.
+    pub(crate) async fn remove_orphans(
+        &self,
+        pool: &sqlx::SqlitePool,
+    ) -> Result<usize, AttachmentError> {
+        let _sweeping = self.inner.sweep.write().await;
+        let mut entries = match tokio::fs::read_dir(&self.inner.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut removed = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !is_stored_name(&file_name) {
+                continue;
+            }
+            let known: bool = sqlx::query_scalar(
+                "SELECT EXISTS(SELECT 1 FROM attachments WHERE stored_name = (?1));",
+            )
+            .bind(&file_name)
+            .fetch_one(pool)
+            .await?;
+            if !known {
+                tokio::fs::remove_file(entry.path()).await?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            tracing::info!("Removed {removed} orphaned attachments");
+        }
+        Ok(removed)
+    }
+
+    /// [`Self::remove_orphans`] after tasks are gone, failures are only logged.
+    pub(crate) async fn clean_up(&self, pool: &sqlx::SqlitePool) {
+        if let Err(e) = self.remove_orphans(pool).await {
+            tracing::warn!("Failed to clean up attachments: {e}");
+        }
+    }
+}
+
+/// `Content-Disposition` of a download, with a plain fallback name for old clients.
+fn content_disposition(name: &str) -> String {
+    let fallback: String = name
+        .chars()
+        .map(|c| match c.is_ascii_graphic() && c != '"' && c != '\\' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    let encoded: String = name
+        .bytes()
+        .map(
+            |byte| match byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                true => (byte as char).to_string(),
+                false => format!("%{byte:02X}"),
+            },
+        )
+        .collect();
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+/// Sends an attachment as download, never rendered inline so uploaded html can't run.
+pub(crate) fn download(attachment: &Attachment, content: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, attachment.mime.clone()),
+            (
+                header::CONTENT_DISPOSITION,
+                content_disposition(&attachment.name),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        content,
+    )
+        .into_response()
+}
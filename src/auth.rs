@@ -0,0 +1,174 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, OriginalUri, Request, State},
+    http::{request::Parts, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+
+use crate::{
+    api::types::{time::DateTime, user::User},
+    config::Config,
+    data_access::utils::now,
+    models,
+    state::AppState,
+};
+
+pub(crate) const SESSION_COOKIE: &str = "kanbasta_session";
+pub(crate) const LOGIN_PATH: &str = "/login";
+
+/// Settings for issuing session cookies.
+#[derive(Clone)]
+pub(crate) struct Sessions {
+    lifetime_days: i64,
+    secure: bool,
+}
+
+impl Sessions {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            lifetime_days: config.session_days.max(1),
+            secure: config.secure_cookies,
+        }
+    }
+
+    pub(crate) fn expires(&self) -> DateTime {
+        now() + chrono::Duration::days(self.lifetime_days)
+    }
+
+    /// Starts a session for `user_id` and adds its cookie to `jar`.
+    pub(crate) async fn start(
+        &self,
+        pool: &sqlx::SqlitePool,
+        jar: CookieJar,
+        user_id: i64,
+    ) -> Result<CookieJar, sqlx::Error> {
+        // Piggyback cleanup on logins, so no extra schedule is needed
+        models::session::purge_expired(pool).await?;
+        let token = models::session::create(pool, user_id, self.expires()).await?;
+        let cookie = Cookie::build((SESSION_COOKIE, token))
+            .path("/")
+            .http_only(true)
+            .secure(self.secure)
+            .same_site(SameSite::Lax)
+            .max_age(time::Duration::days(self.lifetime_days))
+            .build();
+        Ok(jar.add(cookie))
+    }
+
+    /// Ends the session in `jar`, if any, and removes its cookie.
+    pub(crate) async fn end(
+        &self,
+        pool: &sqlx::SqlitePool,
+        jar: CookieJar,
+    ) -> Result<CookieJar, sqlx::Error> {
+        if let Some(token) = token(&jar) {
+            models::session::delete(pool, token).await?;
+        }
+        Ok(jar.remove(Cookie::build(SESSION_COOKIE).path("/")))
+    }
+}
+
+pub(crate) fn token(jar: &CookieJar) -> Option<&str> {
+    jar.get(SESSION_COOKIE).map(|cookie| cookie.value())
+}
+
+/// The logged in user, put into the request by [`require_user`] or [`require_api_user`].
+#[derive(Clone, Debug)]
+pub(crate) struct CurrentUser(pub User);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// The user the session cookie in `jar` belongs to, if any.
+pub(crate) async fn lookup(state: &AppState, jar: &CookieJar) -> Result<Option<User>, sqlx::Error> {
+    match token(jar) {
+        Some(token) => models::session::user_for(&state.db.pool(), token).await,
+        None => Ok(None),
+    }
+}
+
+/// Lets only logged in users through, everybody else is sent to the login page.
+/// Htmx requests get a `HX-Redirect` instead, so the whole page navigates.
+pub(crate) async fn require_user(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    match lookup(&state, &jar).await {
+        Ok(Some(user)) => {
+            request.extensions_mut().insert(CurrentUser(user));
+            next.run(request).await
+        }
+        Ok(None) => {
+            let login = format!("{LOGIN_PATH}?next={}", return_path(&request));
+            if request.headers().contains_key("hx-request") {
+                (StatusCode::UNAUTHORIZED, [("hx-redirect", login)]).into_response()
+            } else {
+                Redirect::to(&login).into_response()
+            }
+        }
+        Err(e) => crate::error::ApiError::from(e).into_response(),
+    }
+}
+
+/// The page to come back to after logging in.
+/// For htmx requests that is the page the partial was requested from.
+fn return_path(request: &Request) -> String {
+    let current_page = request
+        .headers()
+        .get("hx-current-url")
+        .and_then(|url| url.to_str().ok())
+        .and_then(|url| url.parse::<Uri>().ok());
+    match current_page {
+        Some(url) => url.path().to_string(),
+        None => request
+            .extensions()
+            .get::<OriginalUri>()
+            .map(|uri| uri.path().to_string())
+            .unwrap_or_else(|| request.uri().path().to_string()),
+    }
+}
+
+/// Json flavour of [`require_user`], answering with `401` instead of a redirect.
+pub(crate) async fn require_api_user(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    use crate::api::json::error::JsonError;
+
+    match lookup(&state, &jar).await {
+        Ok(Some(user)) => {
+            request.extensions_mut().insert(CurrentUser(user));
+            next.run(request).await
+        }
+        Ok(None) => JsonError::Rejection(
+            StatusCode::UNAUTHORIZED,
+            format!("Log in at {LOGIN_PATH} first"),
+        )
+        .into_response(),
+        Err(e) => JsonError::from(e).into_response(),
+    }
+}
+
+/// Only local paths are followed after a login, so the login page can't be used as open redirect.
+pub(crate) fn local_path(next: Option<&str>) -> &str {
+    match next {
+        Some(path) if path.starts_with('/') && !path.starts_with("//") => path,
+        _ => "/todos",
+    }
+}
@@ -0,0 +1,295 @@
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, Connection, Row};
+use tokio::sync::Mutex;
+
+use crate::{
+    api::types::user::User,
+    config::Config,
+    data_access::utils::{now, DateTime},
+    database::Database,
+};
+
+const FILE_PREFIX: &str = "kanbasta-";
+const FILE_EXTENSION: &str = "db";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const PRE_RESTORE_SUFFIX: &str = "-pre-restore";
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum BackupError {
+    #[error("Backup IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Backup SQL error: {0}")]
+    SQLError(#[from] sqlx::Error),
+    #[error("Backup {0} failed the integrity check: {1}")]
+    Integrity(String, String),
+}
+
+/// A snapshot file found in the backup directory.
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct BackupFile {
+    pub(crate) file_name: String,
+    pub(crate) created: DateTime,
+    pub(crate) size: u64,
+    /// Safety snapshots taken right before a restore, these are never pruned
+    pub(crate) pre_restore: bool,
+}
+
+impl BackupFile {
+    fn file_name_for(created: DateTime, pre_restore: bool) -> String {
+        format!(
+            "{FILE_PREFIX}{}{}.{FILE_EXTENSION}",
+            created.format(TIMESTAMP_FORMAT),
+            if pre_restore { PRE_RESTORE_SUFFIX } else { "" }
+        )
+    }
+
+    /// Parses creation time and kind back out of a file name written by [`BackupService`].
+    fn parse_file_name(file_name: &str) -> Option<(DateTime, bool)> {
+        let stem = file_name
+            .strip_prefix(FILE_PREFIX)?
+            .strip_suffix(FILE_EXTENSION)?
+            .strip_suffix('.')?;
+        let (timestamp, pre_restore) = match stem.strip_suffix(PRE_RESTORE_SUFFIX) {
+            Some(timestamp) => (timestamp, true),
+            None => (stem, false),
+        };
+        NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+            .ok()
+            .map(|naive| (Utc.from_utc_datetime(&naive), pre_restore))
+    }
+}
+
+/// Outcome of the most recent backup run, shown in the UI.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BackupStatus {
+    pub(crate) last_success: Option<BackupFile>,
+    pub(crate) last_failure: Option<(DateTime, String)>,
+}
+
+#[derive(Clone)]
+pub(crate) struct BackupService {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    dir: PathBuf,
+    generations: usize,
+    interval: Option<Duration>,
+    /// Names of the users allowed to restore
+    admins: Vec<String>,
+    /// Held for the whole duration of a backup, so runs never overlap.
+    status: Mutex<BackupStatus>,
+}
+
+impl BackupService {
+    pub(crate) fn new(config: &Config) -> Self {
+        let interval = match config.backup_interval_hours {
+            0 => None,
+            hours => Some(Duration::from_secs(hours * 60 * 60)),
+        };
+
+        Self {
+            inner: Arc::new(Inner {
+                dir: PathBuf::from(&config.backup_dir),
+                generations: config.backup_generations.max(1),
+                interval,
+                admins: config.admins.clone(),
+                status: Mutex::new(BackupStatus::default()),
+            }),
+        }
+    }
+
+    pub(crate) fn dir(&self) -> &std::path::Path {
+        &self.inner.dir
+    }
+
+    pub(crate) fn generations(&self) -> usize {
+        self.inner.generations
+    }
+
+    pub(crate) fn interval(&self) -> Option<Duration> {
+        self.inner.interval
+    }
+
+    /// Restoring replaces everybody's data, so only admins from the config may do it.
+    pub(crate) fn may_restore(&self, user: &User) -> bool {
+        self.inner.admins.contains(&user.name)
+    }
+
+    pub(crate) async fn status(&self) -> BackupStatus {
+        self.inner.status.lock().await.clone()
+    }
+
+    /// Full path of a snapshot in the backup directory.
+    pub(crate) fn path_of(&self, file: &BackupFile) -> PathBuf {
+        self.inner.dir.join(&file.file_name)
+    }
+
+    /// All snapshots in the backup directory, newest first.
+    pub(crate) async fn list(&self) -> Result<Vec<BackupFile>, BackupError> {
+        let mut files = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.inner.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if let Some((created, pre_restore)) = BackupFile::parse_file_name(&file_name) {
+                files.push(BackupFile {
+                    file_name,
+                    created,
+                    size: entry.metadata().await?.len(),
+                    pre_restore,
+                });
+            }
+        }
+
+        files.sort_by_key(|file| std::cmp::Reverse(file.created));
+        Ok(files)
+    }
+
+    /// Looks up a snapshot by its file name.
+    /// Only files actually listed in the backup directory are ever returned,
+    /// so a user supplied name can not point anywhere else.
+    pub(crate) async fn find(&self, file_name: &str) -> Result<Option<BackupFile>, BackupError> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|file| file.file_name == file_name))
+    }
+
+    /// Writes a verified snapshot of the database into the backup directory
+    /// and prunes generations beyond the configured limit.
+    pub(crate) async fn backup_now(
+        &self,
+        pool: &sqlx::SqlitePool,
+    ) -> Result<BackupFile, BackupError> {
+        let mut status = self.inner.status.lock().await;
+
+        match self.write_snapshot(pool, false).await {
+            Ok(file) => {
+                tracing::info!("Wrote backup {}", file.file_name);
+                status.last_success = Some(file.clone());
+                if let Err(e) = self.prune().await {
+                    tracing::warn!("Failed to prune old backups: {e}");
+                }
+                Ok(file)
+            }
+            Err(e) => {
+                tracing::error!("{e}");
+                status.last_failure = Some((now(), e.to_string()));
+                Err(e)
+            }
+        }
+    }
+
+    /// Writes a snapshot of the live database that is kept until removed by hand.
+    pub(crate) async fn safety_snapshot(
+        &self,
+        pool: &sqlx::SqlitePool,
+    ) -> Result<BackupFile, BackupError> {
+        let _guard = self.inner.status.lock().await;
+        self.write_snapshot(pool, true).await
+    }
+
+    async fn write_snapshot(
+        &self,
+        pool: &sqlx::SqlitePool,
+        pre_restore: bool,
+    ) -> Result<BackupFile, BackupError> {
+        tokio::fs::create_dir_all(&self.inner.dir).await?;
+
+        let created = now();
+        let file_name = BackupFile::file_name_for(created, pre_restore);
+        let path = self.inner.dir.join(&file_name);
+        // Written under a temporary name first, so an interrupted or corrupt
+        // snapshot is never picked up as a valid generation.
+        let partial = path.with_extension("partial");
+        let _ = tokio::fs::remove_file(&partial).await;
+
+        // `VACUUM INTO` runs inside a read transaction, so the snapshot is consistent
+        // even while other connections keep writing.
+        sqlx::query("VACUUM main INTO ?1;")
+            .bind(partial.to_string_lossy().to_string())
+            .execute(pool)
+            .await?;
+
+        if let Err(e) = verify(&partial).await {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+
+        tokio::fs::rename(&partial, &path).await?;
+        let size = tokio::fs::metadata(&path).await?.len();
+
+        Ok(BackupFile {
+            file_name,
+            created,
+            size,
+            pre_restore,
+        })
+    }
+
+    async fn prune(&self) -> Result<(), BackupError> {
+        let scheduled = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|file| !file.pre_restore);
+        for file in scheduled.skip(self.inner.generations) {
+            tracing::info!("Removing old backup {}", file.file_name);
+            tokio::fs::remove_file(self.path_of(&file)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs [`Self::backup_now`] every configured interval, if there is one.
+    pub(crate) fn spawn_schedule(&self, db: Database) {
+        let Some(period) = self.inner.interval else {
+            tracing::info!("Scheduled backups are disabled");
+            return;
+        };
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            // The first tick completes immediately, skip it so
+            // restarting the server does not produce a new generation each time.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let _ = service.backup_now(&db.pool()).await;
+            }
+        });
+    }
+}
+
+/// Runs `PRAGMA integrity_check` against a snapshot file.
+pub(crate) async fn verify(path: &std::path::Path) -> Result<(), BackupError> {
+    let mut connection = SqliteConnectOptions::from_str("sqlite://")?
+        .filename(path)
+        .read_only(true)
+        .disable_statement_logging()
+        .connect()
+        .await?;
+
+    let results: Vec<String> = sqlx::query("PRAGMA integrity_check;")
+        .map(|row: sqlx::sqlite::SqliteRow| row.get(0))
+        .fetch_all(&mut connection)
+        .await?;
+    connection.close().await?;
+
+    match results.as_slice() {
+        [ok] if ok == "ok" => Ok(()),
+        _ => Err(BackupError::Integrity(
+            path.to_string_lossy().to_string(),
+            results.join("; "),
+        )),
+    }
+}
@@ -0,0 +1,112 @@
+/// What the binary was asked to do on startup.
+/// Without arguments the server is started as usual.
+pub(crate) enum Command {
+    Serve,
+    /// Print applied and pending migrations, without applying anything
+    Migrations,
+    /// Apply pending migrations and exit
+    Migrate,
+    /// Print the snapshots found in the backup directory
+    Backups,
+    /// Replace the live database with the given snapshot
+    Restore(String),
+}
+
+impl Command {
+    pub(crate) fn from_args() -> Result<Self, String> {
+        let args = std::env::args().skip(1).collect::<Vec<_>>();
+        match args
+            .iter()
+            .map(|a| a.as_str())
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [] | ["serve"] => Ok(Self::Serve),
+            ["migrations"] => Ok(Self::Migrations),
+            ["migrate"] => Ok(Self::Migrate),
+            ["backups"] => Ok(Self::Backups),
+            ["restore", file_name] => Ok(Self::Restore(file_name.to_string())),
+            _ => Err(format!(
+                "Unknown command: {}\n\n{}",
+                args.join(" "),
+                Self::usage()
+            )),
+        }
+    }
+
+    pub(crate) fn usage() -> &'static str {
+        r#"Usage: kanbasta [COMMAND]
+
+Commands:
+  serve        Start the server (default)
+  migrations   List applied and pending schema migrations
+  migrate      Apply pending schema migrations and exit
+  backups      List snapshots in the configured backup directory
+  restore FILE Restore the live database from a snapshot in the backup directory"#
+    }
+}
+
+pub(crate) async fn print_migrations(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+    let status = crate::migrations::status(pool).await?;
+    for migration in status {
+        match migration.applied {
+            Some(applied) => println!(
+                "{:>4}  {:<32} applied {}",
+                migration.version,
+                migration.name,
+                applied.format("%Y-%m-%d %H:%M:%S")
+            ),
+            None => println!("{:>4}  {:<32} pending", migration.version, migration.name),
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn print_backups(
+    backups: &crate::backup::BackupService,
+) -> Result<(), crate::backup::BackupError> {
+    println!("Backups in {}", backups.dir().display());
+    for file in backups.list().await? {
+        println!(
+            "  {:<44} {:>8} KiB{}",
+            file.file_name,
+            file.size / 1024,
+            if file.pre_restore {
+                "  (pre-restore)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn restore(
+    db: &crate::database::Database,
+    backups: &crate::backup::BackupService,
+    file_name: &str,
+) -> Result<(), crate::restore::RestoreError> {
+    let report = crate::restore::restore(db, backups, file_name).await?;
+    println!(
+        "Restored {} (schema version {})",
+        report.restored.file_name, report.schema_version
+    );
+    println!(
+        "Previous state saved as {}",
+        report.safety_snapshot.file_name
+    );
+    for table in report.tables {
+        let count = |count: Option<i64>| count.map(|c| c.to_string()).unwrap_or("-".to_string());
+        println!(
+            "  {:<20} {:>8} -> {:<8}{}",
+            table.table,
+            count(table.before),
+            count(table.after),
+            if table.changed() { "  changed" } else { "" }
+        );
+    }
+
+    Ok(())
+}
@@ -1,8 +1,26 @@
 /// Path to USB on pi: /media/lando/LANDOSTICK
 #[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub(crate) struct Config {
     pub(crate) backup_dir: String,
     pub(crate) assets_dir: String,
+    /// Hours between scheduled backups, `0` disables the schedule
+    pub(crate) backup_interval_hours: u64,
+    /// How many backup files are kept before the oldest get removed
+    pub(crate) backup_generations: usize,
+    /// Days a login stays valid
+    pub(crate) session_days: i64,
+    /// Only send the session cookie over https.
+    /// Browsers treat `localhost` as secure, disable this when serving plain http on the network.
+    pub(crate) secure_cookies: bool,
+    /// Where task attachments are stored, files without metadata in it get removed
+    pub(crate) attachments_dir: String,
+    /// Largest attachment that can be uploaded, in megabytes
+    pub(crate) attachment_max_mb: u64,
+    /// Days deleted todos and tasks stay in the trash, `0` keeps them until they are purged by hand
+    pub(crate) trash_retention_days: u64,
+    /// Names of the users allowed to restore backups, nobody can while it is empty
+    pub(crate) admins: Vec<String>,
 }
 
 impl Default for Config {
@@ -10,6 +28,14 @@ impl Default for Config {
         Self {
             backup_dir: "./".to_string(),
             assets_dir: "./assets".to_string(),
+            backup_interval_hours: 24,
+            backup_generations: 7,
+            session_days: 30,
+            secure_cookies: true,
+            attachments_dir: "./attachments".to_string(),
+            attachment_max_mb: 10,
+            trash_retention_days: 30,
+            admins: Vec::new(),
         }
     }
 }
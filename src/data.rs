@@ -64,71 +64,6 @@ pub(crate) fn now() -> DateTime {
     Utc::now()
 }
 
-pub(crate) async fn create_todo_table(pool: &sqlx::SqlitePool) {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS todos
-        (
-            id INTEGER PRIMARY KEY NOT NULL,
-            name TEXT,
-            done BOOLEAN
-        );
-        "#,
-    )
-    .execute(pool)
-    .await;
-}
-
-pub(crate) async fn create_tasks_table(pool: &sqlx::SqlitePool) {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS tasks
-        (
-            id INTEGER PRIMARY KEY NOT NULL,
-            name TEXT,
-            description TEXT,
-            created TEXT,
-            due TEXT,
-            done BOOLEAN
-        );
-        "#,
-    )
-    .execute(pool)
-    .await;
-}
-
-pub(crate) async fn create_tags_table(pool: &sqlx::SqlitePool) {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS tags
-        (
-            id INTEGER PRIMARY KEY NOT NULL,
-            name TEXT,
-            color TEXT
-        );
-        "#,
-    )
-    .execute(pool)
-    .await;
-}
-
-pub(crate) async fn create_task_todos_mapping_table(pool: &sqlx::SqlitePool) {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS tasktodos
-        (
-            task_id INTEGER NOT NULL,
-            todo_id INTEGER NOT NULL,
-            foreign key (task_id) references tasks(id),
-            foreign key (todo_id) references todos(id),
-            primary key (task_id, todo_id)
-        );
-        "#,
-    )
-    .execute(pool)
-    .await;
-}
-
 pub(crate) async fn select_todo_row(
     id: i64,
     pool: &sqlx::SqlitePool,
@@ -208,17 +143,10 @@ pub(crate) async fn query_todos_by_task(
     .await
 }
 
-pub(crate) async fn migrate_table_state(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
-    let _ = sqlx::query(
-        "CREATE TABLE IF NOT EXISTS users (id INTEGER PRIMARY KEY NOT NULL, name TEXT);",
-    )
-    .execute(pool)
-    .await?;
-
-    create_todo_table(pool).await;
-    create_tasks_table(pool).await;
-    create_tags_table(pool).await;
-    create_task_todos_mapping_table(pool).await;
+pub(crate) async fn migrate_table_state(
+    pool: &sqlx::SqlitePool,
+) -> Result<(), crate::migrations::MigrationError> {
+    crate::migrations::run(pool).await?;
     // create_mock_data(pool).await?;
 
     Ok(())
@@ -1,14 +1,19 @@
 use chrono::Utc;
 use sqlx::Row;
 
-use crate::data::{TableRow, TodoRow};
+use crate::api::types::{task::Priority, todo::TodoItem};
 
 use super::utils::DateTime;
 
-pub(crate) type TaskRow = (i64, String, String, DateTime, DateTime, bool);
-pub(crate) type TaskRowInput = (String, String, DateTime, bool);
+/// (id, name, description, created, due, done)
+pub(crate) type TaskRow = (i64, String, String, DateTime, Option<DateTime>, bool);
+/// (name, description, due, done)
+pub(crate) type TaskRowInput = (String, String, Option<DateTime>, bool);
 
-pub(crate) async fn select_row(id: i64, pool: &sqlx::SqlitePool) -> Result<TaskRow, sqlx::Error> {
+pub(crate) async fn select_row(
+    id: i64,
+    executor: impl sqlx::SqliteExecutor<'_>,
+) -> Result<TaskRow, sqlx::Error> {
     sqlx::query(
         r#"
             SELECT * FROM tasks
@@ -27,19 +32,22 @@ pub(crate) async fn select_row(id: i64, pool: &sqlx::SqlitePool) -> Result<TaskR
             row.get("done"),
         )
     })
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await
 }
 
 pub(crate) async fn insert_row(
+    owner_id: i64,
+    project_id: i64,
+    priority: Priority,
     input: TaskRowInput,
-    pool: &sqlx::SqlitePool,
+    executor: impl sqlx::SqliteExecutor<'_>,
 ) -> Result<i64, sqlx::Error> {
     let (name, description, due, done) = input;
     sqlx::query(
         r#"
-          INSERT INTO tasks (name, description, created, due, done)
-        VALUES (?1, ?2, ?3, ?4, ?5);  
+          INSERT INTO tasks (name, description, created, due, done, owner_id, project_id, priority)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);  
         "#,
     )
     .bind(name)
@@ -47,7 +55,10 @@ pub(crate) async fn insert_row(
     .bind(Utc::now())
     .bind(due)
     .bind(done)
-    .execute(pool)
+    .bind(owner_id)
+    .bind(project_id)
+    .bind(priority)
+    .execute(executor)
     .await
     .map(|result| result.last_insert_rowid())
 }
@@ -55,16 +66,92 @@ pub(crate) async fn insert_row(
 pub(crate) async fn query_todos_by_id(
     task_id: i64,
     pool: &sqlx::SqlitePool,
-) -> Result<Vec<<TodoRow as TableRow>::Data>, sqlx::Error> {
-    sqlx::query(
+) -> Result<Vec<TodoItem>, sqlx::Error> {
+    sqlx::query_as::<_, TodoItem>(
         r#"
-          SELECT * FROM todos 
+          SELECT id, name, done, priority FROM todos 
           JOIN tasktodos tt ON tt.task_id = (?1) AND tt.todo_id = id
+          WHERE deleted_at IS NULL
         ;
         "#,
     )
     .bind(task_id)
-    .map(|row: sqlx::sqlite::SqliteRow| (row.get("id"), row.get("name"), row.get("done")))
     .fetch_all(pool)
     .await
 }
+pub(crate) async fn insert_todo_mapping(
+    task_id: i64,
+    todo_id: i64,
+    executor: impl sqlx::SqliteExecutor<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+            INSERT OR IGNORE INTO tasktodos (task_id, todo_id)
+            VALUES (?1, ?2);
+        "#,
+    )
+    .bind(task_id)
+    .bind(todo_id)
+    .execute(executor)
+    .await
+    .map(|_| ())
+}
+
+pub(crate) async fn delete_todo_mapping(
+    task_id: i64,
+    todo_id: i64,
+    executor: impl sqlx::SqliteExecutor<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+            DELETE FROM tasktodos
+            WHERE task_id = (?1) AND todo_id = (?2)
+        "#,
+    )
+    .bind(task_id)
+    .bind(todo_id)
+    .execute(executor)
+    .await
+    .map(|_| ())
+}
+
+pub(crate) async fn update_row(
+    id: i64,
+    input: TaskRowInput,
+    executor: impl sqlx::SqliteExecutor<'_>,
+) -> Result<(), sqlx::Error> {
+    let (name, description, due, done) = input;
+    sqlx::query(
+        r#"
+            UPDATE tasks
+            SET (name) = (?2),
+                (description) = (?3),
+                (due) = (?4),
+                (done) = (?5)
+            WHERE id = (?1);
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(description)
+    .bind(due)
+    .bind(done)
+    .execute(executor)
+    .await
+    .map(|_| ())
+}
+
+/// Deletes a task for good together with its todo links, the todos themselves are kept.
+/// Tag links, comments, time entries and attachment rows are removed by `ON DELETE CASCADE`.
+pub(crate) async fn delete_row(id: i64, pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM tasktodos WHERE task_id = (?1);")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM tasks WHERE id = (?1);")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
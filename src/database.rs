@@ -0,0 +1,97 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    ConnectOptions, Connection, SqliteConnection,
+};
+
+/// Handle to the live database file.
+/// The pool behind it can be swapped out at runtime, e.g. when restoring a backup,
+/// so always fetch a fresh pool with [`Database::pool`] instead of storing one.
+#[derive(Clone)]
+pub(crate) struct Database {
+    path: PathBuf,
+    pool: Arc<RwLock<sqlx::SqlitePool>>,
+}
+
+/// An open write transaction on the live database file.
+/// While it is held every other writer waits (and eventually fails with `database is locked`),
+/// readers are not affected.
+pub(crate) struct WriteLock(SqliteConnection);
+
+impl WriteLock {
+    async fn release(mut self) -> Result<(), sqlx::Error> {
+        sqlx::query("ROLLBACK;").execute(&mut self.0).await?;
+        self.0.close().await
+    }
+}
+
+fn options(path: &Path) -> SqliteConnectOptions {
+    SqliteConnectOptions::new()
+        .filename(path)
+        .log_statements(tracing::log::LevelFilter::Trace)
+        .create_if_missing(true)
+}
+
+async fn connect(path: &Path) -> Result<sqlx::SqlitePool, sqlx::Error> {
+    SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options(path))
+        .await
+}
+
+impl Database {
+    pub(crate) async fn open(path: impl Into<PathBuf>) -> Result<Self, sqlx::Error> {
+        let path = path.into();
+        let pool = connect(&path).await?;
+        Ok(Self {
+            path,
+            pool: Arc::new(RwLock::new(pool)),
+        })
+    }
+
+    pub(crate) fn pool(&self) -> sqlx::SqlitePool {
+        self.pool.read().unwrap().clone()
+    }
+
+    /// Takes the write lock of the live database file on a connection outside the pool.
+    pub(crate) async fn lock_writes(&self) -> Result<WriteLock, sqlx::Error> {
+        let mut connection = options(&self.path).connect().await?;
+        sqlx::query("BEGIN IMMEDIATE;")
+            .execute(&mut connection)
+            .await?;
+        Ok(WriteLock(connection))
+    }
+
+    /// Replaces the live database file with `source` and reconnects.
+    /// Requests still holding the old pool will fail with `PoolClosed`.
+    /// `lock` is held until the old pool is closed, so no write can slip in before the swap.
+    pub(crate) async fn replace_with(
+        &self,
+        source: &Path,
+        lock: WriteLock,
+    ) -> Result<(), sqlx::Error> {
+        // Stage the copy next to the live file first, so the final swap is a rename
+        // on the same file system and the live file is never half written.
+        let staging = self.path.with_extension("db.restoring");
+        tokio::fs::copy(source, &staging).await?;
+
+        self.pool().close().await;
+        lock.release().await?;
+
+        for suffix in ["-wal", "-shm"] {
+            let mut sidecar = self.path.clone().into_os_string();
+            sidecar.push(suffix);
+            let _ = tokio::fs::remove_file(sidecar).await;
+        }
+        tokio::fs::rename(&staging, &self.path).await?;
+
+        let pool = connect(&self.path).await?;
+        *self.pool.write().unwrap() = pool;
+
+        Ok(())
+    }
+}
@@ -4,6 +4,11 @@ pub(crate) enum ApiError {
     SQLError(sqlx::Error),
     TemplateError(askama::Error),
     FilterError,
+    Validation(String),
+    Forbidden(String),
+    Backup(crate::backup::BackupError),
+    Restore(crate::restore::RestoreError),
+    Attachment(crate::attachments::AttachmentError),
 }
 
 impl From<sqlx::Error> for ApiError {
@@ -18,16 +23,66 @@ impl From<askama::Error> for ApiError {
     }
 }
 
-impl axum::response::IntoResponse for ApiError {
-    fn into_response(self) -> Response {
+impl From<crate::backup::BackupError> for ApiError {
+    fn from(value: crate::backup::BackupError) -> Self {
+        Self::Backup(value)
+    }
+}
+
+impl From<crate::restore::RestoreError> for ApiError {
+    fn from(value: crate::restore::RestoreError) -> Self {
+        Self::Restore(value)
+    }
+}
+
+impl From<crate::attachments::AttachmentError> for ApiError {
+    fn from(value: crate::attachments::AttachmentError) -> Self {
+        Self::Attachment(value)
+    }
+}
+
+impl ApiError {
+    pub(crate) fn status(&self) -> StatusCode {
         match self {
-            Self::SQLError(e) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, format!("SQL Error: {e}")).into_response()
+            Self::SQLError(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+            Self::SQLError(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                StatusCode::CONFLICT
+            }
+            Self::FilterError | Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Restore(crate::restore::RestoreError::NotFound(_)) => StatusCode::NOT_FOUND,
+            Self::Restore(crate::restore::RestoreError::IncompatibleSchema { .. }) => {
+                StatusCode::UNPROCESSABLE_ENTITY
             }
-            Self::TemplateError(e) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            Self::Attachment(crate::attachments::AttachmentError::Upload(_)) => {
+                StatusCode::BAD_REQUEST
             }
-            Self::FilterError => (StatusCode::BAD_REQUEST, "Invalid filter").into_response(),
+            Self::Attachment(crate::attachments::AttachmentError::TooLarge { .. }) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub(crate) fn message(&self) -> String {
+        match self {
+            Self::SQLError(sqlx::Error::RowNotFound) => "Not found".to_string(),
+            Self::SQLError(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                "Refers to a missing item or is still referenced".to_string()
+            }
+            Self::SQLError(e) => format!("SQL Error: {e}"),
+            Self::TemplateError(e) => e.to_string(),
+            Self::FilterError => "Invalid filter".to_string(),
+            Self::Validation(message) | Self::Forbidden(message) => message.clone(),
+            Self::Backup(e) => e.to_string(),
+            Self::Restore(e) => e.to_string(),
+            Self::Attachment(e) => e.to_string(),
         }
     }
 }
+
+impl axum::response::IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), self.message()).into_response()
+    }
+}
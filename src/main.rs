@@ -1,54 +1,33 @@
-use std::str::FromStr;
-
 use askama::Template;
 use axum::{
-    extract::State,
-    http::{header::CONTENT_TYPE, Method, StatusCode},
+    http::{header::CONTENT_TYPE, Method},
+    middleware,
     response::IntoResponse,
-    routing::{delete, get, post, put},
-    Json, Router,
-};
-use serde::{Deserialize, Serialize};
-use sqlx::{
-    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
-    ConnectOptions,
+    routing::get,
+    Router,
 };
 use tokio::io::AsyncReadExt;
 use tower_http::services::ServeDir;
 mod api;
+mod attachments;
+mod auth;
+mod backup;
+mod cli;
 mod config;
 // mod controllers;
 mod data;
 mod data_access;
+mod database;
 mod error;
+mod migrations;
 mod models;
+mod restore;
+mod state;
+mod trash;
+mod undo;
 mod viewmodels;
 
-const DB_NAME: &str = "sqlite://data.db";
-// const DB_NAME: &str = "sqlite::memory:";
-
-// fn build_api_router(pool: sqlx::SqlitePool) -> Router {
-//     Router::new()
-//         // `POST /users` goes to `create_user`
-//         .route("/users", post(create_user))
-//         .route("/todo", post(controllers::create_todo))
-//         .route("/todo/:id", get(controllers::read_todo))
-//         .route("/todo/:id", put(controllers::update_todo))
-//         .route("/todo/:id", delete(controllers::delete_todo))
-//         .route("/todo", get(controllers::get_all_todos))
-//         .route("/backup", get(save_backup))
-//         .route("/tasks", get(controllers::task::get_all_tasks))
-//         .route("/tasks", post(controllers::task::create_task))
-//         .route("/tasks/:id", get(controllers::task::read_task))
-//         .route("/tasks/:id", put(controllers::task::update_task))
-//         .route("/tasks/:id", delete(controllers::task::delete_task))
-//         .route("/tasks/:id/todos/:id", post(controllers::task::add_todo))
-//         .route(
-//             "/tasks/:id/todos/:id",
-//             delete(controllers::task::remove_todo),
-//         )
-//         .with_state(pool)
-// }
+const DB_FILE: &str = "data.db";
 
 #[tokio::main]
 async fn main() -> Result<(), sqlx::Error> {
@@ -57,18 +36,65 @@ async fn main() -> Result<(), sqlx::Error> {
         .with_max_level(tracing::Level::DEBUG)
         .init();
 
+    let command = match cli::Command::from_args() {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+
     let config = read_config().await;
 
-    let sqlite_options = SqliteConnectOptions::from_str(DB_NAME)
-        .unwrap()
-        .log_statements(tracing::log::LevelFilter::Trace)
-        .create_if_missing(true);
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect_with(sqlite_options)
-        .await?;
+    let db = database::Database::open(DB_FILE).await?;
+    let backups = backup::BackupService::new(&config);
+
+    match command {
+        cli::Command::Migrations => return cli::print_migrations(&db.pool()).await,
+        cli::Command::Backups => {
+            if let Err(e) = cli::print_backups(&backups).await {
+                tracing::error!("{e}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        cli::Command::Restore(file_name) => {
+            if let Err(e) = cli::restore(&db, &backups, &file_name).await {
+                tracing::error!("Restore failed: {e}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        cli::Command::Migrate | cli::Command::Serve => {
+            if let Err(e) = init_db(&db.pool()).await {
+                tracing::error!("Aborting startup: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let cli::Command::Migrate = command {
+        return Ok(());
+    }
 
-    init_db(&pool).await?;
+    backups.spawn_schedule(db.clone());
+
+    let attachments = attachments::AttachmentStore::new(&config);
+    attachments.clean_up(&db.pool()).await;
+    let trash = trash::TrashService::new(&config);
+    trash.spawn_schedule(db.clone(), attachments.clone());
+
+    let sessions = auth::Sessions::new(&config);
+    let state = state::AppState {
+        db,
+        backups,
+        sessions,
+        attachments,
+        trash,
+        undo: undo::UndoHistory::default(),
+    };
+    let require_user = middleware::from_fn_with_state(state.clone(), auth::require_user);
+    let require_api_user = middleware::from_fn_with_state(state.clone(), auth::require_api_user);
 
     let cors = tower_http::cors::CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::PUT])
@@ -81,17 +107,58 @@ async fn main() -> Result<(), sqlx::Error> {
     let app = Router::new()
         // `GET /` goes to `root`
         .route("/", get(root))
-        .route("/login", get(login))
+        .merge(api::html::account::login_router())
+        .nest(
+            api::html::account::NEST_PREFIX,
+            api::html::account::router().route_layer(require_user.clone()),
+        )
         .nest(
             api::html::todo::NEST_PREFIX,
-            api::html::todo::router(&pool.clone()),
+            api::html::todo::router().route_layer(require_user.clone()),
         )
         .nest(
             api::html::task::NEST_PREFIX,
-            api::html::task::router(pool.clone()),
+            api::html::task::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::timesheet::NEST_PREFIX,
+            api::html::timesheet::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::activity::NEST_PREFIX,
+            api::html::activity::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::undo::NEST_PREFIX,
+            api::html::undo::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::trash::NEST_PREFIX,
+            api::html::trash::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::board::NEST_PREFIX,
+            api::html::board::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::project::NEST_PREFIX,
+            api::html::project::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::tag::NEST_PREFIX,
+            api::html::tag::router().route_layer(require_user.clone()),
+        )
+        .nest(
+            api::html::backup::NEST_PREFIX,
+            api::html::backup::router().route_layer(require_user),
+        )
+        .nest(
+            api::json::NEST_PREFIX,
+            api::json::router().route_layer(require_api_user),
         )
-        .with_state(pool.clone())
-        // .nest("/api", build_api_router(pool))
+        .route(api::json::OPENAPI_PATH, get(api::json::openapi_json))
+        .route(api::json::EXPLORER_PATH, get(api::json::explorer))
+        .with_state(state)
         .nest_service("/assets", ServeDir::new(config.assets_dir))
         .layer(tower_http::trace::TraceLayer::new_for_http())
         .layer(cors);
@@ -104,7 +171,7 @@ async fn main() -> Result<(), sqlx::Error> {
     Ok(())
 }
 
-async fn init_db(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+async fn init_db(pool: &sqlx::SqlitePool) -> Result<(), migrations::MigrationError> {
     data::migrate_table_state(pool).await?;
 
     // let test = data::select_task_row(1, pool).await?;
@@ -124,10 +191,6 @@ async fn root() -> impl IntoResponse {
     IndexTemplate
 }
 
-async fn login() -> impl IntoResponse {
-    viewmodels::LoginIndex
-}
-
 async fn read_config() -> config::Config {
     let config: config::Config;
     if let Ok(mut file) = tokio::fs::File::open("config.json").await {
@@ -139,87 +202,3 @@ async fn read_config() -> config::Config {
     }
     config
 }
-
-async fn save_backup(
-    State(pool): State<sqlx::SqlitePool>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
-    let config: config::Config;
-
-    if let Ok(mut file) = tokio::fs::File::open("config.json").await {
-        let mut buffer = String::new();
-        file.read_to_string(&mut buffer).await.unwrap();
-        config = serde_json::from_str(&buffer).unwrap();
-    } else {
-        config = config::Config::default();
-    }
-
-    let mut file_name = std::path::PathBuf::from(config.backup_dir);
-
-    file_name.push("sqlite://data.db");
-    tracing::debug!("Saving backup to {:?}", file_name.to_str());
-
-    // TODO: Does not work for some reason, no error,
-    // but also no backup file created.
-    // Maybe I need to use a proper db file instead ? :(
-    match sqlx::query(
-        r#"
-            VACUUM main INTO 'sqlite://backup.db';
-        "#,
-    )
-    // .bind(file_name.to_str().unwrap())
-    .execute(&pool)
-    .await
-    {
-        Ok(_) => Ok(StatusCode::OK),
-        Err(e) => {
-            tracing::error!("{}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
-        }
-    }
-}
-
-async fn create_user(
-    // this argument tells axum to parse the request body
-    // as JSON into a `CreateUser` type
-    State(pool): State<sqlx::SqlitePool>,
-    Json(payload): Json<CreateUser>,
-) -> (StatusCode, Json<User>) {
-    // insert your application logic here
-
-    match sqlx::query("INSERT INTO users (name) Values (?1);")
-        .bind(&payload.username)
-        .execute(&pool)
-        .await
-    {
-        Ok(query_result) => {
-            let id = query_result.last_insert_rowid();
-            (
-                StatusCode::CREATED,
-                Json(User {
-                    id: id.try_into().unwrap(),
-                    username: payload.username,
-                }),
-            )
-        }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(User {
-                id: 0,
-                username: e.to_string(),
-            }),
-        ),
-    }
-}
-
-// the input to our `create_user` handler
-#[derive(Deserialize)]
-struct CreateUser {
-    username: String,
-}
-
-// the output to our `create_user` handler
-#[derive(Serialize, Default)]
-struct User {
-    id: u64,
-    username: String,
-}
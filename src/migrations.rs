@@ -0,0 +1,503 @@
+use sqlx::Row;
+
+use crate::data_access::utils::{now, DateTime};
+
+/// A single, numbered schema change.
+/// Migrations are applied in ascending `version` order and are never edited
+/// once released, new changes always go into a new migration at the end of [`MIGRATIONS`].
+pub(crate) struct Migration {
+    pub(crate) version: i64,
+    pub(crate) name: &'static str,
+    pub(crate) sql: &'static str,
+}
+
+/// All known migrations, ordered by version.
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        // Uses `IF NOT EXISTS`, so databases created before versioning
+        // was introduced are adopted without touching their data.
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS users
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            name TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS todos
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            name TEXT,
+            done BOOLEAN
+        );
+
+        CREATE TABLE IF NOT EXISTS tasks
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            name TEXT,
+            description TEXT,
+            created TEXT,
+            due TEXT,
+            done BOOLEAN
+        );
+
+        CREATE TABLE IF NOT EXISTS tags
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            name TEXT,
+            color TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS tasktodos
+        (
+            task_id INTEGER NOT NULL,
+            todo_id INTEGER NOT NULL,
+            foreign key (task_id) references tasks(id),
+            foreign key (todo_id) references todos(id),
+            primary key (task_id, todo_id)
+        );
+    "#,
+    },
+    Migration {
+        version: 2,
+        name: "tag_mappings",
+        sql: r#"
+        CREATE TABLE todotags
+        (
+            todo_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            foreign key (todo_id) references todos(id) ON DELETE CASCADE,
+            foreign key (tag_id) references tags(id) ON DELETE CASCADE,
+            primary key (todo_id, tag_id)
+        );
+
+        CREATE TABLE tasktags
+        (
+            task_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (tag_id) references tags(id) ON DELETE CASCADE,
+            primary key (task_id, tag_id)
+        );
+    "#,
+    },
+    Migration {
+        version: 3,
+        name: "todo_done_not_null",
+        // Todos created through the html form used to be stored without a done state
+        sql: r#"
+        UPDATE todos SET done = 0 WHERE done IS NULL;
+    "#,
+    },
+    Migration {
+        version: 4,
+        name: "user_accounts",
+        sql: r#"
+        ALTER TABLE users ADD COLUMN email TEXT;
+        ALTER TABLE users ADD COLUMN password_hash TEXT;
+        ALTER TABLE users ADD COLUMN created TEXT;
+        CREATE UNIQUE INDEX users_name ON users (name COLLATE NOCASE);
+
+        CREATE TABLE sessions
+        (
+            token TEXT PRIMARY KEY NOT NULL,
+            user_id INTEGER NOT NULL,
+            created TEXT NOT NULL,
+            expires TEXT NOT NULL,
+            foreign key (user_id) references users(id) ON DELETE CASCADE
+        );
+    "#,
+    },
+    Migration {
+        version: 5,
+        name: "ownership",
+        // Rows created before accounts existed stay without owner,
+        // until the first account adopts them on registration.
+        sql: r#"
+        ALTER TABLE todos ADD COLUMN owner_id INTEGER REFERENCES users(id);
+        ALTER TABLE tasks ADD COLUMN owner_id INTEGER REFERENCES users(id);
+        CREATE INDEX todos_owner ON todos (owner_id);
+        CREATE INDEX tasks_owner ON tasks (owner_id);
+
+        CREATE TABLE task_shares
+        (
+            task_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            access TEXT NOT NULL CHECK (access IN ('read', 'write')),
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (user_id) references users(id) ON DELETE CASCADE,
+            primary key (task_id, user_id)
+        );
+    "#,
+    },
+    Migration {
+        version: 6,
+        name: "board_columns",
+        // Every user gets the default columns on first use of the board,
+        // tasks without column are placed into them at the same time.
+        sql: r#"
+        CREATE TABLE board_columns
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            owner_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            done BOOLEAN NOT NULL DEFAULT 0,
+            foreign key (owner_id) references users(id) ON DELETE CASCADE
+        );
+        CREATE INDEX board_columns_owner ON board_columns (owner_id, position);
+
+        ALTER TABLE tasks ADD COLUMN column_id INTEGER REFERENCES board_columns(id) ON DELETE SET NULL;
+        ALTER TABLE tasks ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+        CREATE INDEX tasks_column ON tasks (column_id, position);
+    "#,
+    },
+    Migration {
+        version: 7,
+        name: "projects",
+        // Existing tasks and columns move into an "Inbox" project of their owner,
+        // rows without owner get one once they are adopted.
+        sql: r#"
+        CREATE TABLE projects
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            owner_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            archived BOOLEAN NOT NULL DEFAULT 0,
+            foreign key (owner_id) references users(id) ON DELETE CASCADE
+        );
+        CREATE INDEX projects_owner ON projects (owner_id);
+
+        ALTER TABLE tasks ADD COLUMN project_id INTEGER REFERENCES projects(id);
+        ALTER TABLE board_columns ADD COLUMN project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE;
+
+        INSERT INTO projects (owner_id, name)
+        SELECT owner_id, 'Inbox' FROM tasks WHERE owner_id IS NOT NULL
+        UNION SELECT owner_id, 'Inbox' FROM board_columns;
+        UPDATE tasks SET project_id = (SELECT id FROM projects p WHERE p.owner_id = tasks.owner_id);
+        UPDATE board_columns SET project_id = (SELECT id FROM projects p WHERE p.owner_id = board_columns.owner_id);
+
+        CREATE INDEX tasks_project ON tasks (project_id);
+        CREATE INDEX board_columns_project ON board_columns (project_id, position);
+    "#,
+    },
+    Migration {
+        version: 8,
+        name: "flow_metrics",
+        // Earlier moves were not recorded,
+        // existing tasks count as having entered their current column when they were created.
+        sql: r#"
+        ALTER TABLE board_columns ADD COLUMN wip_limit INTEGER;
+        ALTER TABLE board_columns ADD COLUMN wip_block BOOLEAN NOT NULL DEFAULT 0;
+
+        CREATE TABLE task_transitions
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            task_id INTEGER NOT NULL,
+            column_id INTEGER NOT NULL,
+            entered TEXT NOT NULL,
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (column_id) references board_columns(id) ON DELETE CASCADE
+        );
+        CREATE INDEX task_transitions_task ON task_transitions (task_id, entered);
+
+        INSERT INTO task_transitions (task_id, column_id, entered)
+        SELECT id, column_id, created FROM tasks WHERE column_id IS NOT NULL;
+    "#,
+    },
+    Migration {
+        version: 9,
+        name: "priorities",
+        sql: r#"
+        ALTER TABLE tasks ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE todos ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+    "#,
+    },
+    Migration {
+        version: 10,
+        name: "optional_due",
+        // Tasks used to be created with their creation time as due date,
+        // those never had a real one.
+        sql: r#"
+        UPDATE tasks SET due = NULL WHERE abs(julianday(due) - julianday(created)) < 1.0 / 86400;
+    "#,
+    },
+    Migration {
+        version: 11,
+        name: "recurrence",
+        // Json of `Recurrence`, only the latest occurrence of a series keeps it
+        sql: r#"
+        ALTER TABLE tasks ADD COLUMN recurrence TEXT;
+    "#,
+    },
+    Migration {
+        version: 12,
+        name: "subtasks",
+        // Only parents without open subtasks can be deleted, done subtasks move up to the top level
+        sql: r#"
+        ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks(id) ON DELETE SET NULL;
+        CREATE INDEX tasks_parent ON tasks (parent_id);
+    "#,
+    },
+    Migration {
+        version: 13,
+        name: "task_dependencies",
+        sql: r#"
+        CREATE TABLE task_dependencies
+        (
+            task_id INTEGER NOT NULL,
+            blocker_id INTEGER NOT NULL,
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (blocker_id) references tasks(id) ON DELETE CASCADE,
+            primary key (task_id, blocker_id)
+        );
+        CREATE INDEX task_dependencies_blocker ON task_dependencies (blocker_id);
+    "#,
+    },
+    Migration {
+        version: 14,
+        name: "time_entries",
+        // A running timer has no stop time yet, users run at most one timer at a time
+        sql: r#"
+        CREATE TABLE time_entries
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            task_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            started TEXT NOT NULL,
+            stopped TEXT,
+            note TEXT NOT NULL DEFAULT '',
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (user_id) references users(id) ON DELETE CASCADE
+        );
+        CREATE INDEX time_entries_task ON time_entries (task_id);
+        CREATE INDEX time_entries_user ON time_entries (user_id, started);
+        CREATE UNIQUE INDEX time_entries_running ON time_entries (user_id) WHERE stopped IS NULL;
+    "#,
+    },
+    Migration {
+        version: 15,
+        name: "comments",
+        // Comments after `last_read_id` are unread, own comments never are.
+        // Ids must not be reused for that, hence AUTOINCREMENT
+        sql: r#"
+        CREATE TABLE comments
+        (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            task_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            body TEXT NOT NULL,
+            created TEXT NOT NULL,
+            edited TEXT,
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (user_id) references users(id) ON DELETE CASCADE
+        );
+        CREATE INDEX comments_task ON comments (task_id, created);
+        CREATE TABLE comment_reads
+        (
+            task_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            last_read_id INTEGER NOT NULL,
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (user_id) references users(id) ON DELETE CASCADE,
+            primary key (task_id, user_id)
+        );
+    "#,
+    },
+    Migration {
+        version: 16,
+        name: "attachments",
+        // The files live in the attachments directory under `stored_name`
+        sql: r#"
+        CREATE TABLE attachments
+        (
+            id INTEGER PRIMARY KEY NOT NULL,
+            task_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            mime TEXT NOT NULL,
+            sha256 TEXT NOT NULL,
+            stored_name TEXT NOT NULL UNIQUE,
+            created TEXT NOT NULL,
+            foreign key (task_id) references tasks(id) ON DELETE CASCADE,
+            foreign key (user_id) references users(id) ON DELETE CASCADE
+        );
+        CREATE INDEX attachments_task ON attachments (task_id);
+    "#,
+    },
+    Migration {
+        version: 17,
+        name: "soft_delete",
+        // Deleted todos and tasks stay in the trash until they are purged,
+        // links left behind by hard deletes before this are dropped.
+        sql: r#"
+        ALTER TABLE todos ADD COLUMN deleted_at TEXT;
+        ALTER TABLE tasks ADD COLUMN deleted_at TEXT;
+        CREATE INDEX todos_deleted ON todos (deleted_at) WHERE deleted_at IS NOT NULL;
+        CREATE INDEX tasks_deleted ON tasks (deleted_at) WHERE deleted_at IS NOT NULL;
+        DELETE FROM tasktodos
+        WHERE task_id NOT IN (SELECT id FROM tasks) OR todo_id NOT IN (SELECT id FROM todos);
+    "#,
+    },
+    Migration {
+        version: 18,
+        name: "audit_log",
+        // Entries outlive their todo or task, so `entity_id` references nothing.
+        // The states are json snapshots, `NULL` before a create and after a delete.
+        sql: r#"
+        CREATE TABLE audit_log
+        (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity TEXT NOT NULL,
+            entity_id INTEGER NOT NULL,
+            user_id INTEGER,
+            action TEXT NOT NULL,
+            at TEXT NOT NULL,
+            before_state TEXT,
+            after_state TEXT,
+            foreign key (user_id) references users(id) ON DELETE SET NULL
+        );
+        CREATE INDEX audit_log_entity ON audit_log (entity, entity_id);
+        CREATE INDEX audit_log_user ON audit_log (user_id);
+    "#,
+    },
+    Migration {
+        version: 19,
+        name: "tag_owners",
+        // Tags in use go to the owner of the oldest task or todo carrying them,
+        // unused ones stay without owner until the first account adopts them.
+        sql: r#"
+        ALTER TABLE tags ADD COLUMN owner_id INTEGER REFERENCES users(id) ON DELETE CASCADE;
+        CREATE INDEX tags_owner ON tags (owner_id);
+        UPDATE tags SET owner_id = COALESCE(
+            (SELECT t.owner_id FROM tasktags m JOIN tasks t ON t.id = m.task_id
+             WHERE m.tag_id = tags.id AND t.owner_id IS NOT NULL ORDER BY t.id LIMIT 1),
+            (SELECT t.owner_id FROM todotags m JOIN todos t ON t.id = m.todo_id
+             WHERE m.tag_id = tags.id AND t.owner_id IS NOT NULL ORDER BY t.id LIMIT 1)
+        );
+    "#,
+    },
+];
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum MigrationError {
+    #[error("Failed to apply migration {version} ({name}): {source}")]
+    Failed {
+        version: i64,
+        name: &'static str,
+        source: sqlx::Error,
+    },
+    #[error("Database schema version {found} is newer than the latest known migration {latest}")]
+    UnknownVersion { found: i64, latest: i64 },
+    #[error("Failed to read schema version: {0}")]
+    SQLError(#[from] sqlx::Error),
+}
+
+/// State of a single migration in a given database.
+pub(crate) struct MigrationStatus {
+    pub(crate) version: i64,
+    pub(crate) name: &'static str,
+    pub(crate) applied: Option<DateTime>,
+}
+
+/// The version the schema will have once every migration is applied.
+pub(crate) fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+async fn create_version_table(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version
+        (
+            version INTEGER PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            applied TEXT NOT NULL
+        );
+        "#,
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+}
+
+/// The highest applied migration version, `0` for a fresh database.
+pub(crate) async fn current_version(pool: &sqlx::SqlitePool) -> Result<i64, sqlx::Error> {
+    create_version_table(pool).await?;
+    sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version;")
+        .map(|row: sqlx::sqlite::SqliteRow| row.get("version"))
+        .fetch_one(pool)
+        .await
+}
+
+/// Lists every known migration together with the time it was applied, if it was.
+pub(crate) async fn status(pool: &sqlx::SqlitePool) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    create_version_table(pool).await?;
+    let applied: Vec<(i64, DateTime)> = sqlx::query("SELECT version, applied FROM schema_version;")
+        .map(|row: sqlx::sqlite::SqliteRow| (row.get("version"), row.get("applied")))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(MIGRATIONS
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            name: migration.name,
+            applied: applied
+                .iter()
+                .find(|(version, _)| *version == migration.version)
+                .map(|(_, applied)| *applied),
+        })
+        .collect())
+}
+
+/// Applies all pending migrations, each one inside its own transaction.
+/// Stops at the first failing migration, leaving the database at the last good version.
+pub(crate) async fn run(pool: &sqlx::SqlitePool) -> Result<(), MigrationError> {
+    let current = current_version(pool).await?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(MigrationError::UnknownVersion {
+            found: current,
+            latest,
+        });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        tracing::info!(
+            "Applying migration {} ({})",
+            migration.version,
+            migration.name
+        );
+        apply(pool, migration)
+            .await
+            .map_err(|source| MigrationError::Failed {
+                version: migration.version,
+                name: migration.name,
+                source,
+            })?;
+    }
+
+    Ok(())
+}
+
+async fn apply(pool: &sqlx::SqlitePool, migration: &Migration) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(migration.sql).execute(&mut *tx).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO schema_version (version, name, applied)
+        VALUES (?1, ?2, ?3);
+        "#,
+    )
+    .bind(migration.version)
+    .bind(migration.name)
+    .bind(now())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
@@ -0,0 +1,199 @@
+use axum::extract::Multipart;
+
+use crate::{
+    api::types::{attachment::Attachment, task::Access},
+    attachments::{AttachmentError, AttachmentStore, StoredFile},
+    data_access::utils::now,
+    error::ApiError,
+};
+
+/// Multipart field holding the uploaded files.
+const FILE_FIELD: &str = "file";
+/// Longest kept file name, in characters.
+const MAX_NAME_CHARS: usize = 255;
+
+/// Columns of an [`Attachment`], joined with the name of the uploader.
+const ATTACHMENT_COLUMNS: &str = r#"
+    SELECT a.id, a.task_id, a.user_id, u.name AS user, a.name, a.size, a.mime, a.sha256, a.created
+    FROM attachments a JOIN users u ON u.id = a.user_id
+"#;
+
+/// The file name without any directories a browser may send along.
+fn clean_name(file_name: Option<&str>) -> Result<String, ApiError> {
+    let name = file_name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    match name {
+        "" => Err(ApiError::Validation(
+            "Attachments need a file name".to_string(),
+        )),
+        name => Ok(name.chars().take(MAX_NAME_CHARS).collect()),
+    }
+}
+
+/// The type the browser sent, guessed from the name if it sent none.
+fn mime_of(content_type: Option<&str>, name: &str) -> String {
+    match content_type {
+        Some(mime) if !mime.is_empty() && mime != "application/octet-stream" => mime.to_string(),
+        _ => mime_guess::from_path(name)
+            .first_or_octet_stream()
+            .to_string(),
+    }
+}
+
+pub async fn get_for_task(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+) -> Result<Vec<Attachment>, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Read).await?;
+    sqlx::query_as::<_, Attachment>(&format!(
+        "{ATTACHMENT_COLUMNS} WHERE a.task_id = (?1) ORDER BY a.id;"
+    ))
+    .bind(task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.into())
+}
+
+pub async fn get_by_id(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    id: i64,
+) -> Result<Attachment, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Read).await?;
+    sqlx::query_as::<_, Attachment>(&format!(
+        "{ATTACHMENT_COLUMNS} WHERE a.id = (?1) AND a.task_id = (?2);"
+    ))
+    .bind(id)
+    .bind(task_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.into())
+}
+
+/// The content of an attachment, along with its metadata.
+pub async fn read(
+    pool: &sqlx::SqlitePool,
+    store: &AttachmentStore,
+    user_id: i64,
+    task_id: i64,
+    id: i64,
+) -> Result<(Attachment, Vec<u8>), ApiError> {
+    let attachment = get_by_id(pool, user_id, task_id, id).await?;
+    let stored_name: String =
+        sqlx::query_scalar("SELECT stored_name FROM attachments WHERE id = (?1);")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+    let content = tokio::fs::read(store.path_of(&stored_name))
+        .await
+        .map_err(AttachmentError::from)?;
+    Ok((attachment, content))
+}
+
+async fn insert(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    name: &str,
+    mime: &str,
+    file: &StoredFile,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query(
+        r#"
+            INSERT INTO attachments (task_id, user_id, name, size, mime, sha256, stored_name, created)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        ;
+        "#,
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .bind(name)
+    .bind(file.size as i64)
+    .bind(mime)
+    .bind(&file.sha256)
+    .bind(&file.stored_name)
+    .bind(now())
+    .execute(pool)
+    .await
+    .map(|result| result.last_insert_rowid())
+}
+
+/// Stores every `file` part of an upload and returns the new attachment ids.
+/// Files stored before a failing part are kept.
+pub async fn upload(
+    pool: &sqlx::SqlitePool,
+    store: &AttachmentStore,
+    user_id: i64,
+    task_id: i64,
+    multipart: &mut Multipart,
+) -> Result<Vec<i64>, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Write).await?;
+
+    let mut ids = Vec::new();
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AttachmentError::Upload(e.body_text()))?
+    {
+        if field.name() != Some(FILE_FIELD) {
+            continue;
+        }
+        // Browsers send an empty part when no file was picked
+        if field.file_name().is_some_and(str::is_empty) {
+            continue;
+        }
+        let name = clean_name(field.file_name())?;
+        let mime = mime_of(field.content_type(), &name);
+        let file = store.write(&name, &mut field).await?;
+
+        // The sweep waits until the row is in and the file committed under its stored name
+        let _sweep = store.lock_sweep().await;
+        let id = match insert(pool, user_id, task_id, &name, &mime, &file).await {
+            Ok(id) => id,
+            Err(e) => {
+                store.remove(&file.stored_name).await;
+                return Err(e.into());
+            }
+        };
+        if let Err(e) = store.commit(&file).await {
+            sqlx::query("DELETE FROM attachments WHERE id = (?1);")
+                .bind(id)
+                .execute(pool)
+                .await?;
+            store.remove(&file.stored_name).await;
+            return Err(e.into());
+        }
+        ids.push(id);
+    }
+
+    match ids.is_empty() {
+        true => Err(ApiError::Validation("No file was uploaded".to_string())),
+        false => Ok(ids),
+    }
+}
+
+/// Removes an attachment along with its file, users who can write the task can do so.
+pub async fn delete(
+    pool: &sqlx::SqlitePool,
+    store: &AttachmentStore,
+    user_id: i64,
+    task_id: i64,
+    id: i64,
+) -> Result<(), ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Write).await?;
+    let stored_name: String = sqlx::query_scalar(
+        "DELETE FROM attachments WHERE id = (?1) AND task_id = (?2) RETURNING stored_name;",
+    )
+    .bind(id)
+    .bind(task_id)
+    .fetch_one(pool)
+    .await?;
+    store.remove(&stored_name).await;
+    Ok(())
+}
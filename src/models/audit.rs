@@ -0,0 +1,247 @@
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use crate::{
+    api::types::{
+        audit::{
+            ActivityQuery, AuditAction, AuditEntity, AuditEntry, AuditRow, TaskSnapshot,
+            TodoSnapshot,
+        },
+        task::Access,
+    },
+    data_access::utils::now,
+    error::ApiError,
+};
+
+/// Entries shown per page of the activity.
+const PAGE_SIZE: i64 = 50;
+
+/// Columns of an [`AuditRow`], joined with the name of the user.
+const ENTRY_COLUMNS: &str = r#"
+    SELECT a.id, a.entity, a.entity_id, a.user_id, u.name AS user, a.action, a.at,
+        a.before_state, a.after_state
+    FROM audit_log a LEFT JOIN users u ON u.id = a.user_id
+"#;
+
+/// The current state of a todo or task, trashed ones included, `None` once it is purged.
+pub(crate) async fn snapshot(
+    conn: &mut SqliteConnection,
+    entity: AuditEntity,
+    id: i64,
+) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    let state = match entity {
+        AuditEntity::Task => {
+            let Some(mut task) = sqlx::query_as::<_, TaskSnapshot>(
+                r#"
+                    SELECT name, description, due, done, priority, project_id, parent_id, column_id
+                    FROM tasks WHERE id = (?1)
+                ;
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            else {
+                return Ok(None);
+            };
+            task.recurrence = super::task::get_recurrence(&mut *conn, id).await?;
+            task.todos = sqlx::query_scalar(
+                "SELECT todo_id FROM tasktodos WHERE task_id = (?1) ORDER BY todo_id;",
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+            task.blockers = sqlx::query_scalar(
+                "SELECT blocker_id FROM task_dependencies WHERE task_id = (?1) ORDER BY blocker_id;",
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+            task.shares = Some(
+                sqlx::query_as(
+                "SELECT user_id, access FROM task_shares WHERE task_id = (?1) ORDER BY user_id;",
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?,
+            );
+            serde_json::to_value(task)
+        }
+        AuditEntity::Todo => {
+            let Some(todo) = sqlx::query_as::<_, TodoSnapshot>(
+                "SELECT name, done, priority FROM todos WHERE id = (?1);",
+            )
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            else {
+                return Ok(None);
+            };
+            serde_json::to_value(todo)
+        }
+    };
+    Ok(Some(state.expect("snapshots always serialize to json")))
+}
+
+/// Records a change `user_id` made, `before` is the [`snapshot`] taken before it.
+/// The state after it is taken here, updates that changed nothing are left out.
+/// Pass the transaction of the change, so it is never committed without its entry.
+pub(crate) async fn record(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    action: AuditAction,
+    entity: AuditEntity,
+    id: i64,
+    before: Option<serde_json::Value>,
+) -> Result<(), sqlx::Error> {
+    let after = match action {
+        AuditAction::Delete => None,
+        _ => snapshot(&mut *conn, entity, id).await?,
+    };
+    if action == AuditAction::Update && before == after {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+            INSERT INTO audit_log (entity, entity_id, user_id, action, at, before_state, after_state)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ;
+        "#,
+    )
+    .bind(entity)
+    .bind(id)
+    .bind(user_id)
+    .bind(action)
+    .bind(now())
+    .bind(before.map(|state| state.to_string()))
+    .bind(after.map(|state| state.to_string()))
+    .execute(&mut *conn)
+    .await
+    .map(|_| ())
+}
+
+/// Id of the newest entry, `0` while the log is empty.
+/// Entries recorded later by a user make up what they changed since, see [`get_since`].
+pub(crate) async fn latest_id(pool: &sqlx::SqlitePool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM audit_log;")
+        .fetch_one(pool)
+        .await
+}
+
+/// Entries `user_id` recorded after the entry `after`, oldest first.
+pub(crate) async fn get_since(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    after: i64,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, AuditRow>(&format!(
+        "{ENTRY_COLUMNS} WHERE a.user_id = (?1) AND a.id > (?2) ORDER BY a.id;"
+    ))
+    .bind(user_id)
+    .bind(after)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(AuditEntry::from).collect())
+}
+
+/// Id of the newest entry about a todo or task.
+pub(crate) async fn latest_for(
+    pool: &sqlx::SqlitePool,
+    entity: AuditEntity,
+    id: i64,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(MAX(id), 0) FROM audit_log WHERE entity = (?1) AND entity_id = (?2);",
+    )
+    .bind(entity)
+    .bind(id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Changes to a task and to the todos linked to it, newest first.
+pub async fn get_for_task(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+) -> Result<Vec<AuditEntry>, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Read).await?;
+    let rows = sqlx::query_as::<_, AuditRow>(&format!(
+        r#"{ENTRY_COLUMNS}
+            WHERE (a.entity = 'task' AND a.entity_id = (?1))
+            OR (a.entity = 'todo' AND a.entity_id IN (SELECT todo_id FROM tasktodos WHERE task_id = (?1)))
+            ORDER BY a.id DESC
+        ;
+        "#
+    ))
+    .bind(task_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(AuditEntry::from).collect())
+}
+
+/// Narrows a query on `audit_log a` to the entries `user_id` may see: their own changes and
+/// changes to todos and tasks they own or can see through a task, trashed ones included.
+fn push_visible(query: &mut QueryBuilder<Sqlite>, user_id: i64) {
+    let tasks = |query: &mut QueryBuilder<Sqlite>| {
+        query
+            .push("SELECT id FROM tasks WHERE owner_id = ")
+            .push_bind(user_id)
+            .push(" UNION SELECT task_id FROM task_shares WHERE user_id = ")
+            .push_bind(user_id);
+    };
+    query
+        .push(" WHERE (a.user_id = ")
+        .push_bind(user_id)
+        .push(" OR (a.entity = 'task' AND a.entity_id IN (");
+    tasks(query);
+    query
+        .push(")) OR (a.entity = 'todo' AND a.entity_id IN (SELECT id FROM todos WHERE owner_id = ")
+        .push_bind(user_id)
+        .push(" UNION SELECT todo_id FROM tasktodos WHERE task_id IN (");
+    tasks(query);
+    query.push("))))");
+}
+
+/// A page of the changes `user_id` may see, newest first.
+pub async fn get_activity(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    query: &ActivityQuery,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(ENTRY_COLUMNS);
+    push_visible(&mut builder, user_id);
+    if let Some(author) = query.user {
+        builder.push(" AND a.user_id = ").push_bind(author);
+    }
+    if let Some(entity) = query.entity {
+        builder.push(" AND a.entity = ").push_bind(entity);
+    }
+    if let Some(before) = query.before {
+        builder.push(" AND a.id < ").push_bind(before);
+    }
+    builder
+        .push(" ORDER BY a.id DESC LIMIT ")
+        .push_bind(PAGE_SIZE);
+
+    let rows = builder.build_query_as::<AuditRow>().fetch_all(pool).await?;
+    Ok(rows.into_iter().map(AuditEntry::from).collect())
+}
+
+/// Whether a page of [`get_activity`] may be followed by older entries.
+pub fn is_full_page(entries: &[AuditEntry]) -> bool {
+    entries.len() as i64 == PAGE_SIZE
+}
+
+/// The users whose changes show up in the activity of `user_id`, by name.
+pub async fn get_authors(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+) -> Result<Vec<(i64, String)>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "SELECT DISTINCT u.id, u.name FROM audit_log a JOIN users u ON u.id = a.user_id",
+    );
+    push_visible(&mut builder, user_id);
+    builder.push(" ORDER BY u.name COLLATE NOCASE");
+    builder.build_query_as().fetch_all(pool).await
+}
@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+
+use sqlx::{Sqlite, SqliteConnection, Transaction};
+
+use crate::{
+    api::types::{
+        audit::{AuditAction, AuditEntity},
+        board::{BoardColumn, Column, CreateColumn, MoveTask, UpdateColumn},
+        task::{Access, TaskHeader},
+    },
+    error::ApiError,
+};
+
+/// Creates the default columns for a project unless it already has some,
+/// then places every task of the project that is not in a column yet.
+pub(crate) async fn ensure_columns(
+    conn: &mut SqliteConnection,
+    project_id: i64,
+) -> Result<(), sqlx::Error> {
+    // A single statement, so concurrent requests can't both create the defaults
+    sqlx::query(
+        r#"
+            INSERT INTO board_columns (owner_id, project_id, name, position, done)
+            SELECT p.owner_id, p.id, v.column1, v.column2, v.column3
+            FROM projects p, (VALUES ('Backlog', 0, 0), ('Doing', 1, 0), ('Review', 2, 0), ('Done', 3, 1)) v
+            WHERE p.id = (?1) AND NOT EXISTS (SELECT 1 FROM board_columns WHERE project_id = (?1))
+        ;
+        "#,
+    )
+    .bind(project_id)
+    .execute(&mut *conn)
+    .await?;
+
+    // Unplaced tasks go to the end of the first column matching their done state,
+    // or the first column at all. Positions only need to keep the order, gaps are fine.
+    // WIP limits don't apply here, the task has to go somewhere.
+    let placed: Vec<(i64, i64)> = sqlx::query_as(
+        r#"
+            UPDATE tasks
+            SET column_id = COALESCE(
+                    (SELECT id FROM board_columns WHERE project_id = (?1) AND done = tasks.done ORDER BY position LIMIT 1),
+                    (SELECT id FROM board_columns WHERE project_id = (?1) ORDER BY position LIMIT 1)
+                ),
+                position = (SELECT COALESCE(MAX(position), 0) FROM tasks WHERE project_id = (?1)) + id
+            WHERE project_id = (?1) AND column_id IS NULL
+            RETURNING id, column_id
+        ;
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (task_id, column_id) in placed {
+        super::task::record_transition(&mut *conn, task_id, column_id).await?;
+    }
+    Ok(())
+}
+
+/// Takes a task out of its column, e.g. after its done state or project was changed outside
+/// of the board, and places it into the first column of its project matching its done state.
+pub(crate) async fn replace_task(
+    conn: &mut SqliteConnection,
+    task_id: i64,
+) -> Result<(), sqlx::Error> {
+    let project_id: Option<i64> = sqlx::query_scalar(
+        "UPDATE tasks SET column_id = NULL WHERE id = (?1) RETURNING project_id;",
+    )
+    .bind(task_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    match project_id {
+        Some(project_id) => ensure_columns(&mut *conn, project_id).await,
+        None => Ok(()),
+    }
+}
+
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    match name.trim() {
+        "" => Err(ApiError::Validation(
+            "Column name must not be empty".to_string(),
+        )),
+        name => Ok(name.to_string()),
+    }
+}
+
+/// `0` stands for no limit.
+fn validate_wip_limit(limit: i64) -> Result<Option<i64>, ApiError> {
+    match limit {
+        0 => Ok(None),
+        limit if limit < 0 => Err(ApiError::Validation(format!(
+            "Invalid WIP limit {limit}, expected a positive number"
+        ))),
+        limit => Ok(Some(limit)),
+    }
+}
+
+/// Rewrites the positions of `ids` in `table` to their index, closing any gaps.
+async fn renumber(
+    tx: &mut Transaction<'_, Sqlite>,
+    table: &str,
+    ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    for (position, id) in ids.iter().enumerate() {
+        sqlx::query(&format!(
+            "UPDATE {table} SET position = (?1) WHERE id = (?2);"
+        ))
+        .bind(position as i64)
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Moves `id` to index `position` of `ids`, clamped to the end.
+fn insert_at(mut ids: Vec<i64>, id: i64, position: i64) -> Vec<i64> {
+    ids.retain(|other| *other != id);
+    let index = position.clamp(0, ids.len() as i64) as usize;
+    ids.insert(index, id);
+    ids
+}
+
+/// The columns of a project of `user_id`.
+pub async fn get_columns(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    project_id: i64,
+) -> Result<Vec<Column>, sqlx::Error> {
+    super::project::get_by_id(pool, user_id, project_id).await?;
+    ensure_columns(&mut *pool.acquire().await?, project_id).await?;
+    sqlx::query_as::<_, Column>(
+        r#"
+            SELECT id, project_id, name, position, done, wip_limit, wip_block FROM board_columns
+            WHERE project_id = (?1)
+            ORDER BY position, id
+        ;
+        "#,
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// A column of `owner_id`, `RowNotFound` for columns of other users.
+pub async fn get_column(
+    pool: &sqlx::SqlitePool,
+    owner_id: i64,
+    id: i64,
+) -> Result<Column, sqlx::Error> {
+    sqlx::query_as::<_, Column>(
+        r#"
+            SELECT id, project_id, name, position, done, wip_limit, wip_block FROM board_columns
+            WHERE id = (?1) AND owner_id = (?2)
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(owner_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// The board of a project of `user_id`.
+/// Shared tasks stay on the board of their owner.
+pub async fn get_board(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    project_id: i64,
+) -> Result<Vec<BoardColumn>, sqlx::Error> {
+    let columns = get_columns(pool, user_id, project_id).await?;
+    let mut by_column: HashMap<i64, Vec<TaskHeader>> = HashMap::new();
+    for task in super::task::get_board_headers(pool, user_id, project_id).await? {
+        if let Some(column_id) = task.column_id {
+            by_column.entry(column_id).or_default().push(task);
+        }
+    }
+
+    Ok(columns
+        .into_iter()
+        .map(|column| BoardColumn {
+            tasks: by_column.remove(&column.id).unwrap_or_default(),
+            column,
+        })
+        .collect())
+}
+
+/// Appends a new column after the existing ones, in the default project if none is given.
+pub async fn create_column(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    payload: &CreateColumn,
+) -> Result<i64, ApiError> {
+    let name = validate_name(&payload.name)?;
+    let wip_limit = validate_wip_limit(payload.wip_limit.unwrap_or(0))?;
+    let project = super::project::resolve(pool, user_id, payload.project_id).await?;
+    ensure_columns(&mut *pool.acquire().await?, project.id).await?;
+    sqlx::query(
+        r#"
+            INSERT INTO board_columns (owner_id, project_id, name, position, done, wip_limit, wip_block)
+            SELECT ?1, ?2, ?3, COALESCE(MAX(position) + 1, 0), ?4, ?5, ?6 FROM board_columns WHERE project_id = (?2)
+        ;
+        "#,
+    )
+    .bind(user_id)
+    .bind(project.id)
+    .bind(name)
+    .bind(payload.done)
+    .bind(wip_limit)
+    .bind(payload.wip_block)
+    .execute(pool)
+    .await
+    .map(|result| result.last_insert_rowid())
+    .map_err(|e| e.into())
+}
+
+/// Changing `done` of a column changes the done state of every task in it,
+/// which fails while it holds blocked tasks.
+pub async fn update_column(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    payload: &UpdateColumn,
+) -> Result<(), ApiError> {
+    let column = get_column(pool, user_id, id).await?;
+    let name = match &payload.name {
+        Some(name) => validate_name(name)?,
+        None => column.name,
+    };
+    let done = payload.done.unwrap_or(column.done);
+    let wip_limit = match payload.wip_limit {
+        Some(limit) => validate_wip_limit(limit)?,
+        None => column.wip_limit,
+    };
+
+    // Recurring tasks done along with the column come back once it is saved
+    let completed: Vec<i64> =
+        match done && !column.done {
+            true => sqlx::query_scalar(
+                "SELECT id FROM tasks WHERE column_id = (?1) AND NOT done AND deleted_at IS NULL;",
+            )
+            .bind(id)
+            .fetch_all(pool)
+            .await?,
+            false => Vec::new(),
+        };
+    for task_id in &completed {
+        super::task::require_unblocked(pool, *task_id).await?;
+    }
+    // Tasks change their done state along with the column
+    let mut before = Vec::new();
+    if done != column.done {
+        let task_ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT id FROM tasks WHERE column_id = (?1) AND deleted_at IS NULL;",
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await?;
+        let mut conn = pool.acquire().await?;
+        for task_id in task_ids {
+            let snapshot = super::audit::snapshot(&mut conn, AuditEntity::Task, task_id).await?;
+            before.push((task_id, snapshot));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+            UPDATE board_columns
+            SET name = (?2), done = (?3), wip_limit = (?4), wip_block = (?5)
+            WHERE id = (?1)
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(done)
+    .bind(wip_limit)
+    .bind(payload.wip_block.unwrap_or(column.wip_block))
+    .execute(&mut *tx)
+    .await?;
+    if done != column.done {
+        sqlx::query("UPDATE tasks SET done = (?2) WHERE column_id = (?1) AND deleted_at IS NULL;")
+            .bind(id)
+            .bind(done)
+            .execute(&mut *tx)
+            .await?;
+    }
+    if let Some(position) = payload.position {
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT id FROM board_columns WHERE project_id = (?1) ORDER BY position, id;",
+        )
+        .bind(column.project_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        renumber(&mut tx, "board_columns", &insert_at(ids, id, position)).await?;
+    }
+    for task_id in completed {
+        super::task::recur(&mut tx, user_id, task_id).await?;
+    }
+    for (task_id, before) in before {
+        super::audit::record(
+            &mut tx,
+            user_id,
+            AuditAction::Update,
+            AuditEntity::Task,
+            task_id,
+            before,
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Puts a column of `user_id` back the way it was, to undo a change to it.
+/// Its tasks stay as they are, their changes are undone from the audit log.
+pub(crate) async fn restore_column(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    column: &Column,
+) -> Result<(), ApiError> {
+    get_column(pool, user_id, column.id).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+            UPDATE board_columns
+            SET name = (?2), done = (?3), wip_limit = (?4), wip_block = (?5)
+            WHERE id = (?1)
+        ;
+        "#,
+    )
+    .bind(column.id)
+    .bind(&column.name)
+    .bind(column.done)
+    .bind(column.wip_limit)
+    .bind(column.wip_block)
+    .execute(&mut *tx)
+    .await?;
+    let ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM board_columns WHERE project_id = (?1) ORDER BY position, id;",
+    )
+    .bind(column.project_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    renumber(
+        &mut tx,
+        "board_columns",
+        &insert_at(ids, column.id, column.position),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Only empty columns can be deleted and every board keeps at least one column.
+pub async fn delete_column(pool: &sqlx::SqlitePool, user_id: i64, id: i64) -> Result<(), ApiError> {
+    let column = get_column(pool, user_id, id).await?;
+    let tasks: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM tasks WHERE column_id = (?1) AND deleted_at IS NULL;",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    if tasks > 0 {
+        return Err(ApiError::Validation(format!(
+            "Column {} still holds {tasks} tasks, move them first",
+            column.name
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM board_columns WHERE id = (?1);")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    let ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM board_columns WHERE project_id = (?1) ORDER BY position, id;",
+    )
+    .bind(column.project_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    if ids.is_empty() {
+        return Err(ApiError::Validation(
+            "The last column of a board can't be deleted".to_string(),
+        ));
+    }
+    renumber(&mut tx, "board_columns", &ids).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Moves a task into a column of its project's board, at `position` within that column.
+/// The task takes over the done state of the column, so blocked tasks can't move into a done column.
+/// Moving into a full column that blocks on its WIP limit fails, reordering within it does not.
+pub async fn move_task(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    payload: &MoveTask,
+) -> Result<(), ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Write).await?;
+    let before =
+        super::audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, task_id).await?;
+    let (owner_id, project_id, column_id, done): (i64, Option<i64>, Option<i64>, bool) =
+        sqlx::query_as("SELECT owner_id, project_id, column_id, done FROM tasks WHERE id = (?1);")
+            .bind(task_id)
+            .fetch_one(pool)
+            .await?;
+    let column = get_column(pool, owner_id, payload.column_id)
+        .await
+        .ok()
+        .filter(|column| Some(column.project_id) == project_id)
+        .ok_or_else(|| {
+            ApiError::Validation(format!(
+                "No column {} on the board of this task",
+                payload.column_id
+            ))
+        })?;
+
+    if column.done && !done {
+        super::task::require_unblocked(pool, task_id).await?;
+    }
+
+    let mut tx = pool.begin().await?;
+    let ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM tasks WHERE column_id = (?1) AND deleted_at IS NULL ORDER BY position, id;",
+    )
+    .bind(column.id)
+    .fetch_all(&mut *tx)
+    .await?;
+    if let Some(limit) = column.wip_limit {
+        if column.wip_block && column_id != Some(column.id) && ids.len() as i64 >= limit {
+            return Err(ApiError::Validation(format!(
+                "Column {} is at its WIP limit of {limit}",
+                column.name
+            )));
+        }
+    }
+    sqlx::query("UPDATE tasks SET column_id = (?2), done = (?3) WHERE id = (?1);")
+        .bind(task_id)
+        .bind(column.id)
+        .bind(column.done)
+        .execute(&mut *tx)
+        .await?;
+    super::task::record_transition(&mut *tx, task_id, column.id).await?;
+    renumber(&mut tx, "tasks", &insert_at(ids, task_id, payload.position)).await?;
+    if column.done && !done {
+        super::task::recur(&mut tx, user_id, task_id).await?;
+    }
+    super::audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        task_id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use crate::{
+    api::types::{
+        comment::{Comment, CreateComment, UpdateComment},
+        task::Access,
+    },
+    data_access::utils::now,
+    error::ApiError,
+};
+
+/// Longest comment body, in characters.
+const MAX_BODY_CHARS: usize = 10_000;
+
+/// Columns of a [`Comment`], joined with the name of its author.
+const COMMENT_COLUMNS: &str = r#"
+    SELECT m.id, m.task_id, m.user_id, u.name AS author, m.body, m.created, m.edited
+    FROM comments m JOIN users u ON u.id = m.user_id
+"#;
+
+fn validate_body(body: &str) -> Result<String, ApiError> {
+    match body.trim() {
+        "" => Err(ApiError::Validation(
+            "Comment must not be empty".to_string(),
+        )),
+        body if body.chars().count() > MAX_BODY_CHARS => Err(ApiError::Validation(format!(
+            "Comment must not be longer than {MAX_BODY_CHARS} characters"
+        ))),
+        body => Ok(body.to_string()),
+    }
+}
+
+/// The thread of a task, oldest first. Fetching it marks every comment as read for `user_id`.
+pub async fn get_for_task(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+) -> Result<Vec<Comment>, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Read).await?;
+    let comments = sqlx::query_as::<_, Comment>(&format!(
+        "{COMMENT_COLUMNS} WHERE m.task_id = (?1) ORDER BY m.id;"
+    ))
+    .bind(task_id)
+    .fetch_all(pool)
+    .await?;
+
+    if let Some(last) = comments.last() {
+        sqlx::query(
+            r#"
+                INSERT INTO comment_reads (task_id, user_id, last_read_id) VALUES (?1, ?2, ?3)
+                ON CONFLICT (task_id, user_id) DO UPDATE SET last_read_id = excluded.last_read_id
+            ;
+            "#,
+        )
+        .bind(task_id)
+        .bind(user_id)
+        .bind(last.id)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(comments)
+}
+
+pub async fn get_by_id(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    id: i64,
+) -> Result<Comment, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Read).await?;
+    sqlx::query_as::<_, Comment>(&format!(
+        "{COMMENT_COLUMNS} WHERE m.id = (?1) AND m.task_id = (?2);"
+    ))
+    .bind(id)
+    .bind(task_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.into())
+}
+
+/// Everybody who can see a task can discuss it.
+pub async fn create(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    payload: &CreateComment,
+) -> Result<i64, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Read).await?;
+    let body = validate_body(&payload.body)?;
+
+    sqlx::query("INSERT INTO comments (task_id, user_id, body, created) VALUES (?1, ?2, ?3, ?4);")
+        .bind(task_id)
+        .bind(user_id)
+        .bind(body)
+        .bind(now())
+        .execute(pool)
+        .await
+        .map(|result| result.last_insert_rowid())
+        .map_err(|e| e.into())
+}
+
+/// Only the author can change a comment.
+pub async fn update(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    id: i64,
+    payload: &UpdateComment,
+) -> Result<(), ApiError> {
+    let comment = get_by_id(pool, user_id, task_id, id).await?;
+    if comment.user_id != user_id {
+        return Err(ApiError::Forbidden(
+            "Only the author can edit a comment".to_string(),
+        ));
+    }
+    let body = validate_body(&payload.body)?;
+
+    sqlx::query("UPDATE comments SET body = (?2), edited = (?3) WHERE id = (?1);")
+        .bind(id)
+        .bind(body)
+        .bind(now())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Authors can delete their comments, task owners every comment on their tasks.
+pub async fn delete(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    id: i64,
+) -> Result<(), ApiError> {
+    let comment = get_by_id(pool, user_id, task_id, id).await?;
+    let access = super::task::access(pool, user_id, task_id).await?;
+    if comment.user_id != user_id && !access.is_owner() {
+        return Err(ApiError::Forbidden(
+            "Only the author or the task owner can delete a comment".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM comments WHERE id = (?1);")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
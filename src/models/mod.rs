@@ -1,2 +1,14 @@
+pub mod attachment;
+pub mod audit;
+pub mod board;
+pub mod comment;
+pub mod project;
+pub mod recurrence;
+pub mod session;
+pub mod tag;
 pub mod task;
 pub mod todo;
+pub mod tracking;
+pub mod trash;
+pub mod undo;
+pub mod user;
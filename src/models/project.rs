@@ -0,0 +1,185 @@
+use sqlx::SqliteConnection;
+
+use crate::{
+    api::types::project::{CreateProject, Project, UpdateProject},
+    error::ApiError,
+};
+
+/// Columns of a [`Project`], selected from `projects p`.
+const SELECT_PROJECT: &str = r#"
+    SELECT p.id, p.name, p.archived,
+        (SELECT COUNT(*) FROM tasks t WHERE t.project_id = p.id AND t.deleted_at IS NULL) AS tasks
+    FROM projects p
+"#;
+
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    match name.trim() {
+        "" => Err(ApiError::Validation(
+            "Project name must not be empty".to_string(),
+        )),
+        name => Ok(name.to_string()),
+    }
+}
+
+/// All projects of `user_id`, archived ones last.
+pub async fn get_all(pool: &sqlx::SqlitePool, user_id: i64) -> Result<Vec<Project>, sqlx::Error> {
+    sqlx::query_as::<_, Project>(&format!(
+        "{SELECT_PROJECT} WHERE p.owner_id = (?1) ORDER BY p.archived, p.name COLLATE NOCASE;"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// A project of `user_id`, `RowNotFound` for projects of other users.
+pub async fn get_by_id(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Project, sqlx::Error> {
+    sqlx::query_as::<_, Project>(&format!(
+        "{SELECT_PROJECT} WHERE p.id = (?1) AND p.owner_id = (?2);"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// The project tasks go to when none is given, the oldest one that is not archived.
+/// Creates an "Inbox" if there is none and moves tasks without project into it.
+pub(crate) async fn default_for(
+    pool: &sqlx::SqlitePool,
+    owner_id: i64,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let id = default_in(&mut tx, owner_id).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// [`default_for`] on a connection that may be in the middle of a transaction.
+pub(crate) async fn default_in(
+    conn: &mut SqliteConnection,
+    owner_id: i64,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query(
+        r#"
+            INSERT INTO projects (owner_id, name)
+            SELECT ?1, 'Inbox'
+            WHERE NOT EXISTS (SELECT 1 FROM projects WHERE owner_id = (?1) AND NOT archived)
+        ;
+        "#,
+    )
+    .bind(owner_id)
+    .execute(&mut *conn)
+    .await?;
+    let id: i64 = sqlx::query_scalar(
+        "SELECT id FROM projects WHERE owner_id = (?1) AND NOT archived ORDER BY id LIMIT 1;",
+    )
+    .bind(owner_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE tasks SET project_id = (?2) WHERE owner_id = (?1) AND project_id IS NULL;")
+        .bind(owner_id)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    super::board::ensure_columns(conn, id).await?;
+
+    Ok(id)
+}
+
+/// The project `id` of `user_id`, or the default project if no id is given.
+pub(crate) async fn resolve(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: Option<i64>,
+) -> Result<Project, sqlx::Error> {
+    let id = match id {
+        Some(id) => id,
+        None => default_for(pool, user_id).await?,
+    };
+    get_by_id(pool, user_id, id).await
+}
+
+/// Like [`resolve`], but fails for archived projects, which take no new tasks.
+pub(crate) async fn resolve_open(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: Option<i64>,
+) -> Result<Project, ApiError> {
+    let project = resolve(pool, user_id, id).await?;
+    match project.archived {
+        true => Err(ApiError::Validation(format!(
+            "Project {} is archived",
+            project.name
+        ))),
+        false => Ok(project),
+    }
+}
+
+/// Creates a project with the default board columns.
+pub async fn create(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    payload: &CreateProject,
+) -> Result<i64, ApiError> {
+    let name = validate_name(&payload.name)?;
+    let mut tx = pool.begin().await?;
+    let id = sqlx::query("INSERT INTO projects (owner_id, name) VALUES (?1, ?2);")
+        .bind(user_id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+    super::board::ensure_columns(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub async fn update(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    payload: &UpdateProject,
+) -> Result<(), ApiError> {
+    let project = get_by_id(pool, user_id, id).await?;
+    let name = match &payload.name {
+        Some(name) => validate_name(name)?,
+        None => project.name,
+    };
+
+    sqlx::query("UPDATE projects SET name = (?2), archived = (?3) WHERE id = (?1);")
+        .bind(id)
+        .bind(name)
+        .bind(payload.archived.unwrap_or(project.archived))
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.into())
+}
+
+/// Only empty projects can be deleted, their columns go with them.
+/// Trashed tasks of the project lose it, they are put into the default project when restored.
+pub async fn delete(pool: &sqlx::SqlitePool, user_id: i64, id: i64) -> Result<(), ApiError> {
+    let project = get_by_id(pool, user_id, id).await?;
+    if project.tasks > 0 {
+        return Err(ApiError::Validation(format!(
+            "Project {} still holds {} tasks, move or delete them first",
+            project.name, project.tasks
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE tasks SET project_id = NULL, column_id = NULL WHERE project_id = (?1) AND deleted_at IS NOT NULL;")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM projects WHERE id = (?1);")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await.map_err(|e| e.into())
+}
@@ -0,0 +1,602 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+use crate::{
+    api::types::recurrence::{Recurrence, Weekday},
+    error::ApiError,
+};
+
+/// How far [`next`] looks ahead for a date matching a rule, rules matching
+/// no day within it end their series.
+const MAX_SEARCH_DAYS: u64 = 3660;
+
+/// The longest gap a recurrence after completion may leave, about ten years.
+const MAX_AFTER_COMPLETION_DAYS: u32 = 3660;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The supported part of an RFC 5545 `RRULE`, weeks start on Monday.
+#[derive(Debug)]
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    weekdays: Vec<Weekday>,
+    month_days: Vec<i32>,
+    months: Vec<u32>,
+    count: Option<u32>,
+    until: Option<NaiveDate>,
+}
+
+fn invalid(message: String) -> ApiError {
+    ApiError::Validation(format!("Invalid recurrence rule: {message}"))
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, ApiError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| invalid(format!("{name}={value} is not a number")))
+}
+
+/// Parts of a rule, without the optional `RRULE:` prefix.
+fn parts(rrule: &str) -> impl Iterator<Item = &str> {
+    let rrule = rrule.trim();
+    let rrule = match rrule.get(..6) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &rrule[6..],
+        _ => rrule,
+    };
+    rrule
+        .split(';')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+}
+
+fn parse(rrule: &str) -> Result<Rule, ApiError> {
+    let mut frequency = None;
+    let mut rule = Rule {
+        frequency: Frequency::Daily,
+        interval: 1,
+        weekdays: Vec::new(),
+        month_days: Vec::new(),
+        months: Vec::new(),
+        count: None,
+        until: None,
+    };
+
+    for part in parts(rrule) {
+        let (name, value) = part
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("expected NAME=VALUE, got {part}")))?;
+        let values = || value.split(',').map(str::trim);
+        match name.trim().to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = Some(match value.trim().to_ascii_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    other => return Err(invalid(format!("FREQ={other} is not supported"))),
+                })
+            }
+            "INTERVAL" => rule.interval = parse_number("INTERVAL", value)?,
+            "BYDAY" => {
+                rule.weekdays = values()
+                    .map(|day| {
+                        Weekday::parse(day).ok_or_else(|| {
+                            invalid(format!("BYDAY={day} is not supported, expected MO to SU"))
+                        })
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            "BYMONTHDAY" => {
+                rule.month_days = values()
+                    .map(|day| parse_number::<i32>("BYMONTHDAY", day))
+                    .collect::<Result<_, _>>()?;
+                if let Some(day) = rule
+                    .month_days
+                    .iter()
+                    .find(|day| **day == 0 || day.abs() > 31)
+                {
+                    return Err(invalid(format!("BYMONTHDAY={day} is out of range")));
+                }
+            }
+            "BYMONTH" => {
+                rule.months = values()
+                    .map(|month| parse_number::<u32>("BYMONTH", month))
+                    .collect::<Result<_, _>>()?;
+                if let Some(month) = rule.months.iter().find(|month| !(1..=12).contains(*month)) {
+                    return Err(invalid(format!("BYMONTH={month} is out of range")));
+                }
+            }
+            "COUNT" => rule.count = Some(parse_number("COUNT", value)?),
+            "UNTIL" => {
+                let value = value.trim();
+                rule.until = Some(
+                    value
+                        .get(..8)
+                        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+                        .ok_or_else(|| {
+                            invalid(format!("UNTIL={value} is not a date like 20240131"))
+                        })?,
+                )
+            }
+            other => return Err(invalid(format!("{other} is not supported"))),
+        }
+    }
+
+    rule.frequency = frequency.ok_or_else(|| invalid("FREQ is missing".to_string()))?;
+    if rule.interval == 0 {
+        return Err(invalid("INTERVAL must be at least 1".to_string()));
+    }
+    if rule.count == Some(0) {
+        return Err(invalid("COUNT must be at least 1".to_string()));
+    }
+    if rule.count.is_some() && rule.until.is_some() {
+        return Err(invalid("COUNT and UNTIL can't be combined".to_string()));
+    }
+    Ok(rule)
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap_or(date);
+    let next = first
+        .checked_add_months(chrono::Months::new(1))
+        .unwrap_or(first);
+    (next - first).num_days() as u32
+}
+
+fn month_number(date: NaiveDate) -> i64 {
+    i64::from(date.year()) * 12 + i64::from(date.month0())
+}
+
+impl Rule {
+    /// Whether `date` is in a period of the rule, counted from `start`.
+    fn in_period(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        let periods = match self.frequency {
+            Frequency::Daily => (date - start).num_days(),
+            Frequency::Weekly => {
+                (date.week(chrono::Weekday::Mon).first_day()
+                    - start.week(chrono::Weekday::Mon).first_day())
+                .num_days()
+                    / 7
+            }
+            Frequency::Monthly => month_number(date) - month_number(start),
+            Frequency::Yearly => i64::from(date.year() - start.year()),
+        };
+        periods % i64::from(self.interval) == 0
+    }
+
+    /// Whether `date` is an occurrence of a series starting at `start`.
+    /// Parts left out of the rule are taken from `start`, as RFC 5545 does.
+    fn matches(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        let by_day = !self.weekdays.is_empty();
+        let by_month_day = !self.month_days.is_empty();
+        let month_ok = match self.months.is_empty() {
+            false => self.months.contains(&date.month()),
+            true => {
+                self.frequency != Frequency::Yearly
+                    || by_day
+                    || by_month_day
+                    || date.month() == start.month()
+            }
+        };
+        let weekday_ok = match by_day {
+            true => self.weekdays.contains(&date.weekday().into()),
+            false => self.frequency != Frequency::Weekly || date.weekday() == start.weekday(),
+        };
+        let day_ok = match by_month_day {
+            true => {
+                let last = days_in_month(date) as i32;
+                self.month_days.iter().any(|day| match *day > 0 {
+                    true => *day == date.day() as i32,
+                    false => last + day + 1 == date.day() as i32,
+                })
+            }
+            false => {
+                !matches!(self.frequency, Frequency::Monthly | Frequency::Yearly)
+                    || by_day
+                    || date.day() == start.day()
+            }
+        };
+        month_ok && weekday_ok && day_ok && self.in_period(start, date)
+    }
+
+    /// The first occurrence after `after`, for a series starting at `start`.
+    fn next_after(&self, start: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        after
+            .checked_add_days(Days::new(1))?
+            .iter_days()
+            .take(MAX_SEARCH_DAYS as usize)
+            .take_while(|date| self.until.is_none_or(|until| *date <= until))
+            .find(|date| self.matches(start, *date))
+    }
+}
+
+/// The first date after `after` on day `day` of its month, or on the last day of a shorter month.
+fn next_day_of_month(day: u32, after: NaiveDate) -> Option<NaiveDate> {
+    let first = after.with_day(1)?;
+    (0..2).find_map(|months| {
+        let month = first.checked_add_months(Months::new(months))?;
+        let date = month.with_day(day.min(days_in_month(month)))?;
+        (date > after).then_some(date)
+    })
+}
+
+/// Checks a recurrence before it is stored, trimming a given rule.
+pub(crate) fn validate(recurrence: &Recurrence) -> Result<Recurrence, ApiError> {
+    match recurrence {
+        Recurrence::Weekly { weekdays } if weekdays.is_empty() => Err(ApiError::Validation(
+            "Weekly recurrence needs at least one weekday".to_string(),
+        )),
+        Recurrence::Weekly { weekdays } => {
+            let mut weekdays = weekdays.clone();
+            weekdays.sort_unstable();
+            weekdays.dedup();
+            Ok(Recurrence::Weekly { weekdays })
+        }
+        Recurrence::Monthly { day } if !(1..=31).contains(day) => Err(ApiError::Validation(
+            format!("Invalid day of month {day}, expected 1 to 31"),
+        )),
+        Recurrence::AfterCompletion { days: 0 } => Err(ApiError::Validation(
+            "Recurrence after completion needs at least 1 day".to_string(),
+        )),
+        Recurrence::AfterCompletion { days } if *days > MAX_AFTER_COMPLETION_DAYS => {
+            Err(ApiError::Validation(format!(
+                "Recurrence after completion can't be more than {MAX_AFTER_COMPLETION_DAYS} days"
+            )))
+        }
+        Recurrence::Rule { rrule } => {
+            parse(rrule)?;
+            Ok(Recurrence::Rule {
+                rrule: rrule.trim().to_string(),
+            })
+        }
+        other => Ok(other.clone()),
+    }
+}
+
+/// The rule with its `COUNT` lowered by one, for the next occurrence.
+fn count_down(rrule: &str, count: u32) -> String {
+    parts(rrule)
+        .map(
+            |part| match part.to_ascii_uppercase().starts_with("COUNT=") {
+                true => format!("COUNT={}", count - 1),
+                false => part.to_string(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// The due date of the occurrence following one due at `due` and done on `completed`,
+/// together with the recurrence it carries on. `None` once the series is over.
+/// Occurrences are never due on or before the day the previous one was done.
+pub(crate) fn next(
+    recurrence: &Recurrence,
+    due: Option<NaiveDate>,
+    completed: NaiveDate,
+) -> Result<Option<(NaiveDate, Recurrence)>, ApiError> {
+    let rrule = match recurrence {
+        Recurrence::Daily => "FREQ=DAILY".to_string(),
+        Recurrence::Weekly { weekdays } => format!(
+            "FREQ=WEEKLY;BYDAY={}",
+            weekdays
+                .iter()
+                .map(|day| day.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        // Unlike BYMONTHDAY in a rule, the preset doesn't skip months without the day
+        Recurrence::Monthly { day } => {
+            let after = due.unwrap_or(completed).max(completed);
+            return Ok(next_day_of_month(*day, after).map(|date| (date, recurrence.clone())));
+        }
+        Recurrence::AfterCompletion { days } => {
+            let date = completed
+                .checked_add_days(Days::new(u64::from(*days)))
+                .ok_or_else(|| {
+                    ApiError::Validation(format!(
+                        "Recurrence after completion of {days} days is out of range"
+                    ))
+                })?;
+            return Ok(Some((date, recurrence.clone())));
+        }
+        Recurrence::Rule { rrule } => rrule.clone(),
+    };
+
+    let rule = parse(&rrule)?;
+    if rule.count.is_some_and(|count| count <= 1) {
+        return Ok(None);
+    }
+    let start = due.unwrap_or(completed);
+    let Some(date) = rule.next_after(start, start.max(completed)) else {
+        return Ok(None);
+    };
+    let recurrence = match (recurrence, rule.count) {
+        (Recurrence::Rule { rrule }, Some(count)) => Recurrence::Rule {
+            rrule: count_down(rrule, count),
+        },
+        (recurrence, _) => recurrence.clone(),
+    };
+    Ok(Some((date, recurrence)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+    }
+
+    fn rule(rrule: &str) -> Recurrence {
+        Recurrence::Rule {
+            rrule: rrule.to_string(),
+        }
+    }
+
+    fn validation_error<T>(result: Result<T, ApiError>) -> String {
+        match result {
+            Err(ApiError::Validation(message)) => message,
+            Err(_) => panic!("expected a validation error, got another error"),
+            Ok(_) => panic!("expected a validation error, got a value"),
+        }
+    }
+
+    /// The next occurrence, `None` once the series is over.
+    fn next_of(
+        recurrence: &Recurrence,
+        due: Option<NaiveDate>,
+        completed: NaiveDate,
+    ) -> Option<(NaiveDate, Recurrence)> {
+        match next(recurrence, due, completed) {
+            Ok(next) => next,
+            Err(_) => panic!("expected {recurrence:?} to have a next occurrence"),
+        }
+    }
+
+    fn next_date(
+        recurrence: &Recurrence,
+        due: Option<NaiveDate>,
+        completed: NaiveDate,
+    ) -> Option<NaiveDate> {
+        next_of(recurrence, due, completed).map(|(date, _)| date)
+    }
+
+    #[test]
+    fn parse_reads_all_supported_parts() {
+        let Ok(rule) = parse(
+            " rrule:freq=yearly; interval=2;byday=mo,FR;BYMONTHDAY=1,-1;BYMONTH=3,12;UNTIL=20301231T000000Z",
+        ) else {
+            panic!("expected the rule to parse");
+        };
+        assert_eq!(rule.frequency, Frequency::Yearly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.weekdays, vec![Weekday::Mo, Weekday::Fr]);
+        assert_eq!(rule.month_days, vec![1, -1]);
+        assert_eq!(rule.months, vec![3, 12]);
+        assert_eq!(rule.count, None);
+        assert_eq!(rule.until, Some(date(2030, 12, 31)));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_rules() {
+        for rrule in [
+            "",
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;INTERVAL=x",
+            "FREQ=DAILY;COUNT=0",
+            "FREQ=DAILY;COUNT=2;UNTIL=20300101",
+            "FREQ=DAILY;UNTIL=2030",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=MONTHLY;BYMONTHDAY=0",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=YEARLY;BYMONTH=13",
+            "FREQ=DAILY;BYSETPOS=1",
+            "FREQ",
+        ] {
+            assert!(parse(rrule).is_err(), "{rrule} should be rejected");
+        }
+    }
+
+    #[test]
+    fn validate_normalizes_weekdays_and_rules() {
+        let Ok(weekly) = validate(&Recurrence::Weekly {
+            weekdays: vec![Weekday::Fr, Weekday::Mo, Weekday::Fr],
+        }) else {
+            panic!("expected the weekdays to be valid");
+        };
+        assert_eq!(
+            weekly,
+            Recurrence::Weekly {
+                weekdays: vec![Weekday::Mo, Weekday::Fr]
+            }
+        );
+
+        let Ok(trimmed) = validate(&rule("  FREQ=DAILY ")) else {
+            panic!("expected the rule to be valid");
+        };
+        assert_eq!(trimmed, rule("FREQ=DAILY"));
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_values() {
+        for recurrence in [
+            Recurrence::Weekly {
+                weekdays: Vec::new(),
+            },
+            Recurrence::Monthly { day: 0 },
+            Recurrence::Monthly { day: 32 },
+            Recurrence::AfterCompletion { days: 0 },
+            Recurrence::AfterCompletion {
+                days: MAX_AFTER_COMPLETION_DAYS + 1,
+            },
+            Recurrence::AfterCompletion { days: u32::MAX },
+            rule("FREQ=SECONDLY"),
+        ] {
+            validation_error(validate(&recurrence));
+        }
+        assert!(validate(&Recurrence::AfterCompletion {
+            days: MAX_AFTER_COMPLETION_DAYS
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn next_follows_the_presets() {
+        let monday = date(2024, 1, 1);
+        assert_eq!(
+            next_date(&Recurrence::Daily, Some(monday), monday),
+            Some(date(2024, 1, 2))
+        );
+        assert_eq!(
+            next_date(
+                &Recurrence::Weekly {
+                    weekdays: vec![Weekday::Mo, Weekday::Th]
+                },
+                Some(monday),
+                monday
+            ),
+            Some(date(2024, 1, 4))
+        );
+        // Months without the day fall back to their last day
+        assert_eq!(
+            next_date(
+                &Recurrence::Monthly { day: 31 },
+                Some(date(2024, 1, 31)),
+                date(2024, 1, 31)
+            ),
+            Some(date(2024, 2, 29))
+        );
+        assert_eq!(
+            next_date(
+                &Recurrence::Monthly { day: 31 },
+                Some(date(2024, 2, 29)),
+                date(2024, 2, 29)
+            ),
+            Some(date(2024, 3, 31))
+        );
+        assert_eq!(
+            next_date(
+                &Recurrence::Monthly { day: 15 },
+                Some(date(2024, 1, 10)),
+                date(2024, 1, 12)
+            ),
+            Some(date(2024, 1, 15))
+        );
+        assert_eq!(
+            next_date(
+                &Recurrence::AfterCompletion { days: 3 },
+                Some(monday),
+                date(2024, 1, 10)
+            ),
+            Some(date(2024, 1, 13))
+        );
+    }
+
+    #[test]
+    fn next_is_never_due_on_or_before_completion() {
+        assert_eq!(
+            next_date(
+                &Recurrence::Daily,
+                Some(date(2024, 1, 1)),
+                date(2024, 1, 10)
+            ),
+            Some(date(2024, 1, 11))
+        );
+        assert_eq!(
+            next_date(&rule("FREQ=WEEKLY;INTERVAL=2"), None, date(2024, 1, 1)),
+            Some(date(2024, 1, 15))
+        );
+    }
+
+    #[test]
+    fn next_follows_rules() {
+        assert_eq!(
+            next_date(
+                &rule("FREQ=MONTHLY;BYMONTHDAY=-1"),
+                Some(date(2024, 1, 31)),
+                date(2024, 1, 31)
+            ),
+            Some(date(2024, 2, 29))
+        );
+        assert_eq!(
+            next_date(
+                &rule("FREQ=YEARLY"),
+                Some(date(2024, 2, 29)),
+                date(2024, 2, 29)
+            ),
+            Some(date(2028, 2, 29))
+        );
+        assert_eq!(
+            next_date(
+                &rule("FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13"),
+                Some(date(2024, 1, 1)),
+                date(2024, 1, 1)
+            ),
+            Some(date(2024, 9, 13))
+        );
+    }
+
+    #[test]
+    fn next_counts_down_and_ends_series() {
+        assert_eq!(
+            next_of(
+                &rule("FREQ=DAILY;COUNT=3"),
+                Some(date(2024, 1, 1)),
+                date(2024, 1, 1)
+            ),
+            Some((date(2024, 1, 2), rule("FREQ=DAILY;COUNT=2")))
+        );
+        assert_eq!(
+            next_date(
+                &rule("FREQ=DAILY;COUNT=1"),
+                Some(date(2024, 1, 1)),
+                date(2024, 1, 1)
+            ),
+            None
+        );
+        assert_eq!(
+            next_date(
+                &rule("FREQ=DAILY;UNTIL=20240102"),
+                Some(date(2024, 1, 2)),
+                date(2024, 1, 2)
+            ),
+            None
+        );
+        // February 30th never comes
+        assert_eq!(
+            next_date(
+                &rule("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30"),
+                Some(date(2024, 1, 1)),
+                date(2024, 1, 1)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn next_does_not_overflow_at_the_end_of_the_calendar() {
+        validation_error(next(
+            &Recurrence::AfterCompletion { days: u32::MAX },
+            None,
+            date(2024, 1, 1),
+        ));
+        validation_error(next(
+            &Recurrence::AfterCompletion { days: 1 },
+            None,
+            NaiveDate::MAX,
+        ));
+        assert_eq!(
+            next_date(&Recurrence::Daily, Some(NaiveDate::MAX), NaiveDate::MAX),
+            None
+        );
+    }
+}
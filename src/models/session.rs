@@ -0,0 +1,90 @@
+use rand_core::{OsRng, RngCore};
+
+use crate::{
+    api::types::{time::DateTime, user::User},
+    data_access::utils::now,
+};
+
+/// 256 random bits, hex encoded.
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Starts a new session for `user_id` and returns its token.
+pub async fn create(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    expires: DateTime,
+) -> Result<String, sqlx::Error> {
+    let token = new_token();
+    sqlx::query(
+        r#"
+            INSERT INTO sessions (token, user_id, created, expires)
+            VALUES (?1, ?2, ?3, ?4);
+        "#,
+    )
+    .bind(&token)
+    .bind(user_id)
+    .bind(now())
+    .bind(expires)
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// The user logged in with `token`, as long as the session has not expired.
+pub async fn user_for(pool: &sqlx::SqlitePool, token: &str) -> Result<Option<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(
+        r#"
+            SELECT u.id, u.name, u.email, u.created FROM sessions s
+            JOIN users u ON u.id = s.user_id
+            WHERE s.token = (?1) AND s.expires > (?2)
+        ;
+        "#,
+    )
+    .bind(token)
+    .bind(now())
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete(pool: &sqlx::SqlitePool, token: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM sessions WHERE token = (?1);")
+        .bind(token)
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+/// Ends every session of a user but the one with `keep`, e.g. after a password change.
+pub async fn delete_others(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    keep: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM sessions WHERE user_id = (?1) AND token != (?2);")
+        .bind(user_id)
+        .bind(keep)
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+pub async fn count_for(pool: &sqlx::SqlitePool, user_id: i64) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE user_id = (?1) AND expires > (?2);")
+        .bind(user_id)
+        .bind(now())
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn purge_expired(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM sessions WHERE expires <= (?1);")
+        .bind(now())
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+
+use sqlx::{QueryBuilder, Row, Sqlite};
+
+use crate::{
+    api::types::{
+        tag::{is_valid_color, CreateTag, Tag, UpdateTag, DEFAULT_COLOR},
+        task::{Access, TaskHeader},
+        todo::TodoItem,
+    },
+    error::ApiError,
+};
+
+/// The kinds of items tags can be attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TagOwner {
+    Todo,
+    Task,
+}
+
+impl TagOwner {
+    fn mapping_table(self) -> &'static str {
+        match self {
+            Self::Todo => "todotags",
+            Self::Task => "tasktags",
+        }
+    }
+
+    fn owner_column(self) -> &'static str {
+        match self {
+            Self::Todo => "todo_id",
+            Self::Task => "task_id",
+        }
+    }
+
+    /// Fails unless `user_id` may change the tags of the item.
+    pub(crate) async fn require_write(
+        self,
+        pool: &sqlx::SqlitePool,
+        user_id: i64,
+        owner_id: i64,
+    ) -> Result<(), ApiError> {
+        match self {
+            Self::Todo => super::todo::require(pool, user_id, owner_id, Access::Write).await?,
+            Self::Task => super::task::require(pool, user_id, owner_id, Access::Write).await?,
+        };
+        Ok(())
+    }
+
+    /// Fails unless `user_id` can see the item.
+    pub(crate) async fn require_read(
+        self,
+        pool: &sqlx::SqlitePool,
+        user_id: i64,
+        owner_id: i64,
+    ) -> Result<(), ApiError> {
+        match self {
+            Self::Todo => super::todo::require(pool, user_id, owner_id, Access::Read).await?,
+            Self::Task => super::task::require(pool, user_id, owner_id, Access::Read).await?,
+        };
+        Ok(())
+    }
+
+    /// Route prefix of the owner, used to build tag toggle urls.
+    pub(crate) fn prefix(self) -> &'static str {
+        match self {
+            Self::Todo => crate::api::html::todo::NEST_PREFIX,
+            Self::Task => crate::api::html::task::NEST_PREFIX,
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    match name.trim() {
+        "" => Err(ApiError::Validation(
+            "Tag name must not be empty".to_string(),
+        )),
+        name => Ok(name.to_string()),
+    }
+}
+
+fn validate_color(color: &str) -> Result<String, ApiError> {
+    match is_valid_color(color) {
+        true => Ok(color.to_ascii_lowercase()),
+        false => Err(ApiError::Validation(format!(
+            "Invalid tag color {color}, expected #rrggbb"
+        ))),
+    }
+}
+
+/// The tags of `user_id`, tags of other users only show up on the items they share.
+pub async fn get_all(pool: &sqlx::SqlitePool, user_id: i64) -> Result<Vec<Tag>, sqlx::Error> {
+    sqlx::query_as::<_, Tag>("SELECT id, name, color FROM tags WHERE owner_id = (?1) ORDER BY name")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// A tag of `user_id`, `RowNotFound` for tags of other users.
+pub async fn get_by_id(pool: &sqlx::SqlitePool, user_id: i64, id: i64) -> Result<Tag, sqlx::Error> {
+    sqlx::query_as::<_, Tag>(
+        r#"
+            SELECT id, name, color FROM tags
+            WHERE id = (?1) AND owner_id = (?2)
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn create(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    payload: &CreateTag,
+) -> Result<i64, ApiError> {
+    let name = validate_name(&payload.name)?;
+    let color = validate_color(payload.color.as_deref().unwrap_or(DEFAULT_COLOR))?;
+
+    sqlx::query("INSERT INTO tags (owner_id, name, color) VALUES (?1, ?2, ?3);")
+        .bind(user_id)
+        .bind(name)
+        .bind(color)
+        .execute(pool)
+        .await
+        .map(|result| result.last_insert_rowid())
+        .map_err(|e| e.into())
+}
+
+pub async fn update(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    payload: &UpdateTag,
+) -> Result<(), ApiError> {
+    let old_tag = get_by_id(pool, user_id, id).await?;
+    let name = match &payload.name {
+        Some(name) => validate_name(name)?,
+        None => old_tag.name,
+    };
+    let color = match &payload.color {
+        Some(color) => validate_color(color)?,
+        None => old_tag.color,
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE tags
+        SET (name) = (?2),
+            (color) = (?3)
+        WHERE id = (?1);
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(color)
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.into())
+}
+
+/// Deleting a tag also removes it from every todo and task, via `ON DELETE CASCADE`.
+pub async fn delete(pool: &sqlx::SqlitePool, user_id: i64, id: i64) -> Result<(), sqlx::Error> {
+    let deleted = sqlx::query(
+        r#"
+            DELETE FROM tags
+            WHERE id = (?1) AND owner_id = (?2)
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    match deleted {
+        0 => Err(sqlx::Error::RowNotFound),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) async fn add(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    owner: TagOwner,
+    owner_id: i64,
+    tag_id: i64,
+) -> Result<(), ApiError> {
+    owner.require_write(pool, user_id, owner_id).await?;
+    // Users can only attach their own tags
+    get_by_id(pool, user_id, tag_id).await?;
+    sqlx::query(&format!(
+        "INSERT OR IGNORE INTO {} ({}, tag_id) VALUES (?1, ?2);",
+        owner.mapping_table(),
+        owner.owner_column()
+    ))
+    .bind(owner_id)
+    .bind(tag_id)
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.into())
+}
+
+pub(crate) async fn remove(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    owner: TagOwner,
+    owner_id: i64,
+    tag_id: i64,
+) -> Result<(), ApiError> {
+    owner.require_write(pool, user_id, owner_id).await?;
+    sqlx::query(&format!(
+        "DELETE FROM {} WHERE {} = (?1) AND tag_id = (?2);",
+        owner.mapping_table(),
+        owner.owner_column()
+    ))
+    .bind(owner_id)
+    .bind(tag_id)
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.into())
+}
+
+/// Tags of all given owners, fetched in a single query.
+pub(crate) async fn get_by_owners(
+    pool: &sqlx::SqlitePool,
+    owner: TagOwner,
+    owner_ids: &[i64],
+) -> Result<HashMap<i64, Vec<Tag>>, sqlx::Error> {
+    let mut by_owner: HashMap<i64, Vec<Tag>> = HashMap::new();
+    if owner_ids.is_empty() {
+        return Ok(by_owner);
+    }
+
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT m.{} AS owner_id, t.id, t.name, t.color FROM {} m JOIN tags t ON t.id = m.tag_id WHERE m.{} IN (",
+        owner.owner_column(),
+        owner.mapping_table(),
+        owner.owner_column()
+    ));
+    let mut ids = query.separated(", ");
+    for id in owner_ids {
+        ids.push_bind(id);
+    }
+    query.push(") ORDER BY t.name");
+
+    let rows = query
+        .build()
+        .map(|row: sqlx::sqlite::SqliteRow| {
+            (
+                row.get::<i64, _>("owner_id"),
+                Tag {
+                    id: row.get("id"),
+                    name: row.get("name"),
+                    color: row.get("color"),
+                },
+            )
+        })
+        .fetch_all(pool)
+        .await?;
+
+    for (owner_id, tag) in rows {
+        by_owner.entry(owner_id).or_default().push(tag);
+    }
+
+    Ok(by_owner)
+}
+
+pub(crate) async fn get_for(
+    pool: &sqlx::SqlitePool,
+    owner: TagOwner,
+    owner_id: i64,
+) -> Result<Vec<Tag>, sqlx::Error> {
+    Ok(get_by_owners(pool, owner, &[owner_id])
+        .await?
+        .remove(&owner_id)
+        .unwrap_or_default())
+}
+
+pub(crate) async fn attach_to_todos(
+    pool: &sqlx::SqlitePool,
+    todos: &mut [TodoItem],
+) -> Result<(), sqlx::Error> {
+    let ids = todos.iter().map(|todo| todo.id).collect::<Vec<_>>();
+    let mut tags = get_by_owners(pool, TagOwner::Todo, &ids).await?;
+    for todo in todos.iter_mut() {
+        todo.tags = tags.remove(&todo.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn attach_to_tasks(
+    pool: &sqlx::SqlitePool,
+    tasks: &mut [TaskHeader],
+) -> Result<(), sqlx::Error> {
+    let ids = tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+    let mut tags = get_by_owners(pool, TagOwner::Task, &ids).await?;
+    for task in tasks.iter_mut() {
+        task.tags = tags.remove(&task.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// Narrows a query on the owner table to rows carrying all of `tag_ids`.
+/// `id_column` names the id of the owner table, qualified if the query joins other tables.
+/// Expects the builder to already contain a `WHERE` clause.
+pub(crate) fn push_filter(
+    query: &mut QueryBuilder<Sqlite>,
+    owner: TagOwner,
+    id_column: &str,
+    tag_ids: &[i64],
+) {
+    let mut tag_ids = tag_ids.to_vec();
+    tag_ids.sort_unstable();
+    tag_ids.dedup();
+    if tag_ids.is_empty() {
+        return;
+    }
+
+    query.push(format!(
+        " AND {id_column} IN (SELECT {} FROM {} WHERE tag_id IN (",
+        owner.owner_column(),
+        owner.mapping_table()
+    ));
+    let mut ids = query.separated(", ");
+    for id in &tag_ids {
+        ids.push_bind(*id);
+    }
+    query.push(format!(
+        ") GROUP BY {} HAVING COUNT(DISTINCT tag_id) = ",
+        owner.owner_column()
+    ));
+    query.push_bind(tag_ids.len() as i64);
+    query.push(")");
+}
@@ -1,22 +1,227 @@
+use std::collections::HashMap;
+
+use chrono::{Days, NaiveDate, NaiveTime, Utc};
+use sqlx::{Connection, QueryBuilder, Sqlite, SqliteConnection};
+
 use crate::{
-    api::types::task::{CreateTask, TaskHeader, TaskModel},
+    api::types::{
+        audit::{AuditAction, AuditEntity, TaskSnapshot},
+        board::{Column, FlowDay, FlowMetrics, FlowQuery, TaskFlow},
+        recurrence::Recurrence,
+        task::{
+            Access, CreateTask, ShareTask, SortBy, TaskHeader, TaskModel, TaskQuery, TaskShare,
+            UpdateTask,
+        },
+        time::DateTime,
+        todo::{CreateTodo, TodoItem},
+        trash::TrashKind,
+    },
     data_access,
+    error::ApiError,
+    models::{
+        audit,
+        tag::{self, TagOwner},
+    },
 };
 
-pub async fn get_all_headers(pool: &sqlx::SqlitePool) -> Result<Vec<TaskHeader>, sqlx::Error> {
-    sqlx::query_as::<_, TaskHeader>("SELECT id, name FROM tasks")
+/// Longest range of days [`get_flow`] computes metrics for.
+const MAX_FLOW_DAYS: i64 = 366;
+
+/// Columns of a [`TaskHeader`], followed by [`push_select`].
+const HEADER_COLUMNS: &str = r#"
+    SELECT t.id, t.name, t.column_id, c.name AS status, t.project_id, p.name AS project, t.parent_id,
+        (SELECT COUNT(*) FROM tasks st WHERE st.parent_id = t.id AND st.deleted_at IS NULL) AS subtasks,
+        (SELECT COUNT(*) FROM tasks st WHERE st.parent_id = t.id AND st.deleted_at IS NULL AND st.done) AS subtasks_done,
+        (SELECT COUNT(*) FROM task_dependencies d JOIN tasks b ON b.id = d.blocker_id
+            WHERE d.task_id = t.id AND NOT b.done AND b.deleted_at IS NULL) AS open_blockers,
+        (SELECT COUNT(*) FROM comments m WHERE m.task_id = t.id) AS comments,
+        (SELECT COUNT(*) FROM tasktodos tt JOIN todos o ON o.id = tt.todo_id
+            WHERE tt.task_id = t.id AND o.deleted_at IS NULL) AS todos,
+        (SELECT COUNT(*) FROM tasktodos tt JOIN todos o ON o.id = tt.todo_id
+            WHERE tt.task_id = t.id AND o.deleted_at IS NULL AND o.done) AS todos_done,
+        t.priority, t.due, t.done, "#;
+
+/// Pushes the `access` and `unread_comments` columns of `user_id` and the `FROM` clause,
+/// narrowed to the tasks the user owns or has been shared, leaving out the trashed ones.
+/// The board column of each task is joined as `c`, its project as `p`.
+fn push_select(query: &mut QueryBuilder<Sqlite>, user_id: i64) {
+    query
+        .push("CASE WHEN t.owner_id = ")
+        .push_bind(user_id)
+        .push(" THEN 'owner' ELSE s.access END AS access")
+        .push(", (SELECT COUNT(*) FROM comments m WHERE m.task_id = t.id AND m.user_id <> ")
+        .push_bind(user_id)
+        .push(" AND m.id > COALESCE((SELECT r.last_read_id FROM comment_reads r WHERE r.task_id = t.id AND r.user_id = ")
+        .push_bind(user_id)
+        .push("), 0)) AS unread_comments")
+        .push(" FROM tasks t LEFT JOIN task_shares s ON s.task_id = t.id AND s.user_id = ")
+        .push_bind(user_id)
+        .push(" LEFT JOIN board_columns c ON c.id = t.column_id")
+        .push(" LEFT JOIN projects p ON p.id = t.project_id")
+        .push(" WHERE t.deleted_at IS NULL AND (t.owner_id = ")
+        .push_bind(user_id)
+        .push(" OR s.user_id IS NOT NULL)");
+}
+
+/// The access `user_id` has to a task, `RowNotFound` if the task is not visible at all.
+pub(crate) async fn access(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Access, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT ");
+    push_select(&mut builder, user_id);
+    builder.push(" AND t.id = ").push_bind(id);
+    builder
+        .build_query_scalar::<Access>()
+        .fetch_optional(pool)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Fails with `404` for tasks `user_id` can't see and `403` if the access is not sufficient.
+pub(crate) async fn require(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    needed: Access,
+) -> Result<Access, ApiError> {
+    let access = access(pool, user_id, id).await?;
+    match access >= needed {
+        true => Ok(access),
+        false => Err(ApiError::Forbidden(format!(
+            "Task {id} needs {} access",
+            needed.as_str()
+        ))),
+    }
+}
+
+pub async fn get_headers_matching(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    query: &TaskQuery,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(HEADER_COLUMNS);
+    push_select(&mut builder, user_id);
+    match query.project {
+        Some(project_id) => builder.push(" AND t.project_id = ").push_bind(project_id),
+        None => builder.push(" AND (p.archived IS NULL OR NOT p.archived)"),
+    };
+    if let Some(due) = query.due {
+        let (from, to) = due.range(Utc::now().date_naive());
+        builder.push(" AND NOT t.done AND t.due < ").push_bind(to);
+        if let Some(from) = from {
+            builder.push(" AND t.due >= ").push_bind(from);
+        }
+    }
+    if query.ready {
+        builder.push(
+            r#" AND NOT t.done AND NOT EXISTS (
+                SELECT 1 FROM task_dependencies d JOIN tasks b ON b.id = d.blocker_id
+                WHERE d.task_id = t.id AND NOT b.done AND b.deleted_at IS NULL
+            )"#,
+        );
+    }
+    if let Some(progress) = query.progress {
+        builder.push(progress.condition());
+    }
+    tag::push_filter(&mut builder, TagOwner::Task, "t.id", &query.tags);
+    builder.push(match query.sort {
+        Some(SortBy::Priority) => " ORDER BY t.priority DESC, t.due IS NULL, t.due, t.id",
+        Some(SortBy::Due) => " ORDER BY t.due IS NULL, t.due, t.priority DESC, t.id",
+        Some(SortBy::Created) => " ORDER BY t.created DESC, t.id DESC",
+        Some(SortBy::Name) => " ORDER BY t.name COLLATE NOCASE, t.id",
+        None => " ORDER BY t.id",
+    });
+
+    let mut headers = builder
+        .build_query_as::<TaskHeader>()
         .fetch_all(pool)
-        .await
+        .await?;
+    tag::attach_to_tasks(pool, &mut headers).await?;
+    Ok(headers)
+}
+
+/// The tasks of a project `user_id` owns, in board order.
+pub(crate) async fn get_board_headers(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    project_id: i64,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(HEADER_COLUMNS);
+    push_select(&mut builder, user_id);
+    builder
+        .push(" AND t.owner_id = ")
+        .push_bind(user_id)
+        .push(" AND t.project_id = ")
+        .push_bind(project_id)
+        .push(" ORDER BY t.position, t.id");
+
+    let mut headers = builder
+        .build_query_as::<TaskHeader>()
+        .fetch_all(pool)
+        .await?;
+    tag::attach_to_tasks(pool, &mut headers).await?;
+    Ok(headers)
+}
+
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    match name.trim() {
+        "" => Err(ApiError::Validation(
+            "Task name must not be empty".to_string(),
+        )),
+        name => Ok(name.to_string()),
+    }
 }
 
-pub async fn get_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<TaskModel, sqlx::Error> {
-    let task = data_access::task::select_row(id, &pool).await?;
+pub async fn get_header(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<TaskHeader, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(HEADER_COLUMNS);
+    push_select(&mut builder, user_id);
+    builder.push(" AND t.id = ").push_bind(id);
+
+    let mut header = builder
+        .build_query_as::<TaskHeader>()
+        .fetch_one(pool)
+        .await?;
+    header.tags = tag::get_for(pool, TagOwner::Task, id).await?;
+    Ok(header)
+}
+
+pub async fn get_by_id(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<TaskModel, sqlx::Error> {
+    let header = get_header(pool, user_id, id).await?;
+    let task = data_access::task::select_row(id, pool).await?;
     let (id, name, description, created, due, done) = task;
-    let todos = data_access::task::query_todos_by_id(id, &pool)
-        .await?
-        .into_iter()
-        .map(|values| values.into())
-        .collect::<Vec<_>>();
+    let todos = get_todos(pool, user_id, id).await?;
+    let parent = match header.parent_id {
+        Some(parent_id) => get_header(pool, user_id, parent_id)
+            .await
+            .ok()
+            .map(|parent| parent.name),
+        None => None,
+    };
+    let subtasks = get_subtasks(pool, user_id, id).await?;
+    let blockers = get_blockers(pool, user_id, id).await?;
+    let dependents = get_dependents(pool, user_id, id).await?;
+    let recurrence = get_recurrence(pool, id).await?;
+    let next_occurrence = match &recurrence {
+        Some(recurrence) => super::recurrence::next(
+            recurrence,
+            due.map(|due| due.date_naive()),
+            Utc::now().date_naive(),
+        )
+        .ok()
+        .flatten()
+        .map(|(date, _)| date.and_time(NaiveTime::MIN).and_utc()),
+        None => None,
+    };
 
     Ok(TaskModel {
         id,
@@ -25,15 +230,1336 @@ pub async fn get_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<TaskModel, sq
         created,
         due,
         done,
+        column_id: header.column_id,
+        status: header.status,
+        project_id: header.project_id,
+        project: header.project,
+        parent_id: header.parent_id,
+        parent,
+        subtasks,
+        blockers,
+        dependents,
+        priority: header.priority,
+        recurrence,
+        next_occurrence,
+        tracked: super::tracking::tracked(pool, id).await?,
+        access: header.access,
         todos,
+        tags: header.tags,
     })
 }
 
+/// The subtasks of a task `user_id` can see.
+pub async fn get_subtasks(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(HEADER_COLUMNS);
+    push_select(&mut builder, user_id);
+    builder
+        .push(" AND t.parent_id = ")
+        .push_bind(id)
+        .push(" ORDER BY t.done, t.id");
+
+    let mut headers = builder
+        .build_query_as::<TaskHeader>()
+        .fetch_all(pool)
+        .await?;
+    tag::attach_to_tasks(pool, &mut headers).await?;
+    Ok(headers)
+}
+
+/// A task and every task below it.
+async fn get_descendants(pool: &sqlx::SqlitePool, id: i64) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+            WITH RECURSIVE tree(id) AS (
+                SELECT (?1)
+                UNION SELECT t.id FROM tasks t JOIN tree ON t.parent_id = tree.id
+            )
+            SELECT id FROM tree
+        ;
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Checks that `user_id` owns `parent_id`, and that the parent is not `id` or below it.
+async fn validate_parent(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: Option<i64>,
+    parent_id: i64,
+) -> Result<i64, ApiError> {
+    require(pool, user_id, parent_id, Access::Owner).await?;
+    if let Some(id) = id {
+        if get_descendants(pool, id).await?.contains(&parent_id) {
+            return Err(ApiError::Validation(format!(
+                "Task {id} can't be a subtask of itself or one of its subtasks"
+            )));
+        }
+    }
+    Ok(parent_id)
+}
+
+/// Tasks of `user_id` that `id` can be moved below,
+/// open ones outside of its own subtree and the current parent.
+pub async fn get_parent_candidates(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let current = get_header(pool, user_id, id).await?.parent_id;
+    let subtree = get_descendants(pool, id).await?;
+    let query = TaskQuery {
+        sort: Some(SortBy::Name),
+        ..Default::default()
+    };
+    Ok(get_headers_matching(pool, user_id, &query)
+        .await?
+        .into_iter()
+        .filter(|task| task.access.is_owner() && !subtree.contains(&task.id))
+        .filter(|task| !task.done || Some(task.id) == current)
+        .collect())
+}
+
 pub async fn create_task(
     pool: &sqlx::SqlitePool,
+    user_id: i64,
     payload: &CreateTask,
-) -> Result<i64, sqlx::Error> {
-    let timestamp = data_access::utils::now();
-    let row = (payload.name.clone(), String::new(), timestamp, false);
-    data_access::task::insert_row(row, pool).await
+) -> Result<i64, ApiError> {
+    let name = validate_name(&payload.name)?;
+    let parent_id = match payload.parent_id {
+        Some(parent_id) => Some(validate_parent(pool, user_id, None, parent_id).await?),
+        None => None,
+    };
+    // Subtasks go into the project of their parent by default
+    let project_id = match (payload.project_id, parent_id) {
+        (None, Some(parent_id)) => get_header(pool, user_id, parent_id).await?.project_id,
+        (project_id, _) => project_id,
+    };
+    let project = super::project::resolve_open(pool, user_id, project_id).await?;
+    let recurrence = match &payload.recurrence {
+        Some(recurrence) => Some(super::recurrence::validate(recurrence)?),
+        None => None,
+    };
+    let row = (name, String::new(), payload.due, false);
+    let mut tx = pool.begin().await?;
+    let id =
+        data_access::task::insert_row(user_id, project.id, payload.priority, row, &mut *tx).await?;
+    if recurrence.is_some() {
+        set_recurrence(&mut *tx, id, recurrence.as_ref()).await?;
+    }
+    if parent_id.is_some() {
+        set_parent(&mut *tx, id, parent_id).await?;
+    }
+    super::board::ensure_columns(&mut tx, project.id).await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Create,
+        AuditEntity::Task,
+        id,
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// The project a task is moved to by an update, `None` if it stays where it is.
+async fn moved_to(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    access: Access,
+    project_id: i64,
+) -> Result<Option<i64>, ApiError> {
+    if get_header(pool, user_id, id).await?.project_id == Some(project_id) {
+        return Ok(None);
+    }
+    if access != Access::Owner {
+        return Err(ApiError::Forbidden(format!(
+            "Only the owner can move task {id} to another project"
+        )));
+    }
+    let project = super::project::resolve_open(pool, user_id, Some(project_id)).await?;
+    Ok(Some(project.id))
+}
+
+/// Updates the given fields of a task, keeping the stored value for every field left out.
+pub async fn update(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    payload: &UpdateTask,
+) -> Result<(), ApiError> {
+    let access = require(pool, user_id, id, Access::Write).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let (_, name, description, _, due, done) = data_access::task::select_row(id, pool).await?;
+    if !done && payload.done == Some(true) {
+        require_unblocked(pool, id).await?;
+    }
+    let name = match &payload.name {
+        Some(name) => validate_name(name)?,
+        None => name,
+    };
+    let row = (
+        name,
+        payload.description.clone().unwrap_or(description),
+        payload.due.unwrap_or(due),
+        payload.done.unwrap_or(done),
+    );
+    let project_id = match payload.project_id {
+        Some(project_id) => moved_to(pool, user_id, id, access, project_id).await?,
+        None => None,
+    };
+    let parent_id = match payload.parent_id {
+        Some(parent_id) if parent_id == get_header(pool, user_id, id).await?.parent_id => None,
+        Some(_) if access != Access::Owner => {
+            return Err(ApiError::Forbidden(format!(
+                "Only the owner can move task {id} below another task"
+            )))
+        }
+        Some(Some(parent_id)) => Some(Some(
+            validate_parent(pool, user_id, Some(id), parent_id).await?,
+        )),
+        Some(None) => Some(None),
+        None => None,
+    };
+    let recurrence = match &payload.recurrence {
+        Some(Some(recurrence)) => Some(Some(super::recurrence::validate(recurrence)?)),
+        Some(None) => Some(None),
+        None => None,
+    };
+
+    let mut tx = pool.begin().await?;
+    data_access::task::update_row(id, row, &mut *tx).await?;
+    if let Some(priority) = payload.priority {
+        sqlx::query("UPDATE tasks SET priority = (?2) WHERE id = (?1);")
+            .bind(id)
+            .bind(priority)
+            .execute(&mut *tx)
+            .await?;
+    }
+    if let Some(project_id) = project_id {
+        sqlx::query("UPDATE tasks SET project_id = (?2) WHERE id = (?1);")
+            .bind(id)
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    if let Some(recurrence) = recurrence {
+        set_recurrence(&mut *tx, id, recurrence.as_ref()).await?;
+    }
+    if let Some(parent_id) = parent_id {
+        set_parent(&mut *tx, id, parent_id).await?;
+    }
+    // Keeps the board column in line with the done state and project
+    if project_id.is_some() || payload.done.is_some_and(|new_done| new_done != done) {
+        super::board::replace_task(&mut tx, id).await?;
+    }
+    if !done && payload.done == Some(true) {
+        recur(&mut tx, user_id, id).await?;
+    }
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Puts a task back into a state recorded in the audit log, re-linking its todos and blockers
+/// and sharing it again as it was.
+/// A project that is gone by now keeps the task where it is, a parent that is gone is dropped.
+pub(crate) async fn restore_snapshot(
+    conn: &mut SqliteConnection,
+    id: i64,
+    snapshot: &TaskSnapshot,
+) -> Result<(), sqlx::Error> {
+    let (project_id, done): (Option<i64>, bool) =
+        sqlx::query_as("SELECT project_id, done FROM tasks WHERE id = (?1);")
+            .bind(id)
+            .fetch_one(&mut *conn)
+            .await?;
+    let mut tx = conn.begin().await?;
+    sqlx::query(
+        r#"
+            UPDATE tasks
+            SET name = (?2), description = (?3), due = (?4), done = (?5), priority = (?6),
+                project_id = COALESCE((SELECT id FROM projects WHERE id = (?7)), project_id),
+                parent_id = (SELECT id FROM tasks WHERE id = (?8))
+            WHERE id = (?1)
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(&snapshot.name)
+    .bind(&snapshot.description)
+    .bind(snapshot.due)
+    .bind(snapshot.done)
+    .bind(snapshot.priority)
+    .bind(snapshot.project_id)
+    .bind(snapshot.parent_id)
+    .execute(&mut *tx)
+    .await?;
+    set_recurrence(&mut *tx, id, snapshot.recurrence.as_ref()).await?;
+    // The column goes back too, as long as it is still on the board of the task
+    let column_id: Option<i64> = sqlx::query_scalar(
+        r#"
+            UPDATE tasks SET column_id = (?2)
+            WHERE id = (?1) AND (?2) IN (SELECT id FROM board_columns WHERE project_id = tasks.project_id)
+            RETURNING column_id
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(snapshot.column_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(column_id) = column_id {
+        record_transition(&mut *tx, id, column_id).await?;
+    }
+
+    sqlx::query("DELETE FROM tasktodos WHERE task_id = (?1);")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for todo_id in &snapshot.todos {
+        sqlx::query(
+            "INSERT INTO tasktodos (task_id, todo_id) SELECT ?1, id FROM todos WHERE id = (?2);",
+        )
+        .bind(id)
+        .bind(todo_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM task_dependencies WHERE task_id = (?1);")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for blocker_id in &snapshot.blockers {
+        sqlx::query(
+            "INSERT INTO task_dependencies (task_id, blocker_id) SELECT ?1, id FROM tasks WHERE id = (?2);",
+        )
+        .bind(id)
+        .bind(blocker_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    if let Some(shares) = &snapshot.shares {
+        sqlx::query("DELETE FROM task_shares WHERE task_id = (?1);")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for share in shares {
+            sqlx::query(
+                "INSERT INTO task_shares (task_id, user_id, access) SELECT ?1, id, ?3 FROM users WHERE id = (?2);",
+            )
+            .bind(id)
+            .bind(share.user_id)
+            .bind(share.access)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    // Keeps the board column in line with the done state and project, like `update`
+    if column_id.is_none() && (project_id != snapshot.project_id || done != snapshot.done) {
+        super::board::replace_task(&mut tx, id).await?;
+    }
+    tx.commit().await
+}
+
+/// The recurrence of a task, stored as json.
+pub(crate) async fn get_recurrence(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    id: i64,
+) -> Result<Option<Recurrence>, sqlx::Error> {
+    let recurrence: Option<String> =
+        sqlx::query_scalar("SELECT recurrence FROM tasks WHERE id = (?1);")
+            .bind(id)
+            .fetch_one(executor)
+            .await?;
+    recurrence
+        .map(|recurrence| serde_json::from_str(&recurrence))
+        .transpose()
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+async fn set_recurrence(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    id: i64,
+    recurrence: Option<&Recurrence>,
+) -> Result<(), sqlx::Error> {
+    let recurrence = recurrence.map(|recurrence| {
+        serde_json::to_string(recurrence).expect("recurrences always serialize to json")
+    });
+    sqlx::query("UPDATE tasks SET recurrence = (?2) WHERE id = (?1);")
+        .bind(id)
+        .bind(recurrence)
+        .execute(executor)
+        .await
+        .map(|_| ())
+}
+
+/// Creates the next occurrence of a recurring task that was just done, if its series goes on.
+/// The occurrence copies the task, with its parent, tags and shares, takes over its recurrence
+/// and is linked to its todos as well, which are reset to not done. The done task stops recurring.
+/// The occurrence is recorded as created by `user_id`, who completed the task,
+/// in the transaction that completes it.
+pub(crate) async fn recur(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    id: i64,
+) -> Result<Option<i64>, ApiError> {
+    let Some(recurrence) = get_recurrence(&mut *conn, id).await? else {
+        return Ok(None);
+    };
+    let (_, name, description, _, due, _) = data_access::task::select_row(id, &mut *conn).await?;
+    let next = super::recurrence::next(
+        &recurrence,
+        due.map(|due| due.date_naive()),
+        Utc::now().date_naive(),
+    )?;
+    // The todos are linked to the next occurrence as well, open again
+    let todo_ids: Vec<i64> = match next {
+        Some(_) => {
+            sqlx::query_scalar("SELECT todo_id FROM tasktodos WHERE task_id = (?1);")
+                .bind(id)
+                .fetch_all(&mut *conn)
+                .await?
+        }
+        None => Vec::new(),
+    };
+    let mut todos = Vec::new();
+    for todo_id in todo_ids {
+        todos.push((
+            todo_id,
+            audit::snapshot(conn, AuditEntity::Todo, todo_id).await?,
+        ));
+    }
+
+    set_recurrence(&mut *conn, id, None).await?;
+    let Some((date, recurrence)) = next else {
+        return Ok(None);
+    };
+    let next_id = sqlx::query(
+        r#"
+            INSERT INTO tasks (name, description, created, due, done, owner_id, project_id, parent_id, priority)
+            SELECT ?2, ?3, ?4, ?5, 0, owner_id, project_id, parent_id, priority FROM tasks WHERE id = (?1)
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(description)
+    .bind(Utc::now())
+    .bind(date.and_time(NaiveTime::MIN).and_utc())
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    set_recurrence(&mut *conn, next_id, Some(&recurrence)).await?;
+    for statement in [
+        "INSERT INTO tasktags (task_id, tag_id) SELECT ?2, tag_id FROM tasktags WHERE task_id = (?1);",
+        "INSERT INTO task_shares (task_id, user_id, access) SELECT ?2, user_id, access FROM task_shares WHERE task_id = (?1);",
+        "UPDATE todos SET done = 0 WHERE id IN (SELECT todo_id FROM tasktodos WHERE task_id = (?1));",
+        "INSERT INTO tasktodos (task_id, todo_id) SELECT ?2, todo_id FROM tasktodos WHERE task_id = (?1);",
+    ] {
+        sqlx::query(statement)
+            .bind(id)
+            .bind(next_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    let project_id: Option<i64> =
+        sqlx::query_scalar("SELECT project_id FROM tasks WHERE id = (?1);")
+            .bind(next_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    if let Some(project_id) = project_id {
+        super::board::ensure_columns(conn, project_id).await?;
+    }
+    audit::record(
+        &mut *conn,
+        user_id,
+        AuditAction::Create,
+        AuditEntity::Task,
+        next_id,
+        None,
+    )
+    .await?;
+    for (todo_id, before) in todos {
+        audit::record(
+            &mut *conn,
+            user_id,
+            AuditAction::Update,
+            AuditEntity::Todo,
+            todo_id,
+            before,
+        )
+        .await?;
+    }
+    Ok(Some(next_id))
+}
+
+async fn set_parent(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    id: i64,
+    parent_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE tasks SET parent_id = (?2) WHERE id = (?1);")
+        .bind(id)
+        .bind(parent_id)
+        .execute(executor)
+        .await
+        .map(|_| ())
+}
+
+/// Moves a task to the trash, where it keeps its todo links until it is restored or purged.
+/// Running timers on it are stopped.
+/// Tasks with open subtasks can't be deleted, done subtasks show up as top level tasks.
+pub async fn delete(pool: &sqlx::SqlitePool, user_id: i64, id: i64) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Owner).await?;
+    let open: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM tasks WHERE parent_id = (?1) AND NOT done AND deleted_at IS NULL;",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    if open > 0 {
+        return Err(ApiError::Validation(format!(
+            "Task {id} still has {open} open subtasks, finish or move them first"
+        )));
+    }
+
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    super::trash::trash_row(&mut tx, TrashKind::Task, id).await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Delete,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The todos linked to a task, including their tags.
+pub async fn get_todos(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Vec<TodoItem>, sqlx::Error> {
+    access(pool, user_id, id).await?;
+    let mut todos = data_access::task::query_todos_by_id(id, pool).await?;
+    tag::attach_to_todos(pool, &mut todos).await?;
+    Ok(todos)
+}
+
+/// Links a todo to a task, which shares the todo with everybody the task is shared with.
+/// So only own todos can be linked.
+pub async fn add_todo(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    todo_id: i64,
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    super::todo::require(pool, user_id, todo_id, Access::Owner).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    data_access::task::insert_todo_mapping(id, todo_id, &mut *tx).await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn remove_todo(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    todo_id: i64,
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    data_access::task::delete_todo_mapping(id, todo_id, &mut *tx).await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Creates a new todo that is linked to the task right away.
+/// The todo belongs to the owner of the task, no matter who created it.
+pub async fn create_todo(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    payload: &CreateTodo,
+) -> Result<i64, ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    let name = super::todo::validate_name(&payload.name)?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    let todo_id = sqlx::query(
+        "INSERT INTO todos (name, done, owner_id, priority) SELECT ?1, 0, owner_id, ?3 FROM tasks WHERE id = (?2);",
+    )
+    .bind(name)
+    .bind(id)
+    .bind(payload.priority)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+    sqlx::query("INSERT INTO tasktodos (task_id, todo_id) VALUES (?1, ?2);")
+        .bind(id)
+        .bind(todo_id)
+        .execute(&mut *tx)
+        .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Create,
+        AuditEntity::Todo,
+        todo_id,
+        None,
+    )
+    .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(todo_id)
+}
+
+/// Tasks `user_id` can see among the ones `linked` selects for the task `id`.
+/// `linked` is a subquery on `task_dependencies` ending in a comparison with the task id.
+async fn get_linked(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    linked: &str,
+    id: i64,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let mut builder = QueryBuilder::<Sqlite>::new(HEADER_COLUMNS);
+    push_select(&mut builder, user_id);
+    builder
+        .push(" AND t.id IN (")
+        .push(linked)
+        .push_bind(id)
+        .push(") ORDER BY t.done, t.id");
+
+    let mut headers = builder
+        .build_query_as::<TaskHeader>()
+        .fetch_all(pool)
+        .await?;
+    tag::attach_to_tasks(pool, &mut headers).await?;
+    Ok(headers)
+}
+
+/// The tasks blocking a task.
+pub async fn get_blockers(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let linked = "SELECT blocker_id FROM task_dependencies WHERE task_id = ";
+    get_linked(pool, user_id, linked, id).await
+}
+
+/// The tasks a task is blocking.
+pub async fn get_dependents(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let linked = "SELECT task_id FROM task_dependencies WHERE blocker_id = ";
+    get_linked(pool, user_id, linked, id).await
+}
+
+/// A task and every task waiting for it, directly or through other tasks.
+async fn get_waiting(pool: &sqlx::SqlitePool, id: i64) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+            WITH RECURSIVE waiting(id) AS (
+                SELECT (?1)
+                UNION SELECT d.task_id FROM task_dependencies d JOIN waiting w ON d.blocker_id = w.id
+            )
+            SELECT id FROM waiting
+        ;
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Fails while a task is blocked by tasks that are not done yet.
+pub(crate) async fn require_unblocked(pool: &sqlx::SqlitePool, id: i64) -> Result<(), ApiError> {
+    let open: i64 = sqlx::query_scalar(
+        r#"
+            SELECT COUNT(*) FROM task_dependencies d JOIN tasks b ON b.id = d.blocker_id
+            WHERE d.task_id = (?1) AND NOT b.done AND b.deleted_at IS NULL
+        ;
+        "#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    match open {
+        0 => Ok(()),
+        1 => Err(ApiError::Validation(format!(
+            "Task {id} is blocked by an open task, finish it first"
+        ))),
+        open => Err(ApiError::Validation(format!(
+            "Task {id} is blocked by {open} open tasks, finish them first"
+        ))),
+    }
+}
+
+/// Open tasks `user_id` can see that can block `id` without closing a cycle.
+pub async fn get_blocker_candidates(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Vec<TaskHeader>, sqlx::Error> {
+    let waiting = get_waiting(pool, id).await?;
+    let blockers = get_blockers(pool, user_id, id).await?;
+    let query = TaskQuery {
+        sort: Some(SortBy::Name),
+        ..Default::default()
+    };
+    Ok(get_headers_matching(pool, user_id, &query)
+        .await?
+        .into_iter()
+        .filter(|task| !task.done && !waiting.contains(&task.id))
+        .filter(|task| !blockers.iter().any(|blocker| blocker.id == task.id))
+        .collect())
+}
+
+/// Records that `id` can't be done before `blocker_id` is.
+/// Needs write access to the task and a blocker the user can see.
+pub async fn add_blocker(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    blocker_id: i64,
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    access(pool, user_id, blocker_id).await?;
+    if blocker_id == id {
+        return Err(ApiError::Validation(format!(
+            "Task {id} can't block itself"
+        )));
+    }
+    if get_waiting(pool, id).await?.contains(&blocker_id) {
+        return Err(ApiError::Validation(format!(
+            "Task {blocker_id} is already waiting for task {id}, blocking it would be a cycle"
+        )));
+    }
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT OR IGNORE INTO task_dependencies (task_id, blocker_id) VALUES (?1, ?2);")
+        .bind(id)
+        .bind(blocker_id)
+        .execute(&mut *tx)
+        .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn remove_blocker(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    blocker_id: i64,
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM task_dependencies WHERE task_id = (?1) AND blocker_id = (?2);")
+        .bind(id)
+        .bind(blocker_id)
+        .execute(&mut *tx)
+        .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Everybody but the owner with access to a task, only visible to the owner.
+pub async fn get_shares(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Vec<TaskShare>, ApiError> {
+    require(pool, user_id, id, Access::Owner).await?;
+    sqlx::query_as::<_, TaskShare>(
+        r#"
+            SELECT s.user_id, u.name AS user_name, s.access FROM task_shares s
+            JOIN users u ON u.id = s.user_id
+            WHERE s.task_id = (?1)
+            ORDER BY u.name
+        ;
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.into())
+}
+
+/// Shares a task and its linked todos with the user named in `payload`,
+/// changing the access if it is already shared with them.
+pub async fn share(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    payload: &ShareTask,
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Owner).await?;
+    if payload.access == Access::Owner {
+        return Err(ApiError::Validation(
+            "Tasks can only be shared for read or write".to_string(),
+        ));
+    }
+
+    let share_with: i64 =
+        sqlx::query_scalar("SELECT id FROM users WHERE name = (?1) COLLATE NOCASE;")
+            .bind(payload.user.trim())
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| ApiError::Validation(format!("No user named {}", payload.user)))?;
+    if share_with == user_id {
+        return Err(ApiError::Validation(
+            "You already own this task".to_string(),
+        ));
+    }
+
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+            INSERT INTO task_shares (task_id, user_id, access)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (task_id, user_id) DO UPDATE SET access = excluded.access;
+        "#,
+    )
+    .bind(id)
+    .bind(share_with)
+    .bind(payload.access)
+    .execute(&mut *tx)
+    .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn unshare(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    share_user_id: i64,
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Owner).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Task, id).await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM task_shares WHERE task_id = (?1) AND user_id = (?2);")
+        .bind(id)
+        .bind(share_user_id)
+        .execute(&mut *tx)
+        .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Task,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Records that a task entered a board column, unless it is the column it entered last.
+pub(crate) async fn record_transition(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    task_id: i64,
+    column_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+            INSERT INTO task_transitions (task_id, column_id, entered)
+            SELECT ?1, ?2, ?3
+            WHERE (?2) IS NOT (SELECT column_id FROM task_transitions WHERE task_id = (?1) ORDER BY id DESC LIMIT 1)
+        ;
+        "#,
+    )
+    .bind(task_id)
+    .bind(column_id)
+    .bind(data_access::utils::now())
+    .execute(executor)
+    .await
+    .map(|_| ())
+}
+
+fn hours(from: DateTime, to: DateTime) -> f64 {
+    (to - from).num_seconds() as f64 / 3600.0
+}
+
+fn average(values: impl ExactSizeIterator<Item = f64>) -> Option<f64> {
+    let count = values.len();
+    (count > 0).then(|| values.sum::<f64>() / count as f64)
+}
+
+/// Lead time, cycle time and cumulative flow of the board of a project `user_id` owns.
+/// A task starts when it first leaves the first column and finishes when it enters
+/// the done column it is in now, moving on between done columns keeps the first finish.
+pub async fn get_flow(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    query: &FlowQuery,
+) -> Result<FlowMetrics, ApiError> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or(to - Days::new(30));
+    if from > to {
+        return Err(ApiError::Validation(format!(
+            "The range must not end before it starts, got {from} to {to}"
+        )));
+    }
+    if (to - from).num_days() >= MAX_FLOW_DAYS {
+        return Err(ApiError::Validation(format!(
+            "The range must not span more than {MAX_FLOW_DAYS} days"
+        )));
+    }
+
+    let project = super::project::resolve(pool, user_id, query.project).await?;
+    let columns = super::board::get_columns(pool, user_id, project.id).await?;
+    let tasks: Vec<(i64, String, DateTime)> = sqlx::query_as(
+        "SELECT id, name, created FROM tasks WHERE project_id = (?1) AND owner_id = (?2) AND deleted_at IS NULL ORDER BY id;",
+    )
+    .bind(project.id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    // Moves within other projects, before a task was moved here, don't count
+    let rows: Vec<(i64, i64, DateTime)> = sqlx::query_as(
+        r#"
+            SELECT tr.task_id, tr.column_id, tr.entered FROM task_transitions tr
+            JOIN tasks t ON t.id = tr.task_id
+            JOIN board_columns c ON c.id = tr.column_id
+            WHERE t.project_id = (?1) AND t.owner_id = (?2) AND c.project_id = (?1) AND t.deleted_at IS NULL
+            ORDER BY tr.id
+        ;
+        "#,
+    )
+    .bind(project.id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    let mut transitions: HashMap<i64, Vec<(i64, DateTime)>> = HashMap::new();
+    for (task_id, column_id, entered) in rows {
+        transitions
+            .entry(task_id)
+            .or_default()
+            .push((column_id, entered));
+    }
+
+    Ok(flow_metrics(
+        project.id,
+        columns,
+        tasks,
+        &transitions,
+        from,
+        to,
+    ))
+}
+
+/// The flow of a board from its tasks and their column moves, oldest move first.
+fn flow_metrics(
+    project_id: i64,
+    columns: Vec<Column>,
+    tasks: Vec<(i64, String, DateTime)>,
+    transitions: &HashMap<i64, Vec<(i64, DateTime)>>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> FlowMetrics {
+    let index_of = |column_id: i64| columns.iter().position(|column| column.id == column_id);
+    let is_done = |column_id: i64| index_of(column_id).is_some_and(|index| columns[index].done);
+    let first_column = columns.first().map(|column| column.id);
+
+    let days = from
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| {
+            let end = (date + Days::new(1)).and_time(NaiveTime::MIN).and_utc();
+            let mut counts = vec![0; columns.len()];
+            for moves in transitions.values() {
+                let current = moves.iter().rev().find(|(_, entered)| *entered < end);
+                if let Some(index) = current.and_then(|(column_id, _)| index_of(*column_id)) {
+                    counts[index] += 1;
+                }
+            }
+            FlowDay { date, counts }
+        })
+        .collect();
+
+    let mut finished = Vec::new();
+    for (id, name, created) in tasks {
+        let Some(moves) = transitions.get(&id) else {
+            continue;
+        };
+        let open = moves
+            .iter()
+            .rposition(|(column_id, _)| !is_done(*column_id))
+            .map_or(0, |index| index + 1);
+        let Some((_, finished_at)) = moves.get(open) else {
+            continue;
+        };
+        if finished_at.date_naive() < from || finished_at.date_naive() > to {
+            continue;
+        }
+        let started = moves
+            .iter()
+            .find(|(column_id, _)| Some(*column_id) != first_column)
+            .map_or(*finished_at, |(_, entered)| *entered)
+            .min(*finished_at);
+
+        finished.push(TaskFlow {
+            id,
+            name,
+            created,
+            started,
+            finished: *finished_at,
+            lead_time: hours(created, *finished_at),
+            cycle_time: hours(started, *finished_at),
+        });
+    }
+    finished.sort_by_key(|task| task.finished);
+
+    FlowMetrics {
+        project_id,
+        from,
+        to,
+        columns,
+        days,
+        lead_time: average(finished.iter().map(|task| task.lead_time)),
+        cycle_time: average(finished.iter().map(|task| task.cycle_time)),
+        finished,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    /// A fresh in-memory database with one user, a single connection keeps it alive.
+    async fn setup() -> (sqlx::SqlitePool, i64) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory database opens");
+        crate::migrations::run(&pool)
+            .await
+            .expect("migrations apply to an empty database");
+        let user_id = sqlx::query("INSERT INTO users (name) VALUES ('ann');")
+            .execute(&pool)
+            .await
+            .expect("user is stored")
+            .last_insert_rowid();
+        (pool, user_id)
+    }
+
+    async fn task(pool: &sqlx::SqlitePool, user_id: i64, name: &str) -> i64 {
+        let payload = CreateTask {
+            name: name.to_string(),
+            project_id: None,
+            priority: Default::default(),
+            due: None,
+            recurrence: None,
+            parent_id: None,
+        };
+        match create_task(pool, user_id, &payload).await {
+            Ok(id) => id,
+            Err(_) => panic!("task {name} is created"),
+        }
+    }
+
+    async fn block(pool: &sqlx::SqlitePool, user_id: i64, id: i64, blocker_id: i64) -> bool {
+        match add_blocker(pool, user_id, id, blocker_id).await {
+            Ok(()) => true,
+            Err(ApiError::Validation(_)) => false,
+            Err(_) => panic!("blocking {id} by {blocker_id} fails with a validation error"),
+        }
+    }
+
+    async fn get_blocker_ids(pool: &sqlx::SqlitePool, id: i64) -> Vec<i64> {
+        sqlx::query_scalar(
+            "SELECT blocker_id FROM task_dependencies WHERE task_id = (?1) ORDER BY blocker_id;",
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await
+        .expect("blockers load")
+    }
+
+    fn column(id: i64, name: &str, done: bool) -> Column {
+        Column {
+            id,
+            project_id: 1,
+            name: name.to_string(),
+            position: id,
+            done,
+            wip_limit: None,
+            wip_block: false,
+        }
+    }
+
+    fn board() -> Vec<Column> {
+        vec![
+            column(1, "Backlog", false),
+            column(2, "Doing", false),
+            column(3, "Done", true),
+            column(4, "Archived", true),
+        ]
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).expect("valid date")
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime {
+        date(day)
+            .and_hms_opt(hour, 0, 0)
+            .expect("valid time")
+            .and_utc()
+    }
+
+    /// The flow of a single task created on the first day, between the 1st and the 10th.
+    fn flow_of(moves: Vec<(i64, DateTime)>) -> FlowMetrics {
+        let tasks = vec![(7, "a".to_string(), at(1, 0))];
+        let transitions = HashMap::from([(7, moves)]);
+        flow_metrics(1, board(), tasks, &transitions, date(1), date(10))
+    }
+
+    #[test]
+    fn flow_measures_from_leaving_the_first_column_to_done() {
+        let flow = flow_of(vec![(1, at(1, 0)), (2, at(2, 0)), (3, at(3, 12))]);
+
+        let [task] = flow.finished.as_slice() else {
+            panic!("expected one finished task");
+        };
+        assert_eq!(task.started, at(2, 0));
+        assert_eq!(task.finished, at(3, 12));
+        assert_eq!(task.lead_time, 60.0);
+        assert_eq!(task.cycle_time, 36.0);
+        assert_eq!(flow.lead_time, Some(60.0));
+        assert_eq!(flow.cycle_time, Some(36.0));
+    }
+
+    #[test]
+    fn flow_keeps_the_first_finish_between_done_columns() {
+        let flow = flow_of(vec![(1, at(1, 0)), (3, at(2, 0)), (4, at(5, 0))]);
+
+        let [task] = flow.finished.as_slice() else {
+            panic!("expected one finished task");
+        };
+        assert_eq!(task.finished, at(2, 0));
+        // Skipping the columns in between starts and finishes at once
+        assert_eq!(task.cycle_time, 0.0);
+    }
+
+    #[test]
+    fn flow_skips_reopened_and_out_of_range_tasks() {
+        let reopened = flow_of(vec![(1, at(1, 0)), (3, at(2, 0)), (2, at(3, 0))]);
+        assert!(reopened.finished.is_empty());
+        assert_eq!(reopened.lead_time, None);
+
+        let tasks = vec![(7, "a".to_string(), at(1, 0))];
+        let transitions = HashMap::from([(7, vec![(2, at(1, 0)), (3, at(2, 0))])]);
+        let later = flow_metrics(1, board(), tasks, &transitions, date(3), date(10));
+        assert!(later.finished.is_empty());
+    }
+
+    #[test]
+    fn flow_counts_tasks_per_column_at_the_end_of_each_day() {
+        let flow = flow_of(vec![(1, at(1, 0)), (2, at(2, 0)), (3, at(3, 23))]);
+
+        let counts = flow
+            .days
+            .iter()
+            .take(4)
+            .map(|day| day.counts.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                vec![1, 0, 0, 0],
+                vec![0, 1, 0, 0],
+                vec![0, 0, 1, 0],
+                vec![0, 0, 1, 0],
+            ]
+        );
+        assert_eq!(flow.days.len(), 10);
+        assert_eq!(flow.days[0].date, date(1));
+    }
+
+    #[tokio::test]
+    async fn tasks_cannot_block_themselves() {
+        let (pool, user_id) = setup().await;
+        let a = task(&pool, user_id, "a").await;
+
+        assert!(!block(&pool, user_id, a, a).await);
+    }
+
+    #[tokio::test]
+    async fn direct_cycles_are_rejected() {
+        let (pool, user_id) = setup().await;
+        let a = task(&pool, user_id, "a").await;
+        let b = task(&pool, user_id, "b").await;
+
+        assert!(block(&pool, user_id, b, a).await);
+        assert!(!block(&pool, user_id, a, b).await);
+        assert_eq!(get_blocker_ids(&pool, a).await, Vec::<i64>::new());
+    }
+
+    #[tokio::test]
+    async fn indirect_cycles_are_rejected() {
+        let (pool, user_id) = setup().await;
+        let a = task(&pool, user_id, "a").await;
+        let b = task(&pool, user_id, "b").await;
+        let c = task(&pool, user_id, "c").await;
+        let d = task(&pool, user_id, "d").await;
+
+        // d waits for c, which waits for b, which waits for a
+        assert!(block(&pool, user_id, b, a).await);
+        assert!(block(&pool, user_id, c, b).await);
+        assert!(block(&pool, user_id, d, c).await);
+        let mut waiting = get_waiting(&pool, a).await.expect("waiting tasks load");
+        waiting.sort_unstable();
+        assert_eq!(waiting, vec![a, b, c, d]);
+
+        assert!(!block(&pool, user_id, a, d).await);
+        assert!(!block(&pool, user_id, a, c).await);
+        assert!(!block(&pool, user_id, b, d).await);
+    }
+
+    #[tokio::test]
+    async fn shared_blockers_are_not_cycles() {
+        let (pool, user_id) = setup().await;
+        let a = task(&pool, user_id, "a").await;
+        let b = task(&pool, user_id, "b").await;
+        let c = task(&pool, user_id, "c").await;
+
+        // Both b and c wait for a, c may wait for b as well
+        assert!(block(&pool, user_id, b, a).await);
+        assert!(block(&pool, user_id, c, a).await);
+        assert!(block(&pool, user_id, c, b).await);
+        assert_eq!(get_blocker_ids(&pool, c).await, vec![a, b]);
+    }
+
+    #[tokio::test]
+    async fn recurring_tasks_share_their_todos_with_the_next_occurrence() {
+        let (pool, user_id) = setup().await;
+        let id = task(&pool, user_id, "water plants").await;
+        set_recurrence(&pool, id, Some(&Recurrence::Daily))
+            .await
+            .expect("recurrence is stored");
+        let todo_id =
+            sqlx::query("INSERT INTO todos (name, done, owner_id) VALUES ('fern', 1, ?1);")
+                .bind(user_id)
+                .execute(&pool)
+                .await
+                .expect("todo is stored")
+                .last_insert_rowid();
+        sqlx::query("INSERT INTO tasktodos (task_id, todo_id) VALUES (?1, ?2);")
+            .bind(id)
+            .bind(todo_id)
+            .execute(&pool)
+            .await
+            .expect("todo is linked");
+
+        let mut conn = pool.acquire().await.expect("connection is free");
+        let next_id = match recur(&mut conn, user_id, id).await {
+            Ok(Some(next_id)) => next_id,
+            _ => panic!("a daily task has a next occurrence"),
+        };
+        drop(conn);
+        let mut task_ids: Vec<i64> =
+            sqlx::query_scalar("SELECT task_id FROM tasktodos WHERE todo_id = (?1);")
+                .bind(todo_id)
+                .fetch_all(&pool)
+                .await
+                .expect("links load");
+        task_ids.sort_unstable();
+        assert_eq!(task_ids, vec![id, next_id]);
+        let done: bool = sqlx::query_scalar("SELECT done FROM todos WHERE id = (?1);")
+            .bind(todo_id)
+            .fetch_one(&pool)
+            .await
+            .expect("todo loads");
+        assert!(!done);
+    }
 }
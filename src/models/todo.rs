@@ -1,40 +1,163 @@
-use tracing::info;
+use sqlx::{QueryBuilder, Sqlite};
 
 use crate::{
-    api::types::todo::{CreateTodo, TodoItem, TodoQuery, UpdateTodo},
+    api::types::{
+        audit::{AuditAction, AuditEntity, TodoSnapshot},
+        task::{Access, SortBy},
+        todo::{CreateTodo, TodoItem, TodoQuery, UpdateTodo},
+        trash::TrashKind,
+    },
     error::ApiError,
+    models::{
+        audit,
+        tag::{self, TagOwner},
+    },
 };
 
-pub async fn get_all(pool: &sqlx::SqlitePool) -> Result<Vec<TodoItem>, sqlx::Error> {
-    sqlx::query_as::<_, TodoItem>("SELECT id, name, done FROM todos")
-        .fetch_all(pool)
-        .await
+/// Narrows a query on `todos` to the ones `user_id` owns or can see through a task,
+/// trashed todos and tasks are left out.
+/// Expects the builder to already contain a `WHERE` clause.
+pub(crate) fn push_visible(query: &mut QueryBuilder<Sqlite>, user_id: i64) {
+    query
+        .push(" AND deleted_at IS NULL AND (owner_id = ")
+        .push_bind(user_id)
+        .push(" OR id IN (SELECT todo_id FROM tasktodos WHERE task_id IN (")
+        .push("SELECT id FROM tasks WHERE deleted_at IS NULL AND (owner_id = ")
+        .push_bind(user_id)
+        .push(" OR id IN (SELECT task_id FROM task_shares WHERE user_id = ")
+        .push_bind(user_id)
+        .push(")))))");
+}
+
+/// The access `user_id` has to a todo, `RowNotFound` for trashed todos.
+/// Owners have full access, otherwise the best access to any task the todo is linked to counts.
+pub(crate) async fn access(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<Access, sqlx::Error> {
+    let rank: Option<i64> = sqlx::query_scalar(
+        r#"
+            SELECT MAX(rank) FROM (
+                SELECT 3 AS rank FROM todos
+                WHERE id = (?1) AND owner_id = (?2)
+                UNION ALL
+                SELECT 2 FROM tasktodos tt JOIN tasks t ON t.id = tt.task_id
+                WHERE tt.todo_id = (?1) AND t.owner_id = (?2) AND t.deleted_at IS NULL
+                UNION ALL
+                SELECT CASE s.access WHEN 'write' THEN 2 ELSE 1 END
+                FROM tasktodos tt JOIN task_shares s ON s.task_id = tt.task_id
+                JOIN tasks t ON t.id = tt.task_id
+                WHERE tt.todo_id = (?1) AND s.user_id = (?2) AND t.deleted_at IS NULL
+            )
+            WHERE EXISTS (SELECT 1 FROM todos WHERE id = (?1) AND deleted_at IS NULL)
+        ;
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    match rank {
+        Some(3) => Ok(Access::Owner),
+        Some(2) => Ok(Access::Write),
+        Some(_) => Ok(Access::Read),
+        None => Err(sqlx::Error::RowNotFound),
+    }
+}
+
+/// Fails with `404` for todos `user_id` can't see and `403` if the access is not sufficient.
+pub(crate) async fn require(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+    needed: Access,
+) -> Result<Access, ApiError> {
+    let access = access(pool, user_id, id).await?;
+    match access >= needed {
+        true => Ok(access),
+        false => Err(ApiError::Forbidden(format!(
+            "Todo {id} needs {} access",
+            needed.as_str()
+        ))),
+    }
+}
+
+pub async fn get_all(pool: &sqlx::SqlitePool, user_id: i64) -> Result<Vec<TodoItem>, sqlx::Error> {
+    get_all_matching(pool, user_id, &TodoQuery::default()).await
 }
 
 pub async fn get_all_matching(
     pool: &sqlx::SqlitePool,
+    user_id: i64,
     query: &TodoQuery,
 ) -> Result<Vec<TodoItem>, sqlx::Error> {
-    let mut raw_query = r#"
-            SELECT id, name, done FROM todos
-        "#
-    .to_string();
-
-    let where_buffer = match query.done {
-        Some(true) => "\n WHERE done = 1;",
-        Some(false) => "\n WHERE done = 0;",
-        _ => "",
-    };
+    // `due` is the earliest due date of the tasks a todo is linked to, only used for sorting
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        r#"
+            SELECT id, name, done, priority,
+                (SELECT MIN(t.due) FROM tasktodos tt JOIN tasks t ON t.id = tt.task_id
+                    WHERE tt.todo_id = todos.id AND t.deleted_at IS NULL) AS due
+            FROM todos
+            WHERE 1 = 1
+        "#,
+    );
 
-    raw_query.push_str(&where_buffer);
+    push_visible(&mut builder, user_id);
+    if let Some(done) = query.done {
+        builder.push(" AND done = ").push_bind(done);
+    }
+    tag::push_filter(&mut builder, TagOwner::Todo, "id", &query.tags);
+    builder.push(match query.sort {
+        Some(SortBy::Priority) => " ORDER BY priority DESC, id",
+        Some(SortBy::Due) => " ORDER BY due IS NULL, due, priority DESC, id",
+        // Todos have no creation time, their ids are in creation order
+        Some(SortBy::Created) => " ORDER BY id DESC",
+        Some(SortBy::Name) => " ORDER BY name COLLATE NOCASE, id",
+        None => " ORDER BY id",
+    });
 
-    sqlx::query_as::<_, TodoItem>(&raw_query)
-        .fetch_all(pool)
-        .await
+    let mut todos = builder.build_query_as::<TodoItem>().fetch_all(pool).await?;
+    tag::attach_to_todos(pool, &mut todos).await?;
+    Ok(todos)
 }
 
-pub async fn get_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<TodoItem, ApiError> {
-    sqlx::query_as::<_, TodoItem>(
+/// Own todos whose name contains `text` and that are not linked to the task `task_id` yet.
+pub async fn search_unlinked(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    text: &str,
+) -> Result<Vec<TodoItem>, sqlx::Error> {
+    let mut todos = sqlx::query_as::<_, TodoItem>(
+        r#"
+            SELECT id, name, done, priority FROM todos
+            WHERE instr(lower(name), lower(?2)) > 0
+            AND owner_id = (?3)
+            AND deleted_at IS NULL
+            AND id NOT IN (SELECT todo_id FROM tasktodos WHERE task_id = (?1))
+            ORDER BY done, name
+            LIMIT 20
+        ;
+        "#,
+    )
+    .bind(task_id)
+    .bind(text.trim())
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    tag::attach_to_todos(pool, &mut todos).await?;
+    Ok(todos)
+}
+
+pub async fn get_by_id(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<TodoItem, ApiError> {
+    require(pool, user_id, id, Access::Read).await?;
+    let mut todo = sqlx::query_as::<_, TodoItem>(
         r#"
             SELECT * FROM todos
             WHERE id = (?1)
@@ -43,56 +166,137 @@ pub async fn get_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<TodoItem, Api
     )
     .bind(id)
     .fetch_one(pool)
-    .await
-    .map_err(|e| e.into())
+    .await?;
+    todo.tags = tag::get_for(pool, TagOwner::Todo, id).await?;
+    Ok(todo)
 }
 
-pub async fn create(pool: &sqlx::SqlitePool, payload: &CreateTodo) -> Result<i64, sqlx::Error> {
-    sqlx::query("INSERT INTO todos (name) Values (?1);")
-        .bind(&payload.name)
-        .execute(pool)
-        .await
-        .map(|result| result.last_insert_rowid())
+pub(crate) fn validate_name(name: &str) -> Result<String, ApiError> {
+    match name.trim() {
+        "" => Err(ApiError::Validation(
+            "Todo name must not be empty".to_string(),
+        )),
+        name => Ok(name.to_string()),
+    }
+}
+
+pub async fn create(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    payload: &CreateTodo,
+) -> Result<i64, ApiError> {
+    let name = validate_name(&payload.name)?;
+    let mut tx = pool.begin().await?;
+    let id =
+        sqlx::query("INSERT INTO todos (name, done, owner_id, priority) Values (?1, 0, ?2, ?3);")
+            .bind(name)
+            .bind(user_id)
+            .bind(payload.priority)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Create,
+        AuditEntity::Todo,
+        id,
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(id)
 }
 
+/// Updates the given fields of a todo, keeping the stored value for every field left out.
 pub async fn update(
     pool: &sqlx::SqlitePool,
+    user_id: i64,
     id: i64,
     payload: &UpdateTodo,
-) -> Result<(), sqlx::Error> {
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Todo, id).await?;
+    let old_todo = get_by_id(pool, user_id, id).await?;
+    let name = match &payload.name {
+        Some(name) => validate_name(name)?,
+        None => old_todo.name,
+    };
+
+    let mut tx = pool.begin().await?;
     sqlx::query(
         r#"
         UPDATE todos
         Set (name) = (?2),
-            (done) = (?3)
+            (done) = (?3),
+            (priority) = (?4)
         WHERE id = (?1);
         "#,
     )
-    .bind(&id)
-    .bind(&payload.name)
-    .bind(&payload.done)
-    .execute(pool)
-    .await
-    .map(|_| ())
+    .bind(id)
+    .bind(name)
+    .bind(payload.done.unwrap_or(old_todo.done))
+    .bind(payload.priority.unwrap_or(old_todo.priority))
+    .execute(&mut *tx)
+    .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Todo,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
 }
 
-pub async fn delete(pool: &sqlx::SqlitePool, id: i64) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"
-            DELETE FROM todos
-            WHERE id = (?1)
-        ;
-        "#,
+/// Puts a todo back into a state recorded in the audit log.
+pub(crate) async fn restore_snapshot(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    id: i64,
+    snapshot: &TodoSnapshot,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE todos SET name = (?2), done = (?3), priority = (?4) WHERE id = (?1);")
+        .bind(id)
+        .bind(&snapshot.name)
+        .bind(snapshot.done)
+        .bind(snapshot.priority)
+        .execute(executor)
+        .await
+        .map(|_| ())
+}
+
+/// Moves a todo to the trash of its owner, its task links come back when it is restored.
+pub async fn delete(pool: &sqlx::SqlitePool, user_id: i64, id: i64) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Todo, id).await?;
+    let mut tx = pool.begin().await?;
+    super::trash::trash_row(&mut tx, TrashKind::Todo, id).await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Delete,
+        AuditEntity::Todo,
+        id,
+        before,
     )
-    .bind(id)
-    .execute(pool)
-    .await
-    .map(|_| ())
+    .await?;
+    tx.commit().await?;
+    Ok(())
 }
 
-pub(crate) async fn toggle_state(pool: &sqlx::SqlitePool, id: i64) -> Result<(), ApiError> {
-    let mut todo = get_by_id(pool, id).await?;
+pub(crate) async fn toggle_state(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<(), ApiError> {
+    require(pool, user_id, id, Access::Write).await?;
+    let before = audit::snapshot(&mut *pool.acquire().await?, AuditEntity::Todo, id).await?;
+    let mut todo = get_by_id(pool, user_id, id).await?;
     todo.done = !todo.done;
+    let mut tx = pool.begin().await?;
     sqlx::query(
         r#"
         UPDATE todos
@@ -100,10 +304,19 @@ pub(crate) async fn toggle_state(pool: &sqlx::SqlitePool, id: i64) -> Result<(),
         WHERE id = (?1);
         "#,
     )
-    .bind(&id)
+    .bind(id)
     .bind(todo.done)
-    .execute(pool)
-    .await
-    .map(|_| ())
-    .map_err(|e| e.into())
+    .execute(&mut *tx)
+    .await?;
+    audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Update,
+        AuditEntity::Todo,
+        id,
+        before,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
 }
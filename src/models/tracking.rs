@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Days, NaiveTime, Utc};
+
+use crate::{
+    api::types::{
+        task::Access,
+        time::DateTime,
+        tracking::{CreateTimeEntry, TimeEntry, Timesheet, TimesheetQuery, TimesheetRow},
+    },
+    data_access::utils::now,
+    error::ApiError,
+    models::tag::{self, TagOwner},
+};
+
+/// Longest time entry that can be entered by hand.
+const MAX_ENTRY_MINUTES: i64 = 24 * 60;
+
+/// Columns of a [`TimeEntry`], joined with the names of its task and user.
+/// Entries of trashed tasks are left out.
+const ENTRY_COLUMNS: &str = r#"
+    SELECT e.id, e.task_id, t.name AS task, e.user_id, u.name AS user, e.started, e.stopped, e.note
+    FROM time_entries e JOIN tasks t ON t.id = e.task_id AND t.deleted_at IS NULL
+    JOIN users u ON u.id = e.user_id
+"#;
+
+/// Fills in the tracked seconds, running timers count up to `now`.
+fn with_seconds(mut entries: Vec<TimeEntry>, now: DateTime) -> Vec<TimeEntry> {
+    for entry in entries.iter_mut() {
+        entry.seconds = (entry.stopped.unwrap_or(now) - entry.started)
+            .num_seconds()
+            .max(0);
+    }
+    entries
+}
+
+/// Every entry on a task, newest first.
+pub async fn get_for_task(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+) -> Result<Vec<TimeEntry>, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Read).await?;
+    let entries = sqlx::query_as::<_, TimeEntry>(&format!(
+        "{ENTRY_COLUMNS} WHERE e.task_id = (?1) ORDER BY e.started DESC, e.id DESC;"
+    ))
+    .bind(task_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(with_seconds(entries, now()))
+}
+
+/// A single entry, as returned after tracking time.
+pub async fn get_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<TimeEntry, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(&format!("{ENTRY_COLUMNS} WHERE e.id = (?1);"))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    Ok(with_seconds(vec![entry], now())
+        .pop()
+        .expect("a single entry goes in"))
+}
+
+/// The timer `user_id` is running, on any task.
+pub async fn get_running(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+) -> Result<Option<TimeEntry>, sqlx::Error> {
+    let entry = sqlx::query_as::<_, TimeEntry>(&format!(
+        "{ENTRY_COLUMNS} WHERE e.user_id = (?1) AND e.stopped IS NULL;"
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(entry.and_then(|entry| with_seconds(vec![entry], now()).pop()))
+}
+
+/// Starts a timer on a task, stopping the one running on another task.
+/// Keeps a timer that already runs on this task.
+pub async fn start(pool: &sqlx::SqlitePool, user_id: i64, task_id: i64) -> Result<i64, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Write).await?;
+    if let Some(running) = get_running(pool, user_id).await? {
+        if running.task_id == task_id {
+            return Ok(running.id);
+        }
+    }
+
+    let started = now();
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE time_entries SET stopped = (?2) WHERE user_id = (?1) AND stopped IS NULL;")
+        .bind(user_id)
+        .bind(started)
+        .execute(&mut *tx)
+        .await?;
+    let id = sqlx::query(
+        "INSERT INTO time_entries (task_id, user_id, started, note) VALUES (?1, ?2, ?3, '');",
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .bind(started)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// Stops the timer `user_id` runs on a task.
+pub async fn stop(pool: &sqlx::SqlitePool, user_id: i64, task_id: i64) -> Result<(), ApiError> {
+    let stopped = sqlx::query(
+        r#"
+            UPDATE time_entries SET stopped = (?3)
+            WHERE user_id = (?1) AND task_id = (?2) AND stopped IS NULL
+        ;
+        "#,
+    )
+    .bind(user_id)
+    .bind(task_id)
+    .bind(now())
+    .execute(pool)
+    .await?
+    .rows_affected();
+    match stopped {
+        0 => Err(ApiError::Validation(format!(
+            "No timer is running on task {task_id}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Tracks time on a task by hand.
+pub async fn create(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    payload: &CreateTimeEntry,
+) -> Result<i64, ApiError> {
+    super::task::require(pool, user_id, task_id, Access::Write).await?;
+    if !(1..=MAX_ENTRY_MINUTES).contains(&payload.minutes) {
+        return Err(ApiError::Validation(format!(
+            "Tracked time must be between 1 minute and {} hours, got {} minutes",
+            MAX_ENTRY_MINUTES / 60,
+            payload.minutes
+        )));
+    }
+    let stopped = payload
+        .started
+        .checked_add_signed(chrono::Duration::minutes(payload.minutes))
+        .ok_or_else(|| {
+            ApiError::Validation(format!(
+                "Tracked time starting at {} ends too late",
+                payload.started
+            ))
+        })?;
+
+    sqlx::query(
+        r#"
+            INSERT INTO time_entries (task_id, user_id, started, stopped, note)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+        ;
+        "#,
+    )
+    .bind(task_id)
+    .bind(user_id)
+    .bind(payload.started)
+    .bind(stopped)
+    .bind(payload.note.trim())
+    .execute(pool)
+    .await
+    .map(|result| result.last_insert_rowid())
+    .map_err(|e| e.into())
+}
+
+/// Deletes an entry of a task, users can only delete their own entries.
+pub async fn delete(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    task_id: i64,
+    id: i64,
+) -> Result<(), ApiError> {
+    let deleted = sqlx::query(
+        "DELETE FROM time_entries WHERE id = (?1) AND task_id = (?2) AND user_id = (?3);",
+    )
+    .bind(id)
+    .bind(task_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    match deleted {
+        0 => Err(sqlx::Error::RowNotFound.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Adds an entry to the row of `id`, creating the row on first use.
+fn add_to(
+    rows: &mut HashMap<Option<i64>, TimesheetRow>,
+    id: Option<i64>,
+    name: &str,
+    day: usize,
+    seconds: i64,
+) {
+    let row = rows.entry(id).or_insert_with(|| TimesheetRow {
+        id,
+        name: name.to_string(),
+        days: vec![0; 7],
+        total: 0,
+    });
+    row.days[day] += seconds;
+    row.total += seconds;
+}
+
+fn sorted(rows: HashMap<Option<i64>, TimesheetRow>) -> Vec<TimesheetRow> {
+    let mut rows: Vec<TimesheetRow> = rows.into_values().collect();
+    // The untagged row goes last
+    rows.sort_by(|a, b| {
+        (a.id.is_none(), a.name.to_lowercase()).cmp(&(b.id.is_none(), b.name.to_lowercase()))
+    });
+    rows
+}
+
+/// The time `user_id` tracked in a week, per task and per tag.
+pub async fn get_timesheet(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    query: &TimesheetQuery,
+) -> Result<Timesheet, sqlx::Error> {
+    let week = query
+        .week
+        .unwrap_or_else(|| Utc::now().date_naive())
+        .week(chrono::Weekday::Mon)
+        .first_day();
+    let days: Vec<_> = week.iter_days().take(7).collect();
+    let from = week.and_time(NaiveTime::MIN).and_utc();
+    let to = (week + Days::new(7)).and_time(NaiveTime::MIN).and_utc();
+
+    let entries = sqlx::query_as::<_, TimeEntry>(&format!(
+        "{ENTRY_COLUMNS} WHERE e.user_id = (?1) AND e.started >= (?2) AND e.started < (?3) ORDER BY e.started;"
+    ))
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    let entries = with_seconds(entries, now());
+
+    let mut task_ids: Vec<i64> = entries.iter().map(|entry| entry.task_id).collect();
+    task_ids.sort_unstable();
+    task_ids.dedup();
+    let task_tags = tag::get_by_owners(pool, TagOwner::Task, &task_ids).await?;
+
+    let mut tasks = HashMap::new();
+    let mut tags = HashMap::new();
+    let mut totals = vec![0; 7];
+    for entry in &entries {
+        let day = entry.started.weekday().num_days_from_monday() as usize;
+        add_to(
+            &mut tasks,
+            Some(entry.task_id),
+            &entry.task,
+            day,
+            entry.seconds,
+        );
+        match task_tags.get(&entry.task_id) {
+            Some(entry_tags) if !entry_tags.is_empty() => {
+                for tag in entry_tags {
+                    add_to(&mut tags, Some(tag.id), &tag.name, day, entry.seconds);
+                }
+            }
+            _ => add_to(&mut tags, None, "No tag", day, entry.seconds),
+        }
+        totals[day] += entry.seconds;
+    }
+
+    Ok(Timesheet {
+        week,
+        days,
+        tasks: sorted(tasks),
+        tags: sorted(tags),
+        total: totals.iter().sum(),
+        totals,
+        running: get_running(pool, user_id).await?,
+    })
+}
+
+/// Seconds tracked on a task by everybody, running timers up to now.
+pub async fn tracked(pool: &sqlx::SqlitePool, task_id: i64) -> Result<i64, sqlx::Error> {
+    let spans: Vec<(DateTime, Option<DateTime>)> =
+        sqlx::query_as("SELECT started, stopped FROM time_entries WHERE task_id = (?1);")
+            .bind(task_id)
+            .fetch_all(pool)
+            .await?;
+    let now = now();
+    Ok(spans
+        .into_iter()
+        .map(|(started, stopped)| (stopped.unwrap_or(now) - started).num_seconds().max(0))
+        .sum())
+}
+
+/// Quotes `value` for a CSV cell. Values a spreadsheet would read as a formula get a leading `'`.
+fn csv_field(value: &str) -> String {
+    let guard = match value.starts_with(['=', '+', '-', '@']) {
+        true => "'",
+        false => "",
+    };
+    format!("\"{guard}{}\"", value.replace('"', "\"\""))
+}
+
+fn csv_hours(seconds: i64) -> String {
+    format!("{:.2}", seconds as f64 / 3600.0)
+}
+
+/// A timesheet as CSV with hours per day, tasks first and tags after them.
+pub fn to_csv(timesheet: &Timesheet) -> String {
+    let mut lines = vec![std::iter::once("kind".to_string())
+        .chain(std::iter::once("name".to_string()))
+        .chain(
+            timesheet
+                .days
+                .iter()
+                .map(|day| day.format("%Y-%m-%d").to_string()),
+        )
+        .chain(std::iter::once("total".to_string()))
+        .collect::<Vec<_>>()
+        .join(",")];
+    let rows = timesheet
+        .tasks
+        .iter()
+        .map(|row| ("task", row))
+        .chain(timesheet.tags.iter().map(|row| ("tag", row)));
+    for (kind, row) in rows {
+        lines.push(
+            [kind.to_string(), csv_field(&row.name)]
+                .into_iter()
+                .chain(row.days.iter().map(|seconds| csv_hours(*seconds)))
+                .chain(std::iter::once(csv_hours(row.total)))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+    lines.push(
+        ["total".to_string(), String::new()]
+            .into_iter()
+            .chain(timesheet.totals.iter().map(|seconds| csv_hours(*seconds)))
+            .chain(std::iter::once(csv_hours(timesheet.total)))
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+    lines.join("\r\n") + "\r\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::api::types::tracking::TimesheetRow;
+
+    fn row(name: &str, days: Vec<i64>) -> TimesheetRow {
+        TimesheetRow {
+            id: Some(1),
+            name: name.to_string(),
+            total: days.iter().sum(),
+            days,
+        }
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_and_escaped() {
+        assert_eq!(csv_field("plain"), "\"plain\"");
+        assert_eq!(csv_field(""), "\"\"");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\r\nlines"), "\"two\r\nlines\"");
+        assert_eq!(csv_field("=SUM(A1:A9)"), "\"'=SUM(A1:A9)\"");
+        assert_eq!(csv_field("+1"), "\"'+1\"");
+        assert_eq!(csv_field("-fix"), "\"'-fix\"");
+        assert_eq!(csv_field("@cmd"), "\"'@cmd\"");
+        assert_eq!(csv_field("a=b"), "\"a=b\"");
+    }
+
+    #[test]
+    fn csv_hours_have_two_decimals() {
+        assert_eq!(csv_hours(0), "0.00");
+        assert_eq!(csv_hours(5400), "1.50");
+        assert_eq!(csv_hours(60), "0.02");
+    }
+
+    #[test]
+    fn to_csv_lists_tasks_then_tags_then_totals() {
+        let week = NaiveDate::from_ymd_opt(2024, 1, 1).expect("valid date");
+        let timesheet = Timesheet {
+            week,
+            days: week.iter_days().take(2).collect(),
+            tasks: vec![row("Write \"docs\", again", vec![3600, 1800])],
+            tags: vec![row("work", vec![3600, 1800])],
+            totals: vec![3600, 1800],
+            total: 5400,
+            running: None,
+        };
+
+        assert_eq!(
+            to_csv(&timesheet),
+            "kind,name,2024-01-01,2024-01-02,total\r\n\
+             task,\"Write \"\"docs\"\", again\",1.00,0.50,1.50\r\n\
+             tag,\"work\",1.00,0.50,1.50\r\n\
+             total,,1.00,0.50,1.50\r\n"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use sqlx::{Connection, SqliteConnection};
+
+use crate::{
+    api::types::{
+        audit::AuditAction,
+        time::DateTime,
+        trash::{TrashItem, TrashKind},
+    },
+    data_access::{self, utils::now},
+    error::ApiError,
+};
+
+/// Trashed tasks and todos, narrowed by a condition on `owner_id` and `deleted_at` bound to `?1`.
+fn select_trashed(condition: &str) -> String {
+    format!(
+        r#"
+            SELECT 'task' AS kind, id, name, deleted_at FROM tasks
+            WHERE deleted_at IS NOT NULL AND {condition}
+            UNION ALL
+            SELECT 'todo' AS kind, id, name, deleted_at FROM todos
+            WHERE deleted_at IS NOT NULL AND {condition}
+            ORDER BY deleted_at DESC, id DESC
+        ;
+        "#
+    )
+}
+
+fn table_of(kind: TrashKind) -> &'static str {
+    match kind {
+        TrashKind::Task => "tasks",
+        TrashKind::Todo => "todos",
+    }
+}
+
+/// Everything `user_id` owns in the trash, most recently deleted first.
+/// Todos deleted by others through a shared task end up in the trash of their owner.
+pub async fn get_all(pool: &sqlx::SqlitePool, user_id: i64) -> Result<Vec<TrashItem>, sqlx::Error> {
+    sqlx::query_as::<_, TrashItem>(&select_trashed("owner_id = (?1)"))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Fails with `404` unless `user_id` owns the item and it is in the trash.
+async fn require_trashed(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    kind: TrashKind,
+    id: i64,
+) -> Result<(), ApiError> {
+    sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT id FROM {} WHERE id = (?1) AND owner_id = (?2) AND deleted_at IS NOT NULL;",
+        table_of(kind)
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether an item is in the trash, `None` once it is purged.
+pub(crate) async fn is_trashed(
+    pool: &sqlx::SqlitePool,
+    kind: TrashKind,
+    id: i64,
+) -> Result<Option<bool>, sqlx::Error> {
+    sqlx::query_scalar(&format!(
+        "SELECT deleted_at IS NOT NULL FROM {} WHERE id = (?1);",
+        table_of(kind)
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Moves an item to the trash, running timers on a task are stopped.
+pub(crate) async fn trash_row(
+    conn: &mut SqliteConnection,
+    kind: TrashKind,
+    id: i64,
+) -> Result<(), sqlx::Error> {
+    let deleted_at = now();
+    let mut tx = conn.begin().await?;
+    sqlx::query(&format!(
+        "UPDATE {} SET deleted_at = (?2) WHERE id = (?1);",
+        table_of(kind)
+    ))
+    .bind(id)
+    .bind(deleted_at)
+    .execute(&mut *tx)
+    .await?;
+    if kind == TrashKind::Task {
+        sqlx::query(
+            "UPDATE time_entries SET stopped = (?2) WHERE task_id = (?1) AND stopped IS NULL;",
+        )
+        .bind(id)
+        .bind(deleted_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// Takes an item out of the trash, together with its task links.
+/// Restored tasks whose project was deleted meanwhile go to the default project.
+pub async fn restore(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    kind: TrashKind,
+    id: i64,
+) -> Result<(), ApiError> {
+    require_trashed(pool, user_id, kind, id).await?;
+    restore_row(pool, user_id, kind, id).await
+}
+
+/// [`restore`] without asking who owns the item, which `user_id` is recorded as restoring.
+pub(crate) async fn restore_row(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    kind: TrashKind,
+    id: i64,
+) -> Result<(), ApiError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!(
+        "UPDATE {} SET deleted_at = NULL WHERE id = (?1);",
+        table_of(kind)
+    ))
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    if kind == TrashKind::Task {
+        let project_id: Option<i64> =
+            sqlx::query_scalar("SELECT project_id FROM tasks WHERE id = (?1);")
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+        // Also places the task again if its column was deleted while it was in the trash
+        match project_id {
+            Some(project_id) => super::board::ensure_columns(&mut tx, project_id).await?,
+            None => {
+                super::project::default_in(&mut tx, user_id).await?;
+            }
+        }
+    }
+    super::audit::record(
+        &mut tx,
+        user_id,
+        AuditAction::Restore,
+        kind.into(),
+        id,
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Deletes an item for good, along with its task links.
+async fn delete_row(pool: &sqlx::SqlitePool, kind: TrashKind, id: i64) -> Result<(), sqlx::Error> {
+    match kind {
+        TrashKind::Task => data_access::task::delete_row(id, pool).await,
+        TrashKind::Todo => {
+            let mut tx = pool.begin().await?;
+            sqlx::query("DELETE FROM tasktodos WHERE todo_id = (?1);")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM todos WHERE id = (?1);")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await
+        }
+    }
+}
+
+/// Deletes a trashed item of `user_id` for good.
+/// Attachment files of purged tasks are left for [`crate::attachments::AttachmentStore::clean_up`].
+pub async fn purge(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    kind: TrashKind,
+    id: i64,
+) -> Result<(), ApiError> {
+    require_trashed(pool, user_id, kind, id).await?;
+    delete_row(pool, kind, id).await.map_err(|e| e.into())
+}
+
+/// Purges the whole trash of `user_id`.
+pub async fn empty(pool: &sqlx::SqlitePool, user_id: i64) -> Result<(), sqlx::Error> {
+    for item in get_all(pool, user_id).await? {
+        delete_row(pool, item.kind, item.id).await?;
+    }
+    Ok(())
+}
+
+/// Purges everything deleted before `before`, of every user.
+/// Returns how many items were deleted.
+pub(crate) async fn purge_deleted_before(
+    pool: &sqlx::SqlitePool,
+    before: DateTime,
+) -> Result<usize, sqlx::Error> {
+    let items = sqlx::query_as::<_, TrashItem>(&select_trashed("deleted_at < (?1)"))
+        .bind(before)
+        .fetch_all(pool)
+        .await?;
+    for item in &items {
+        delete_row(pool, item.kind, item.id).await?;
+    }
+    Ok(items.len())
+}
@@ -0,0 +1,90 @@
+use crate::{
+    api::types::{
+        audit::{AuditAction, AuditEntity, AuditEntry},
+        task::Access,
+    },
+    error::ApiError,
+    models::audit,
+};
+
+/// Reverts the changes recorded by `entries` as `user_id`, newest first.
+/// Refuses when one of the todos and tasks was changed by anybody since, was purged,
+/// or when `user_id` lost write access to it meanwhile.
+pub async fn revert(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    entries: &[AuditEntry],
+) -> Result<(), ApiError> {
+    for entry in entries {
+        let (entity, id) = (entry.entity, entry.entity_id);
+        let latest = audit::latest_for(pool, entity, id).await?;
+        if !entries.iter().any(|entry| entry.id == latest) {
+            return Err(ApiError::Validation(format!(
+                "Can't undo, {} {id} was changed since",
+                entity.as_str()
+            )));
+        }
+        match super::trash::is_trashed(pool, entity.into(), id).await? {
+            None => {
+                return Err(ApiError::Validation(format!(
+                    "Can't undo, {} {id} was purged",
+                    entity.as_str()
+                )))
+            }
+            Some(true) => {}
+            Some(false) => match entity {
+                AuditEntity::Task => {
+                    super::task::require(pool, user_id, id, Access::Write).await?;
+                }
+                AuditEntity::Todo => {
+                    super::todo::require(pool, user_id, id, Access::Write).await?;
+                }
+            },
+        }
+    }
+
+    for entry in entries.iter().rev() {
+        revert_entry(pool, user_id, entry).await?;
+    }
+    Ok(())
+}
+
+/// Undoes a single change, which is recorded as a change of its own.
+async fn revert_entry(
+    pool: &sqlx::SqlitePool,
+    user_id: i64,
+    entry: &AuditEntry,
+) -> Result<(), ApiError> {
+    let (entity, id) = (entry.entity, entry.entity_id);
+    match entry.action {
+        AuditAction::Create | AuditAction::Restore => {
+            let before = audit::snapshot(&mut *pool.acquire().await?, entity, id).await?;
+            let mut tx = pool.begin().await?;
+            super::trash::trash_row(&mut tx, entity.into(), id).await?;
+            audit::record(&mut tx, user_id, AuditAction::Delete, entity, id, before).await?;
+            tx.commit().await?;
+        }
+        AuditAction::Delete => super::trash::restore_row(pool, user_id, entity.into(), id).await?,
+        AuditAction::Update => {
+            let Some(state) = entry.before.clone() else {
+                return Ok(());
+            };
+            let before = audit::snapshot(&mut *pool.acquire().await?, entity, id).await?;
+            let decode = |e| sqlx::Error::Decode(Box::new(e));
+            let mut tx = pool.begin().await?;
+            match entity {
+                AuditEntity::Task => {
+                    let snapshot = serde_json::from_value(state).map_err(decode)?;
+                    super::task::restore_snapshot(&mut tx, id, &snapshot).await?;
+                }
+                AuditEntity::Todo => {
+                    let snapshot = serde_json::from_value(state).map_err(decode)?;
+                    super::todo::restore_snapshot(&mut *tx, id, &snapshot).await?;
+                }
+            }
+            audit::record(&mut tx, user_id, AuditAction::Update, entity, id, before).await?;
+            tx.commit().await?;
+        }
+    }
+    Ok(())
+}
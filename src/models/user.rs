@@ -0,0 +1,173 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use sqlx::Row;
+
+use crate::{
+    api::types::user::{ChangePassword, RegisterUser, User},
+    data_access::utils::now,
+    error::ApiError,
+};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+fn validate_name(name: &str) -> Result<String, ApiError> {
+    match name.trim() {
+        "" => Err(ApiError::Validation(
+            "User name must not be empty".to_string(),
+        )),
+        name => Ok(name.to_string()),
+    }
+}
+
+fn validate_email(email: &str) -> Result<Option<String>, ApiError> {
+    match email.trim() {
+        "" => Ok(None),
+        email if email.contains('@') => Ok(Some(email.to_string())),
+        email => Err(ApiError::Validation(format!("Invalid email {email}"))),
+    }
+}
+
+fn validate_password(password: &str, confirm: &str) -> Result<(), ApiError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(ApiError::Validation(format!(
+            "Password needs at least {MIN_PASSWORD_LENGTH} characters"
+        )));
+    }
+    if password != confirm {
+        return Err(ApiError::Validation("Passwords do not match".to_string()));
+    }
+    Ok(())
+}
+
+/// Argon2id hash in PHC string format, salt included.
+fn hash_password(password: &str) -> Result<String, ApiError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ApiError::Validation(format!("Password could not be hashed: {e}")))
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+pub async fn get_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<User, sqlx::Error> {
+    sqlx::query_as::<_, User>("SELECT id, name, email, created FROM users WHERE id = (?1);")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn register(pool: &sqlx::SqlitePool, payload: &RegisterUser) -> Result<i64, ApiError> {
+    let name = validate_name(&payload.name)?;
+    let email = validate_email(&payload.email)?;
+    validate_password(&payload.password, &payload.password_confirm)?;
+    let hash = hash_password(&payload.password)?;
+
+    let id = sqlx::query(
+        r#"
+            INSERT INTO users (name, email, password_hash, created)
+            VALUES (?1, ?2, ?3, ?4);
+        "#,
+    )
+    .bind(&name)
+    .bind(email)
+    .bind(hash)
+    .bind(now())
+    .execute(pool)
+    .await
+    .map(|result| result.last_insert_rowid())
+    .map_err(|e| match e {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ApiError::Validation(format!("The name {name} is already taken"))
+        }
+        e => e.into(),
+    })?;
+
+    adopt_unowned(pool, id).await?;
+    super::project::default_for(pool, id).await?;
+    Ok(id)
+}
+
+/// Hands todos, tasks and tags from before accounts existed to the first account that can log in.
+async fn adopt_unowned(pool: &sqlx::SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    let accounts: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE password_hash IS NOT NULL;")
+            .fetch_one(pool)
+            .await?;
+    if accounts != 1 {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    for table in ["todos", "tasks", "tags"] {
+        sqlx::query(&format!(
+            "UPDATE {table} SET owner_id = (?1) WHERE owner_id IS NULL;"
+        ))
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+/// The user with `name`, if `password` matches.
+/// Accounts without a password, created before logins existed, can never log in.
+pub async fn authenticate(
+    pool: &sqlx::SqlitePool,
+    name: &str,
+    password: &str,
+) -> Result<Option<User>, sqlx::Error> {
+    let row = sqlx::query("SELECT id, password_hash FROM users WHERE name = (?1) COLLATE NOCASE;")
+        .bind(name.trim())
+        .map(|row: sqlx::sqlite::SqliteRow| {
+            (
+                row.get::<i64, _>("id"),
+                row.get::<Option<String>, _>("password_hash"),
+            )
+        })
+        .fetch_optional(pool)
+        .await?;
+
+    match row {
+        Some((id, Some(hash))) if verify_password(password, &hash) => {
+            get_by_id(pool, id).await.map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+pub async fn change_password(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+    payload: &ChangePassword,
+) -> Result<(), ApiError> {
+    let user = get_by_id(pool, id).await?;
+    if authenticate(pool, &user.name, &payload.current)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::Validation(
+            "Current password is wrong".to_string(),
+        ));
+    }
+    validate_password(&payload.password, &payload.password_confirm)?;
+    let hash = hash_password(&payload.password)?;
+
+    sqlx::query("UPDATE users SET (password_hash) = (?2) WHERE id = (?1);")
+        .bind(id)
+        .bind(hash)
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.into())
+}
@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, Connection, Row};
+
+use crate::{
+    backup::{self, BackupError, BackupFile, BackupService},
+    database::Database,
+    migrations::{self, MigrationError},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RestoreError {
+    #[error("No backup named {0} found")]
+    NotFound(String),
+    #[error(
+        "Backup {file_name} has schema version {found}, but this build only knows up to {latest}"
+    )]
+    IncompatibleSchema {
+        file_name: String,
+        found: i64,
+        latest: i64,
+    },
+    #[error(transparent)]
+    Backup(#[from] BackupError),
+    #[error("Restored database could not be migrated: {0}")]
+    Migration(#[from] MigrationError),
+    #[error("Restore SQL error: {0}")]
+    SQLError(#[from] sqlx::Error),
+}
+
+/// Number of rows in one table, before and after a restore.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct TableChange {
+    pub(crate) table: String,
+    pub(crate) before: Option<i64>,
+    pub(crate) after: Option<i64>,
+}
+
+impl TableChange {
+    pub(crate) fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct RestoreReport {
+    pub(crate) restored: BackupFile,
+    pub(crate) safety_snapshot: BackupFile,
+    pub(crate) schema_version: i64,
+    pub(crate) tables: Vec<TableChange>,
+}
+
+/// The schema version stored in a snapshot file.
+/// Snapshots taken before versioning was introduced report `0`.
+async fn schema_version_of(path: &std::path::Path) -> Result<i64, sqlx::Error> {
+    let mut connection = SqliteConnectOptions::from_str("sqlite://")?
+        .filename(path)
+        .read_only(true)
+        .disable_statement_logging()
+        .connect()
+        .await?;
+
+    let has_version_table: bool = sqlx::query(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version';",
+    )
+    .map(|row: sqlx::sqlite::SqliteRow| row.get(0))
+    .fetch_one(&mut connection)
+    .await?;
+
+    let version = if has_version_table {
+        sqlx::query("SELECT COALESCE(MAX(version), 0) FROM schema_version;")
+            .map(|row: sqlx::sqlite::SqliteRow| row.get(0))
+            .fetch_one(&mut connection)
+            .await?
+    } else {
+        0
+    };
+    connection.close().await?;
+
+    Ok(version)
+}
+
+async fn row_counts(pool: &sqlx::SqlitePool) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    let tables: Vec<String> = sqlx::query(
+        r#"
+            SELECT name FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+            ORDER BY name
+        ;
+        "#,
+    )
+    .map(|row: sqlx::sqlite::SqliteRow| row.get("name"))
+    .fetch_all(pool)
+    .await?;
+
+    let mut counts = Vec::with_capacity(tables.len());
+    for table in tables {
+        // Table names come straight from sqlite_master, quoting guards odd names.
+        let count = sqlx::query(&format!(
+            "SELECT COUNT(*) FROM \"{}\";",
+            table.replace('"', "\"\"")
+        ))
+        .map(|row: sqlx::sqlite::SqliteRow| row.get(0))
+        .fetch_one(pool)
+        .await?;
+        counts.push((table, count));
+    }
+
+    Ok(counts)
+}
+
+fn diff(before: Vec<(String, i64)>, after: Vec<(String, i64)>) -> Vec<TableChange> {
+    let mut tables = before
+        .iter()
+        .chain(after.iter())
+        .map(|(table, _)| table.clone())
+        .collect::<Vec<_>>();
+    tables.sort();
+    tables.dedup();
+
+    let count_in = |counts: &[(String, i64)], table: &str| {
+        counts
+            .iter()
+            .find(|(name, _)| name == table)
+            .map(|(_, count)| *count)
+    };
+
+    tables
+        .into_iter()
+        .map(|table| TableChange {
+            before: count_in(&before, &table),
+            after: count_in(&after, &table),
+            table,
+        })
+        .collect()
+}
+
+/// Replaces the live database with the snapshot `file_name` from the backup directory.
+/// A safety snapshot of the current state is written first, so a restore can itself be undone.
+pub(crate) async fn restore(
+    db: &Database,
+    backups: &BackupService,
+    file_name: &str,
+) -> Result<RestoreReport, RestoreError> {
+    let file = backups
+        .find(file_name)
+        .await?
+        .ok_or_else(|| RestoreError::NotFound(file_name.to_string()))?;
+    let path = backups.path_of(&file);
+
+    backup::verify(&path).await?;
+    let found = schema_version_of(&path).await?;
+    let latest = migrations::latest_version();
+    if found > latest {
+        return Err(RestoreError::IncompatibleSchema {
+            file_name: file.file_name,
+            found,
+            latest,
+        });
+    }
+
+    // Writers are held off from here until the swap, so the safety snapshot
+    // is exactly the state that gets replaced.
+    let lock = db.lock_writes().await?;
+    let before = row_counts(&db.pool()).await?;
+    let safety_snapshot = backups.safety_snapshot(&db.pool()).await?;
+    tracing::info!(
+        "Restoring {}, previous state saved as {}",
+        file.file_name,
+        safety_snapshot.file_name
+    );
+
+    db.replace_with(&path, lock).await?;
+    // Older snapshots are brought up to the current schema right away
+    migrations::run(&db.pool()).await?;
+    let after = row_counts(&db.pool()).await?;
+
+    Ok(RestoreReport {
+        restored: file,
+        safety_snapshot,
+        schema_version: found,
+        tables: diff(before, after),
+    })
+}
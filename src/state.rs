@@ -0,0 +1,60 @@
+use axum::extract::FromRef;
+
+use crate::{
+    attachments::AttachmentStore, auth::Sessions, backup::BackupService, database::Database,
+    trash::TrashService, undo::UndoHistory,
+};
+
+/// Shared state handed to every router.
+/// Handlers only extract the parts they need, e.g. `State<sqlx::SqlitePool>`.
+#[derive(Clone)]
+pub(crate) struct AppState {
+    pub(crate) db: Database,
+    pub(crate) backups: BackupService,
+    pub(crate) sessions: Sessions,
+    pub(crate) attachments: AttachmentStore,
+    pub(crate) trash: TrashService,
+    pub(crate) undo: UndoHistory,
+}
+
+impl FromRef<AppState> for sqlx::SqlitePool {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.pool()
+    }
+}
+
+impl FromRef<AppState> for Database {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for BackupService {
+    fn from_ref(state: &AppState) -> Self {
+        state.backups.clone()
+    }
+}
+
+impl FromRef<AppState> for Sessions {
+    fn from_ref(state: &AppState) -> Self {
+        state.sessions.clone()
+    }
+}
+
+impl FromRef<AppState> for AttachmentStore {
+    fn from_ref(state: &AppState) -> Self {
+        state.attachments.clone()
+    }
+}
+
+impl FromRef<AppState> for TrashService {
+    fn from_ref(state: &AppState) -> Self {
+        state.trash.clone()
+    }
+}
+
+impl FromRef<AppState> for UndoHistory {
+    fn from_ref(state: &AppState) -> Self {
+        state.undo.clone()
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use crate::{
+    attachments::AttachmentStore, config::Config, data_access::utils::now, database::Database,
+    models,
+};
+
+/// How often expired items are purged from the trash.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Purges deleted todos and tasks once they were in the trash for the retention period.
+#[derive(Clone)]
+pub(crate) struct TrashService {
+    retention_days: Option<u64>,
+}
+
+impl TrashService {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            retention_days: match config.trash_retention_days {
+                0 => None,
+                days => Some(days),
+            },
+        }
+    }
+
+    /// Days items stay in the trash, `None` keeps them until they are purged by hand.
+    pub(crate) fn retention_days(&self) -> Option<u64> {
+        self.retention_days
+    }
+
+    /// Purges everything trashed longer than the retention period, failures are only logged.
+    pub(crate) async fn purge_expired(&self, pool: &sqlx::SqlitePool, store: &AttachmentStore) {
+        let Some(days) = self.retention_days else {
+            return;
+        };
+        // A retention period beyond what the calendar can represent never expires anything
+        let Some(before) = i64::try_from(days)
+            .ok()
+            .and_then(chrono::Duration::try_days)
+            .and_then(|retention| now().checked_sub_signed(retention))
+        else {
+            return;
+        };
+        match models::trash::purge_deleted_before(pool, before).await {
+            Ok(0) => {}
+            Ok(purged) => {
+                tracing::info!("Purged {purged} items from the trash");
+                store.clean_up(pool).await;
+            }
+            Err(e) => tracing::error!("Failed to purge the trash: {e}"),
+        }
+    }
+
+    /// Purges expired items right away and then every [`PURGE_INTERVAL`].
+    pub(crate) fn spawn_schedule(&self, db: Database, store: AttachmentStore) {
+        if self.retention_days.is_none() {
+            tracing::info!("Items stay in the trash until they are purged");
+            return;
+        }
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                service.purge_expired(&db.pool(), &store).await;
+            }
+        });
+    }
+}
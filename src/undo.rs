@@ -0,0 +1,357 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    http::HeaderValue,
+    response::{IntoResponse, Response},
+};
+use rand_core::{OsRng, RngCore};
+
+use crate::{
+    api::types::{audit::AuditEntry, board::Column, time::DateTime},
+    data_access::utils::now,
+    error::ApiError,
+    models,
+};
+
+/// Steps kept per user, older ones can't be undone anymore.
+const HISTORY_LEN: usize = 10;
+
+/// How long a step can be undone.
+const MAX_AGE_MINUTES: i64 = 15;
+
+/// Sent along with every undoable change, its `token` fetches the undo toast.
+pub(crate) const UNDO_EVENT: &str = "undo-available";
+
+/// A change made by a single request, as recorded in the audit log.
+struct UndoStep {
+    token: String,
+    at: DateTime,
+    /// Oldest first
+    entries: Vec<AuditEntry>,
+    /// A board column as it was before, columns are not part of the audit log
+    column: Option<Column>,
+}
+
+impl UndoStep {
+    /// What the step did, as in "Deleted todo Milk", named after its column or first change.
+    fn label(&self) -> String {
+        let label = match (&self.column, self.entries.first()) {
+            (Some(column), _) => format!("updated column {}", column.name),
+            (None, Some(entry)) => format!(
+                "{} {} {}",
+                entry.action.past_tense(),
+                entry.entity.as_str(),
+                entry.name().unwrap_or_default()
+            ),
+            (None, None) => String::new(),
+        };
+        let mut chars = label.trim_end().chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The last few undoable changes of each user, kept in memory only.
+/// A change is everything a user recorded in the audit log during one request,
+/// so concurrent requests of the same user may end up in the same step.
+#[derive(Clone, Default)]
+pub(crate) struct UndoHistory {
+    steps: Arc<Mutex<HashMap<i64, VecDeque<UndoStep>>>>,
+}
+
+/// Drops the steps that are too old to be undone.
+fn expire(history: &mut VecDeque<UndoStep>) {
+    let oldest = now() - chrono::Duration::minutes(MAX_AGE_MINUTES);
+    history.retain(|step| step.at > oldest);
+}
+
+/// 128 random bits, hex encoded.
+fn new_token() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+impl UndoHistory {
+    /// Where the audit log stands before an undoable change, hand it to [`Self::push`] afterwards.
+    pub(crate) async fn mark(&self, pool: &sqlx::SqlitePool) -> Result<i64, sqlx::Error> {
+        models::audit::latest_id(pool).await
+    }
+
+    /// Remembers what `user_id` changed since `mark` as one step and returns its token,
+    /// `None` if nothing changed.
+    pub(crate) async fn push(
+        &self,
+        pool: &sqlx::SqlitePool,
+        user_id: i64,
+        mark: i64,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let entries = models::audit::get_since(pool, user_id, mark).await?;
+        Ok(self.remember(user_id, entries, None))
+    }
+
+    /// Like [`Self::push`] for a change to the board column `before`,
+    /// which goes back along with the tasks the change updated.
+    pub(crate) async fn push_column(
+        &self,
+        pool: &sqlx::SqlitePool,
+        user_id: i64,
+        mark: i64,
+        before: Column,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let entries = models::audit::get_since(pool, user_id, mark).await?;
+        let after = models::board::get_column(pool, user_id, before.id).await?;
+        let column = (after != before).then_some(before);
+        Ok(self.remember(user_id, entries, column))
+    }
+
+    /// Adds a step to the history of `user_id` and returns its token, `None` if it is empty.
+    fn remember(
+        &self,
+        user_id: i64,
+        entries: Vec<AuditEntry>,
+        column: Option<Column>,
+    ) -> Option<String> {
+        if entries.is_empty() && column.is_none() {
+            return None;
+        }
+
+        let token = new_token();
+        let mut steps = self
+            .steps
+            .lock()
+            .expect("undo history lock is never poisoned");
+        let history = steps.entry(user_id).or_default();
+        history.push_back(UndoStep {
+            token: token.clone(),
+            at: now(),
+            entries,
+            column,
+        });
+        if history.len() > HISTORY_LEN {
+            history.pop_front();
+        }
+        Some(token)
+    }
+
+    /// What the step `token` of `user_id` did, `None` if it can't be undone anymore.
+    pub(crate) fn label(&self, user_id: i64, token: &str) -> Option<String> {
+        let mut steps = self
+            .steps
+            .lock()
+            .expect("undo history lock is never poisoned");
+        let history = steps.get_mut(&user_id)?;
+        expire(history);
+        history
+            .iter()
+            .find(|step| step.token == token)
+            .map(UndoStep::label)
+    }
+
+    /// Reverts the step `token` of `user_id` and returns what it did.
+    /// The step is gone afterwards, even if it could not be reverted.
+    pub(crate) async fn undo(
+        &self,
+        pool: &sqlx::SqlitePool,
+        user_id: i64,
+        token: &str,
+    ) -> Result<String, ApiError> {
+        let step = {
+            let mut steps = self
+                .steps
+                .lock()
+                .expect("undo history lock is never poisoned");
+            steps.get_mut(&user_id).and_then(|history| {
+                expire(history);
+                let index = history.iter().position(|step| step.token == token)?;
+                history.remove(index)
+            })
+        };
+        let Some(step) = step else {
+            return Err(ApiError::Validation(
+                "This change can't be undone anymore".to_string(),
+            ));
+        };
+        models::undo::revert(pool, user_id, &step.entries).await?;
+        if let Some(column) = &step.column {
+            models::board::restore_column(pool, user_id, column).await?;
+        }
+        Ok(step.label())
+    }
+}
+
+/// A response to an undoable change, carrying the token of its step in an [`UNDO_EVENT`]
+/// next to the events the response triggers already.
+pub(crate) struct Undoable<T>(pub T, pub Option<String>);
+
+impl<T: IntoResponse> IntoResponse for Undoable<T> {
+    fn into_response(self) -> Response {
+        let Undoable(inner, token) = self;
+        let mut response = inner.into_response();
+        let Some(token) = token else {
+            return response;
+        };
+
+        let headers = response.headers_mut();
+        // Plain `HX-Trigger` values are comma separated event names, json ones carry details
+        let mut events = match headers
+            .get(axum_htmx::HX_TRIGGER)
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(value) => serde_json::from_str(value).unwrap_or_else(|_| {
+                value
+                    .split(',')
+                    .map(|event| (event.trim().to_string(), serde_json::Value::Null))
+                    .collect()
+            }),
+            None => serde_json::Map::new(),
+        };
+        events.insert(
+            UNDO_EVENT.to_string(),
+            serde_json::json!({ "token": token }),
+        );
+        let value = serde_json::Value::Object(events).to_string();
+        headers.insert(
+            axum_htmx::HX_TRIGGER,
+            HeaderValue::from_str(&value).expect("event names and tokens are visible ascii"),
+        );
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::api::types::{
+        board::{MoveTask, UpdateColumn},
+        project::CreateProject,
+        task::CreateTask,
+    };
+
+    /// A fresh in-memory database with one user and a task on the board of a new project.
+    async fn setup() -> (sqlx::SqlitePool, i64, Vec<Column>, i64) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory database opens");
+        crate::migrations::run(&pool)
+            .await
+            .expect("migrations apply to an empty database");
+        let user_id = sqlx::query("INSERT INTO users (name) VALUES ('ann');")
+            .execute(&pool)
+            .await
+            .expect("user is stored")
+            .last_insert_rowid();
+        let project = CreateProject {
+            name: "Home".to_string(),
+        };
+        let Ok(project_id) = models::project::create(&pool, user_id, &project).await else {
+            panic!("project is created");
+        };
+        let columns = models::board::get_columns(&pool, user_id, project_id)
+            .await
+            .expect("columns load");
+        let task = CreateTask {
+            name: "Paint".to_string(),
+            project_id: Some(project_id),
+            priority: Default::default(),
+            due: None,
+            recurrence: None,
+            parent_id: None,
+        };
+        let Ok(task_id) = models::task::create_task(&pool, user_id, &task).await else {
+            panic!("task is created");
+        };
+        (pool, user_id, columns, task_id)
+    }
+
+    /// The column and done state of a task.
+    async fn placement(pool: &sqlx::SqlitePool, task_id: i64) -> (i64, bool) {
+        sqlx::query_as("SELECT column_id, done FROM tasks WHERE id = (?1);")
+            .bind(task_id)
+            .fetch_one(pool)
+            .await
+            .expect("task loads")
+    }
+
+    #[tokio::test]
+    async fn undoing_a_board_move_puts_the_task_back() {
+        let (pool, user_id, columns, task_id) = setup().await;
+        let history = UndoHistory::default();
+        let done = columns.last().expect("board has columns");
+        assert_eq!(placement(&pool, task_id).await, (columns[0].id, false));
+
+        let mark = history.mark(&pool).await.expect("audit log loads");
+        let payload = MoveTask {
+            column_id: done.id,
+            position: 0,
+        };
+        if models::board::move_task(&pool, user_id, task_id, &payload)
+            .await
+            .is_err()
+        {
+            panic!("task moves");
+        }
+        let token = history
+            .push(&pool, user_id, mark)
+            .await
+            .expect("step is kept");
+        assert_eq!(placement(&pool, task_id).await, (done.id, true));
+
+        let Some(token) = token else {
+            panic!("moving a task can be undone");
+        };
+        if history.undo(&pool, user_id, &token).await.is_err() {
+            panic!("move is undone");
+        }
+        assert_eq!(placement(&pool, task_id).await, (columns[0].id, false));
+    }
+
+    #[tokio::test]
+    async fn undoing_a_column_done_change_reverts_the_column_and_its_tasks() {
+        let (pool, user_id, columns, task_id) = setup().await;
+        let history = UndoHistory::default();
+        let backlog = columns[0].clone();
+
+        let mark = history.mark(&pool).await.expect("audit log loads");
+        let payload = UpdateColumn {
+            done: Some(true),
+            ..Default::default()
+        };
+        if models::board::update_column(&pool, user_id, backlog.id, &payload)
+            .await
+            .is_err()
+        {
+            panic!("column is updated");
+        }
+        let token = history
+            .push_column(&pool, user_id, mark, backlog.clone())
+            .await
+            .expect("step is kept");
+        assert_eq!(placement(&pool, task_id).await, (backlog.id, true));
+
+        let Some(token) = token else {
+            panic!("a column change can be undone");
+        };
+        assert_eq!(
+            history.label(user_id, &token).as_deref(),
+            Some("Updated column Backlog")
+        );
+        if history.undo(&pool, user_id, &token).await.is_err() {
+            panic!("column change is undone");
+        }
+        assert_eq!(placement(&pool, task_id).await, (backlog.id, false));
+        let column = models::board::get_column(&pool, user_id, backlog.id)
+            .await
+            .expect("column loads");
+        assert_eq!(column, backlog);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::api::types::user::User;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "account/login.html")]
+pub(crate) struct LoginIndex {
+    pub next: String,
+    pub name: String,
+    pub error: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "account/register.html")]
+pub(crate) struct RegisterIndex {
+    pub name: String,
+    pub email: String,
+    pub error: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "account/index.html")]
+pub(crate) struct AccountIndex {
+    pub user: User,
+    pub sessions: i64,
+    /// Outcome of the last password change, if there was one
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
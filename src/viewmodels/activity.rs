@@ -0,0 +1,31 @@
+use askama::Template;
+
+use crate::api::types::audit::{AuditEntity, AuditEntry};
+
+#[derive(Template)]
+#[template(path = "activity/index.html")]
+pub(crate) struct ActivityIndex {
+    /// Users to filter by, as id and name
+    pub authors: Vec<(i64, String)>,
+}
+
+impl ActivityIndex {
+    fn entities(&self) -> [AuditEntity; 2] {
+        AuditEntity::ALL
+    }
+}
+
+/// A page of the activity, `more` when older entries may follow.
+#[derive(Template)]
+#[template(path = "activity/entries.html")]
+pub(crate) struct ActivityEntries {
+    pub entries: Vec<AuditEntry>,
+    pub more: bool,
+}
+
+impl ActivityEntries {
+    /// Id to page back from.
+    fn oldest(&self) -> Option<i64> {
+        self.entries.last().map(|entry| entry.id)
+    }
+}
@@ -0,0 +1,9 @@
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "api/explorer.html")]
+pub(crate) struct ApiExplorer {
+    pub openapi_path: &'static str,
+    /// The explorer is public, visitors get the navigation of the login page
+    pub signed_in: bool,
+}
@@ -0,0 +1,26 @@
+use crate::{
+    backup::{BackupFile, BackupStatus},
+    restore::RestoreReport,
+};
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "backups/index.html")]
+pub(crate) struct BackupIndex;
+
+#[derive(Template)]
+#[template(path = "backups/backups.html")]
+pub(crate) struct Backups {
+    pub dir: String,
+    pub generations: usize,
+    pub interval_hours: Option<u64>,
+    pub status: BackupStatus,
+    pub files: Vec<BackupFile>,
+    pub can_restore: bool,
+}
+
+#[derive(Template)]
+#[template(path = "backups/restored.html")]
+pub(crate) struct Restored {
+    pub report: RestoreReport,
+}
@@ -0,0 +1,136 @@
+use crate::api::types::{
+    board::{BoardColumn, FlowDay, FlowMetrics},
+    project::Project,
+    task::Priority,
+};
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "board/index.html")]
+pub(crate) struct BoardIndex {
+    pub project: Project,
+}
+
+/// All columns of a project's board, `error` holds the reason the last change was rejected.
+#[derive(Template)]
+#[template(path = "board/board.html")]
+pub(crate) struct Board {
+    pub project: Project,
+    pub columns: Vec<BoardColumn>,
+    pub error: Option<String>,
+}
+
+/// Size of the plot area of the flow charts, in svg units.
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 240.0;
+
+/// Colors of the cumulative flow bands, repeated for boards with more columns.
+const BAND_COLORS: &[&str] = &[
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+/// The area of one column in the cumulative flow diagram, as svg polygon points.
+pub(crate) struct FlowBand {
+    pub name: String,
+    pub color: &'static str,
+    pub points: String,
+}
+
+/// A finished task in the cycle time chart.
+pub(crate) struct FlowPoint {
+    pub x: f64,
+    pub y: f64,
+    pub title: String,
+}
+
+/// Flow metrics of a project's board with the charts drawn as inline svg.
+#[derive(Template)]
+#[template(path = "board/flow.html")]
+pub(crate) struct FlowReport {
+    pub project: Project,
+    pub metrics: FlowMetrics,
+}
+
+impl FlowReport {
+    const WIDTH: f64 = CHART_WIDTH;
+    const HEIGHT: f64 = CHART_HEIGHT;
+
+    /// Most tasks on the board on any day, the top of the flow diagram.
+    fn peak(&self) -> i64 {
+        self.metrics
+            .days
+            .iter()
+            .map(|day| day.counts.iter().sum::<i64>())
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    /// One band per column, stacked with the last column at the bottom.
+    fn bands(&self) -> Vec<FlowBand> {
+        // A single day is drawn across the whole width
+        let days: Vec<&FlowDay> = match self.metrics.days.as_slice() {
+            [day] => vec![day, day],
+            days => days.iter().collect(),
+        };
+        let step = CHART_WIDTH / (days.len().max(2) - 1) as f64;
+        let scale = CHART_HEIGHT / self.peak() as f64;
+        let mut below = vec![0; days.len()];
+
+        let mut bands: Vec<FlowBand> = Vec::new();
+        for (index, column) in self.metrics.columns.iter().enumerate().rev() {
+            let mut upper = Vec::new();
+            let mut lower = Vec::new();
+            for (day_index, day) in days.iter().enumerate() {
+                let x = step * day_index as f64;
+                let base = below[day_index];
+                let top = base + day.counts.get(index).copied().unwrap_or(0);
+                lower.push(format!("{x:.1},{:.1}", CHART_HEIGHT - base as f64 * scale));
+                upper.push(format!("{x:.1},{:.1}", CHART_HEIGHT - top as f64 * scale));
+                below[day_index] = top;
+            }
+            lower.reverse();
+            upper.extend(lower);
+            bands.push(FlowBand {
+                name: column.name.clone(),
+                color: BAND_COLORS[index % BAND_COLORS.len()],
+                points: upper.join(" "),
+            });
+        }
+        // Legend in board order
+        bands.reverse();
+        bands
+    }
+
+    /// Longest cycle time in days, the top of the cycle time chart.
+    fn max_cycle_days(&self) -> f64 {
+        self.metrics
+            .finished
+            .iter()
+            .map(|task| task.cycle_time / 24.0)
+            .fold(1.0, f64::max)
+    }
+
+    /// Finished tasks placed by the day they finished and their cycle time.
+    fn cycle_points(&self) -> Vec<FlowPoint> {
+        let start = self.metrics.from.and_time(chrono::NaiveTime::MIN).and_utc();
+        let end = (self.metrics.to + chrono::Days::new(1))
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc();
+        let range = (end - start).num_seconds() as f64;
+        let max_days = self.max_cycle_days();
+
+        self.metrics
+            .finished
+            .iter()
+            .map(|task| {
+                let days = task.cycle_time / 24.0;
+                FlowPoint {
+                    x: (task.finished - start).num_seconds() as f64 / range * CHART_WIDTH,
+                    y: CHART_HEIGHT - days / max_days * CHART_HEIGHT,
+                    title: format!("{}: {days:.1} days", task.name),
+                }
+            })
+            .collect()
+    }
+}
@@ -1,8 +1,39 @@
+pub mod account;
+pub mod activity;
+pub mod api;
+pub mod backups;
+pub mod board;
+pub mod projects;
+pub mod tags;
 pub mod tasks;
+pub mod timesheet;
 pub mod todos;
+pub mod trash;
+pub mod undo;
 
-use askama::Template;
+/// Filters shared by all templates.
+pub(crate) mod filters {
+    /// Anything askama hands to a filter in place of a number of seconds.
+    pub trait Seconds {
+        fn seconds(&self) -> i64;
+    }
 
-#[derive(Template)]
-#[template(path = "login.html")]
-pub(crate) struct LoginIndex;
+    impl Seconds for i64 {
+        fn seconds(&self) -> i64 {
+            *self
+        }
+    }
+
+    impl<T: Seconds> Seconds for &T {
+        fn seconds(&self) -> i64 {
+            (*self).seconds()
+        }
+    }
+
+    /// Tracked seconds as hours and minutes.
+    pub fn duration(seconds: impl Seconds) -> askama::Result<String> {
+        Ok(crate::api::types::tracking::format_duration(
+            seconds.seconds(),
+        ))
+    }
+}
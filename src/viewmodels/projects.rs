@@ -0,0 +1,31 @@
+use crate::api::types::project::Project;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "projects/index.html")]
+pub(crate) struct ProjectIndex;
+
+#[derive(Template)]
+#[template(path = "projects/projects.html")]
+pub(crate) struct Projects {
+    pub projects: Vec<Project>,
+}
+
+#[derive(Template)]
+#[template(path = "projects/project.html")]
+pub(crate) struct ProjectModel {
+    pub project: Project,
+}
+
+#[derive(Template)]
+#[template(path = "projects/edit.html")]
+pub(crate) struct ProjectEdit {
+    pub project: Project,
+}
+
+/// Project links in the navigation, only the ones that are not archived.
+#[derive(Template)]
+#[template(path = "projects/switcher.html")]
+pub(crate) struct ProjectSwitcher {
+    pub projects: Vec<Project>,
+}
@@ -0,0 +1,39 @@
+use crate::api::types::tag::Tag;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "tags/index.html")]
+pub(crate) struct TagIndex;
+
+#[derive(Template)]
+#[template(path = "tags/tags.html")]
+pub(crate) struct Tags {
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Template)]
+#[template(path = "tags/tag.html")]
+pub(crate) struct TagModel {
+    pub tag: Tag,
+}
+
+#[derive(Template)]
+#[template(path = "tags/edit.html")]
+pub(crate) struct TagEdit {
+    pub tag: Tag,
+}
+
+pub(crate) struct PickerTag {
+    pub tag: Tag,
+    pub assigned: bool,
+}
+
+/// All tags as toggles for a single todo or task.
+#[derive(Template)]
+#[template(path = "tags/picker.html")]
+pub(crate) struct TagPicker {
+    /// Route prefix of the owner, e.g. `/todos`
+    pub prefix: &'static str,
+    pub owner_id: i64,
+    pub tags: Vec<PickerTag>,
+}
@@ -1,9 +1,34 @@
-use crate::api::types::task::{TaskHeader, TaskModel};
+use crate::api::types::{
+    attachment::Attachment,
+    audit::AuditEntry,
+    comment::Comment,
+    project::Project,
+    recurrence::{Recurrence, Weekday},
+    tag::Tag,
+    task::{Priority, SortBy, TaskHeader, TaskModel, TaskShare},
+    todo::TodoItem,
+    tracking::TimeEntry,
+};
 use askama::Template;
 
+use super::filters;
+
+/// All tasks, or the ones of a single `project`.
 #[derive(Template)]
 #[template(path = "tasks/index.html")]
-pub(crate) struct TaskIndex;
+pub(crate) struct TaskIndex {
+    pub tags: Vec<Tag>,
+    pub project: Option<Project>,
+}
+
+impl TaskIndex {
+    fn list_url(&self) -> String {
+        match &self.project {
+            Some(project) => format!("/tasks/all?project={}", project.id),
+            None => "/tasks/all".to_string(),
+        }
+    }
+}
 
 #[derive(Template)]
 #[template(path = "tasks/tasks.html")]
@@ -11,10 +36,100 @@ pub(crate) struct Tasks {
     pub tasks: Vec<TaskHeader>,
 }
 
+impl Tasks {
+    /// The tasks in list order with their depth, each one followed by its subtasks.
+    /// Subtasks whose parent is not in the list show up at the top level.
+    fn tree(&self) -> Vec<(usize, &TaskHeader)> {
+        let listed = |id: Option<i64>| id.is_some_and(|id| self.tasks.iter().any(|t| t.id == id));
+        let mut tree = Vec::with_capacity(self.tasks.len());
+        let mut stack: Vec<(usize, &TaskHeader)> = self
+            .tasks
+            .iter()
+            .rev()
+            .filter(|task| !listed(task.parent_id))
+            .map(|task| (0, task))
+            .collect();
+        while let Some((depth, task)) = stack.pop() {
+            tree.push((depth, task));
+            stack.extend(
+                self.tasks
+                    .iter()
+                    .rev()
+                    .filter(|child| child.parent_id == Some(task.id))
+                    .map(|child| (depth + 1, child)),
+            );
+        }
+        tree
+    }
+}
+
+#[derive(Template)]
+#[template(path = "tasks/task.html")]
+pub(crate) struct TaskHeaderModel {
+    pub task_header: TaskHeader,
+    /// How far the row is indented below its parent
+    pub depth: usize,
+}
+
 #[derive(Template)]
 #[template(path = "tasks/edit.html")]
 pub(crate) struct TaskEdit {
     pub task: TaskModel,
+    /// Projects the task can be moved to, only filled for the owner
+    pub projects: Vec<Project>,
+    /// Tasks it can be moved below, only filled for the owner
+    pub parents: Vec<TaskHeader>,
+}
+
+impl TaskEdit {
+    fn in_project(&self, project: &Project) -> bool {
+        self.task.project_id == Some(project.id)
+    }
+
+    fn is_parent(&self, task: &TaskHeader) -> bool {
+        self.task.parent_id == Some(task.id)
+    }
+
+    /// Whether `value` of the `repeat` select is selected, the empty one for tasks that don't recur.
+    fn repeats(&self, value: &str) -> bool {
+        let selected = match &self.task.recurrence {
+            None => "",
+            Some(Recurrence::Daily) => "daily",
+            Some(Recurrence::Weekly { .. }) => "weekly",
+            Some(Recurrence::Monthly { .. }) => "monthly",
+            Some(Recurrence::AfterCompletion { .. }) => "after_completion",
+            Some(Recurrence::Rule { .. }) => "rule",
+        };
+        selected == value
+    }
+
+    fn repeats_on(&self, weekday: &Weekday) -> bool {
+        match &self.task.recurrence {
+            Some(Recurrence::Weekly { weekdays }) => weekdays.contains(weekday),
+            _ => false,
+        }
+    }
+
+    fn repeat_day(&self) -> String {
+        match &self.task.recurrence {
+            Some(Recurrence::Monthly { day }) => day.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn repeat_days(&self) -> String {
+        match &self.task.recurrence {
+            Some(Recurrence::AfterCompletion { days }) => days.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn repeat_rule(&self) -> &str {
+        match &self.task.recurrence {
+            Some(Recurrence::Rule { rrule }) => rrule,
+            _ => "",
+        }
+    }
 }
 
 #[derive(Template)]
@@ -22,3 +137,140 @@ pub(crate) struct TaskEdit {
 pub(crate) struct TaskDetails {
     pub task: TaskModel,
 }
+
+/// Linked todos with controls to attach, create and detach, used in the edit dialog.
+#[derive(Template)]
+#[template(path = "tasks/todos.html")]
+pub(crate) struct TaskTodos {
+    pub task_id: i64,
+    pub todos: Vec<TodoItem>,
+}
+
+/// Read only list of linked todos, used in the details dialog.
+#[derive(Template)]
+#[template(path = "tasks/todo_list.html")]
+pub(crate) struct TaskTodoList {
+    pub task_id: i64,
+    pub todos: Vec<TodoItem>,
+}
+
+#[derive(Template)]
+#[template(path = "tasks/todo_search.html")]
+pub(crate) struct TaskTodoSearch {
+    pub task_id: i64,
+    pub todos: Vec<TodoItem>,
+}
+
+/// Subtasks with their progress, used in the details dialog.
+/// Only the owner can add subtasks.
+#[derive(Template)]
+#[template(path = "tasks/subtasks.html")]
+pub(crate) struct TaskSubtasks {
+    pub task_id: i64,
+    pub subtasks: Vec<TaskHeader>,
+    pub can_add: bool,
+    pub error: Option<String>,
+}
+
+impl TaskSubtasks {
+    fn done(&self) -> usize {
+        self.subtasks.iter().filter(|task| task.done).count()
+    }
+}
+
+/// Tasks blocking a task and the ones waiting for it, used in the details dialog.
+/// `candidates` can be added as blockers, only filled for users who can write.
+#[derive(Template)]
+#[template(path = "tasks/dependencies.html")]
+pub(crate) struct TaskDependencies {
+    pub task_id: i64,
+    pub blockers: Vec<TaskHeader>,
+    pub dependents: Vec<TaskHeader>,
+    pub candidates: Vec<TaskHeader>,
+    pub can_write: bool,
+    pub error: Option<String>,
+}
+
+/// Time tracked on a task, with the timer and the form for manual entries.
+/// `running` is the timer `user_id` runs, which can be on another task.
+#[derive(Template)]
+#[template(path = "tasks/time.html")]
+pub(crate) struct TaskTime {
+    pub task_id: i64,
+    pub user_id: i64,
+    pub entries: Vec<TimeEntry>,
+    pub running: Option<TimeEntry>,
+    pub can_track: bool,
+    pub today: String,
+    pub error: Option<String>,
+}
+
+impl TaskTime {
+    fn total(&self) -> i64 {
+        self.entries.iter().map(|entry| entry.seconds).sum()
+    }
+
+    fn is_running_here(&self) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|running| running.task_id == self.task_id)
+    }
+
+    fn is_own(&self, entry: &TimeEntry) -> bool {
+        entry.user_id == self.user_id
+    }
+}
+
+/// Files attached to a task, with the upload form for users who can write.
+#[derive(Template)]
+#[template(path = "tasks/attachments.html")]
+pub(crate) struct TaskAttachments {
+    pub task_id: i64,
+    pub attachments: Vec<Attachment>,
+    pub can_write: bool,
+    pub max_mb: u64,
+    pub error: Option<String>,
+}
+
+/// The discussion of a task, `editing` is the comment shown as edit form.
+#[derive(Template)]
+#[template(path = "tasks/comments.html")]
+pub(crate) struct TaskComments {
+    pub task_id: i64,
+    pub user_id: i64,
+    pub comments: Vec<Comment>,
+    pub is_owner: bool,
+    pub editing: Option<i64>,
+    pub error: Option<String>,
+}
+
+impl TaskComments {
+    fn is_own(&self, comment: &Comment) -> bool {
+        comment.user_id == self.user_id
+    }
+
+    fn can_delete(&self, comment: &Comment) -> bool {
+        self.is_own(comment) || self.is_owner
+    }
+
+    fn is_editing(&self, comment: &Comment) -> bool {
+        self.editing == Some(comment.id)
+    }
+}
+
+/// Changes made to a task and its todos, newest first.
+#[derive(Template)]
+#[template(path = "tasks/history.html")]
+pub(crate) struct TaskHistory {
+    pub task_id: i64,
+    pub entries: Vec<AuditEntry>,
+}
+
+/// The people a task is shared with, only shown to its owner.
+#[derive(Template)]
+#[template(path = "tasks/shares.html")]
+pub(crate) struct TaskShares {
+    pub task_id: i64,
+    pub shares: Vec<TaskShare>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,46 @@
+use crate::api::types::tracking::{format_duration, Timesheet, TimesheetRow};
+use askama::Template;
+use chrono::Days;
+
+use super::filters;
+
+/// The time the current user tracked in one week.
+#[derive(Template)]
+#[template(path = "timesheet/index.html")]
+pub(crate) struct TimesheetIndex {
+    pub timesheet: Timesheet,
+}
+
+impl TimesheetIndex {
+    fn previous_week(&self) -> String {
+        (self.timesheet.week - Days::new(7))
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+
+    fn next_week(&self) -> String {
+        (self.timesheet.week + Days::new(7))
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+
+    fn week(&self) -> String {
+        self.timesheet.week.format("%Y-%m-%d").to_string()
+    }
+
+    /// Days without tracked time stay empty.
+    fn cell(&self, seconds: &i64) -> String {
+        match *seconds {
+            0 => String::new(),
+            seconds => format_duration(seconds),
+        }
+    }
+
+    /// Task and tag tables, in this order.
+    fn sections(&self) -> [(&'static str, &Vec<TimesheetRow>); 2] {
+        [
+            ("Task", &self.timesheet.tasks),
+            ("Tag", &self.timesheet.tags),
+        ]
+    }
+}
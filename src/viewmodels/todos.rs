@@ -1,4 +1,8 @@
-use crate::api::types::todo::TodoItem;
+use crate::api::types::{
+    tag::Tag,
+    task::{Priority, SortBy},
+    todo::TodoItem,
+};
 use askama::Template;
 
 #[derive(Template)]
@@ -20,8 +24,20 @@ pub(crate) struct TodoListModel {
     pub todos: Vec<TodoItem>,
 }
 
+impl TodoListModel {
+    /// The done state the list is narrowed to, kept when the list is sorted.
+    fn done(&self) -> Option<bool> {
+        match self.filter.as_str() {
+            "active" => Some(false),
+            "completed" => Some(true),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Template)]
 #[template(path = "todos/index.html")]
 pub(crate) struct TodoIndex {
     pub filter: String,
+    pub tags: Vec<Tag>,
 }
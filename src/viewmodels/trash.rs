@@ -0,0 +1,23 @@
+use askama::Template;
+
+use crate::api::types::{time::DateTime, trash::TrashItem};
+
+#[derive(Template)]
+#[template(path = "trash/index.html")]
+pub(crate) struct TrashIndex;
+
+#[derive(Template)]
+#[template(path = "trash/items.html")]
+pub(crate) struct TrashItems {
+    pub items: Vec<TrashItem>,
+    /// Days items stay in the trash, `None` keeps them until they are purged by hand
+    pub retention_days: Option<u64>,
+}
+
+impl TrashItems {
+    /// When an item is purged automatically.
+    fn purged_at(&self, item: &TrashItem) -> Option<DateTime> {
+        self.retention_days
+            .map(|days| item.deleted_at + chrono::Duration::days(days as i64))
+    }
+}
@@ -0,0 +1,10 @@
+use askama::Template;
+
+/// Offers to undo a step, given as token and label, or tells how undoing it went.
+/// Fades out after a few seconds.
+#[derive(Template)]
+#[template(path = "undo/toast.html")]
+pub(crate) struct UndoToast {
+    pub step: Option<(String, String)>,
+    pub message: Option<String>,
+}